@@ -0,0 +1,125 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+use serde_json::{Map, Value};
+
+/// Reports generated by the vanilla server, see `data/README.md`.
+const BLOCKS: &str = "data/blocks.json";
+const REGISTRIES: &str = "data/registries.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed={BLOCKS}");
+    println!("cargo:rerun-if-changed={REGISTRIES}");
+
+    let blocks = read(BLOCKS);
+    let registries = read(REGISTRIES);
+
+    let mut code = String::from("// Generated by build.rs from the reports in data/, don't edit.\n\n");
+    write_blocks(&mut code, &blocks);
+    for (registry, statics, module) in [
+        ("minecraft:item", "ITEMS", "items"),
+        ("minecraft:entity_type", "ENTITY_TYPES", "entity_types"),
+        ("minecraft:block_entity_type", "BLOCK_ENTITY_TYPES", "block_entity_types"),
+        ("minecraft:worldgen/biome", "BIOMES", "biomes"),
+        ("minecraft:dimension_type", "DIMENSION_TYPES", "dimension_types"),
+    ] {
+        let entries = registries[registry]["entries"].as_object().unwrap_or_else(|| panic!("{REGISTRIES} has no {registry} registry"));
+        write_registry(&mut code, entries, statics, module);
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("registry.rs");
+    fs::write(out, code).unwrap();
+}
+
+fn read(path: &str) -> Map<String, Value> {
+    let data = fs::read_to_string(path).unwrap_or_else(|e| panic!("Couldn't read {path}: {e}"));
+    match serde_json::from_str(&data) {
+        Ok(Value::Object(object)) => object,
+        _ => panic!("{path} isn't a JSON object"),
+    }
+}
+
+/// `minecraft:oak_log` becomes `OAK_LOG`.
+fn constant(name: &str) -> String {
+    let path = name.strip_prefix("minecraft:").unwrap_or(name);
+    path.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect()
+}
+
+fn string(value: &Value) -> &str {
+    value.as_str().unwrap_or_else(|| panic!("Expected a string, got {value}"))
+}
+
+fn properties(state: &Value) -> String {
+    let pairs: Vec<String> = state["properties"].as_object().into_iter().flatten()
+        .map(|(key, value)| format!("({key:?}, {:?})", string(value)))
+        .collect();
+    format!("&[{}]", pairs.join(", "))
+}
+
+fn write_blocks(code: &mut String, blocks: &Map<String, Value>) {
+    let mut index = Vec::new();
+    let mut defaults = Vec::new();
+
+    let mut sorted: Vec<(&String, &Value)> = blocks.iter().collect();
+    sorted.sort_by_key(|(name, _)| *name);
+
+    code.push_str("/// Every block, sorted by name.\nstatic BLOCKS: &[Block] = &[\n");
+    for (block, (name, data)) in sorted.into_iter().enumerate() {
+        let states = data["states"].as_array().unwrap_or_else(|| panic!("{name} has no states"));
+        let default = states.iter().find(|state| state["default"] == true).unwrap_or_else(|| panic!("{name} has no default state"));
+        let default = default["id"].as_u64().unwrap();
+        defaults.push((name, default));
+
+        let definitions: Vec<String> = data["properties"].as_object().into_iter().flatten()
+            .map(|(key, values)| {
+                let values: Vec<String> = values.as_array().into_iter().flatten().map(|value| format!("{:?}", string(value))).collect();
+                format!("({key:?}, &[{}])", values.join(", "))
+            })
+            .collect();
+        let _ = write!(code, "    Block {{ name: {name:?}, properties: &[{}], default_state: {default}, states: &[", definitions.join(", "));
+        for (position, state) in states.iter().enumerate() {
+            let id = state["id"].as_u64().unwrap_or_else(|| panic!("{name} has a state without an ID"));
+            index.push((id, block, position));
+            let _ = write!(code, "BlockState {{ id: {id}, properties: {} }}, ", properties(state));
+        }
+        code.push_str("] },\n");
+    }
+    code.push_str("];\n\n");
+
+    index.sort();
+    code.push_str("/// Block and position in its states of every state, sorted by state ID.\nstatic STATES: &[(u32, u16, u16)] = &[\n");
+    for (id, block, position) in index {
+        let _ = writeln!(code, "    ({id}, {block}, {position}),");
+    }
+    code.push_str("];\n\n");
+
+    code.push_str("/// The default state of every block.\npub mod blocks {\n");
+    for (name, default) in defaults {
+        let _ = writeln!(code, "    pub const {}: u32 = {default};", constant(name));
+    }
+    code.push_str("}\n\n");
+}
+
+fn write_registry(code: &mut String, entries: &Map<String, Value>, statics: &str, module: &str) {
+    let mut by_id: Vec<(i64, &str)> = entries.iter()
+        .map(|(name, entry)| (entry["protocol_id"].as_i64().unwrap_or_else(|| panic!("{name} has no protocol ID")), name.as_str()))
+        .collect();
+    let mut by_name = by_id.clone();
+    by_name.sort_by_key(|(_, name)| *name);
+    by_id.sort();
+
+    let _ = writeln!(code, "static {statics}: Registry = Registry {{\n    by_name: &[");
+    for (id, name) in by_name {
+        let _ = writeln!(code, "        ({name:?}, {id}),");
+    }
+    code.push_str("    ],\n    by_id: &[\n");
+    for (id, name) in &by_id {
+        let _ = writeln!(code, "        ({id}, {name:?}),");
+    }
+    code.push_str("    ],\n};\n\n");
+
+    let _ = writeln!(code, "pub mod {module} {{");
+    for (id, name) in by_id {
+        let _ = writeln!(code, "    pub const {}: i32 = {id};", constant(name));
+    }
+    code.push_str("}\n\n");
+}
\ No newline at end of file
@@ -0,0 +1,215 @@
+//! The C ABI shared between the server and native plugins.
+//!
+//! A plugin is a `cdylib` exporting [`ABI_VERSION_SYMBOL`], [`INFO_SYMBOL`], [`LOAD_SYMBOL`] and [`UNLOAD_SYMBOL`],
+//! most easily with [`declare_plugin!`]. Everything crossing the boundary is `#[repr(C)]`, so plugins don't have to be
+//! built with the same compiler as the server.
+
+use std::{ffi::c_void, marker::PhantomData, slice, str};
+
+/// Bumped whenever anything in this crate changes in an incompatible way.
+pub const ABI_VERSION: u32 = 1;
+
+pub const ABI_VERSION_SYMBOL: &[u8] = b"RUSTCRAFT_PLUGIN_ABI_VERSION\0";
+pub const INFO_SYMBOL: &[u8] = b"rustcraft_plugin_info\0";
+pub const LOAD_SYMBOL: &[u8] = b"rustcraft_plugin_load\0";
+pub const UNLOAD_SYMBOL: &[u8] = b"rustcraft_plugin_unload\0";
+
+pub type InfoFn = extern "C" fn() -> PluginInfo;
+/// Returns `false` if the plugin failed to load. The API stays valid until the plugin is unloaded.
+pub type LoadFn = extern "C" fn(api: *const PluginApi) -> bool;
+pub type UnloadFn = extern "C" fn();
+
+/// A borrowed UTF-8 string.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct StrRef<'a> {
+    pub ptr: *const u8,
+    pub len: usize,
+    _lifetime: PhantomData<&'a str>,
+}
+
+impl<'a> StrRef<'a> {
+    pub fn new(value: &'a str) -> Self {
+        Self { ptr: value.as_ptr(), len: value.len(), _lifetime: PhantomData }
+    }
+
+    /// # Safety
+    /// `ptr` and `len` have to describe valid UTF-8, as they do when made with `StrRef::new`.
+    pub unsafe fn as_str(&self) -> &'a str {
+        if self.ptr.is_null() { return ""; }
+        str::from_utf8_unchecked(slice::from_raw_parts(self.ptr, self.len))
+    }
+}
+
+impl<'a> From<&'a str> for StrRef<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::new(value)
+    }
+}
+
+#[repr(C)]
+pub struct PluginInfo {
+    pub name: StrRef<'static>,
+    pub version: StrRef<'static>,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Verbose,
+    Debug,
+}
+
+/// Same order as the server's event priorities: lowest runs first, monitor last.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPriority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    Highest,
+    Monitor,
+}
+
+/// The events plugins can listen to. What `message` and `cancelled` mean depends on the kind.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// `message` is the MOTD. Cancelling sends no response.
+    StatusPing,
+    /// `message` is the kick message, if any. Cancelling disallows the login.
+    PlayerLogin,
+    /// `message` is the join message, if any.
+    PlayerJoin,
+    /// `message` is the quit message, if any.
+    PlayerQuit,
+    /// `message` is the chat message. Cancelling keeps it from being broadcast.
+    PlayerChat,
+}
+
+/// An event handed to a plugin's handler. Change the message through `PluginApi::set_event_message`.
+#[repr(C)]
+pub struct EventData<'a> {
+    pub kind: EventKind,
+    /// Empty for status pings.
+    pub player_name: StrRef<'a>,
+    pub player_uuid: [u8; 16],
+    pub message: StrRef<'a>,
+    pub has_message: bool,
+    pub cancelled: bool,
+}
+
+pub type TaskCallback = extern "C" fn(user_data: *mut c_void);
+pub type EventCallback = extern "C" fn(user_data: *mut c_void, event: *mut EventData);
+/// `source` is what replies go to, see `PluginApi::reply`. `arguments` is everything after the command name.
+pub type CommandCallback = extern "C" fn(user_data: *mut c_void, source: *const c_void, sender: StrRef, arguments: StrRef);
+
+/// What the server offers to plugins. `host` has to be passed back with every call that takes it.
+#[repr(C)]
+pub struct PluginApi {
+    pub abi_version: u32,
+    pub host: *const c_void,
+    pub log: extern "C" fn(host: *const c_void, level: LogLevel, message: StrRef),
+    /// Only works while the plugin is loading. Returns `false` if there's already a command with that name.
+    pub register_command: extern "C" fn(host: *const c_void, name: StrRef, description: StrRef, op_level: u8, callback: CommandCallback, user_data: *mut c_void) -> bool,
+    pub reply: extern "C" fn(source: *const c_void, message: StrRef),
+    pub register_event_handler: extern "C" fn(host: *const c_void, kind: EventKind, priority: EventPriority, callback: EventCallback, user_data: *mut c_void),
+    /// Replaces the event's message, or clears it for an empty string.
+    pub set_event_message: extern "C" fn(event: *mut EventData, message: StrRef),
+    /// Returns an ID for `cancel_task`.
+    pub run_later: extern "C" fn(host: *const c_void, delay: u64, callback: TaskCallback, user_data: *mut c_void) -> u64,
+    pub run_repeating: extern "C" fn(host: *const c_void, delay: u64, period: u64, callback: TaskCallback, user_data: *mut c_void) -> u64,
+    pub cancel_task: extern "C" fn(host: *const c_void, task: u64),
+    pub broadcast: extern "C" fn(host: *const c_void, message: StrRef),
+}
+
+// The server's side of every function can be called from any thread
+unsafe impl Send for PluginApi {}
+unsafe impl Sync for PluginApi {}
+
+impl PluginApi {
+    pub fn log(&self, level: LogLevel, message: &str) {
+        (self.log)(self.host, level, message.into());
+    }
+
+    pub fn register_command(&self, name: &str, description: &str, op_level: u8, callback: CommandCallback, user_data: *mut c_void) -> bool {
+        (self.register_command)(self.host, name.into(), description.into(), op_level, callback, user_data)
+    }
+
+    pub fn reply(&self, source: *const c_void, message: &str) {
+        (self.reply)(source, message.into());
+    }
+
+    pub fn register_event_handler(&self, kind: EventKind, priority: EventPriority, callback: EventCallback, user_data: *mut c_void) {
+        (self.register_event_handler)(self.host, kind, priority, callback, user_data);
+    }
+
+    pub fn set_event_message(&self, event: *mut EventData, message: &str) {
+        (self.set_event_message)(event, message.into());
+    }
+
+    pub fn run_later(&self, delay: u64, callback: TaskCallback, user_data: *mut c_void) -> u64 {
+        (self.run_later)(self.host, delay, callback, user_data)
+    }
+
+    pub fn run_repeating(&self, delay: u64, period: u64, callback: TaskCallback, user_data: *mut c_void) -> u64 {
+        (self.run_repeating)(self.host, delay, period, callback, user_data)
+    }
+
+    pub fn cancel_task(&self, task: u64) {
+        (self.cancel_task)(self.host, task);
+    }
+
+    pub fn broadcast(&self, message: &str) {
+        (self.broadcast)(self.host, message.into());
+    }
+}
+
+/// Exports the symbols the server looks for. `$load` is a `fn(&'static PluginApi) -> bool`, `$unload` a `fn()`.
+#[macro_export]
+macro_rules! declare_plugin {
+    ($name:expr, $version:expr, $load:path, $unload:path) => {
+        #[no_mangle]
+        pub static RUSTCRAFT_PLUGIN_ABI_VERSION: u32 = $crate::ABI_VERSION;
+
+        #[no_mangle]
+        pub extern "C" fn rustcraft_plugin_info() -> $crate::PluginInfo {
+            $crate::PluginInfo { name: $crate::StrRef::new($name), version: $crate::StrRef::new($version) }
+        }
+
+        #[no_mangle]
+        #[allow(clippy::not_unsafe_ptr_arg_deref)]
+        pub extern "C" fn rustcraft_plugin_load(api: *const $crate::PluginApi) -> bool {
+            // The server keeps the API alive until `rustcraft_plugin_unload` returns
+            let api: Option<&'static $crate::PluginApi> = unsafe { api.as_ref() };
+            match api {
+                Some(api) if api.abi_version == $crate::ABI_VERSION => $load(api),
+                _ => false,
+            }
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rustcraft_plugin_unload() {
+            $unload();
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_ref() {
+        let value = String::from("Hello, plugins");
+        let string = StrRef::new(&value);
+        assert_eq!(unsafe { string.as_str() }, "Hello, plugins");
+
+        let empty = StrRef { ptr: std::ptr::null(), len: 0, _lifetime: PhantomData };
+        assert_eq!(unsafe { empty.as_str() }, "");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,51 @@
+//! A plugin showing off the native plugin API: a command, an event handler and a scheduled task.
+//! Build it with `cargo build -p sample_plugin` and copy the library into the server's `plugins/` directory.
+
+use std::{ffi::c_void, ptr, sync::OnceLock};
+
+use rustcraft_plugin_api::{declare_plugin, EventData, EventKind, EventPriority, LogLevel, PluginApi, StrRef};
+
+static API: OnceLock<&'static PluginApi> = OnceLock::new();
+
+declare_plugin!("SamplePlugin", "0.1.0", load, unload);
+
+fn load(api: &'static PluginApi) -> bool {
+    let _ = API.set(api);
+
+    api.register_command("hello", "Says hello from the sample plugin", 0, hello, ptr::null_mut());
+    api.register_event_handler(EventKind::PlayerJoin, EventPriority::Normal, on_join, ptr::null_mut());
+    api.run_later(20, announce, ptr::null_mut());
+
+    api.log(LogLevel::Info, "Sample plugin loaded");
+    true
+}
+
+fn unload() {
+    if let Some(api) = API.get() {
+        api.log(LogLevel::Info, "Sample plugin unloaded");
+    }
+}
+
+extern "C" fn hello(_: *mut c_void, source: *const c_void, sender: StrRef, arguments: StrRef) {
+    let Some(api) = API.get() else { return; };
+    let (sender, arguments) = unsafe { (sender.as_str(), arguments.as_str()) };
+
+    match arguments {
+        "" => api.reply(source, &format!("Hello, {}!", sender)),
+        arguments => api.reply(source, &format!("Hello, {}! You said: {}", sender, arguments)),
+    }
+}
+
+extern "C" fn on_join(_: *mut c_void, event: *mut EventData) {
+    let Some(api) = API.get() else { return; };
+    let Some(data) = (unsafe { event.as_ref() }) else { return; };
+
+    let name = unsafe { data.player_name.as_str() };
+    api.set_event_message(event, &format!("{} joined the game, say hello!", name));
+}
+
+extern "C" fn announce(_: *mut c_void) {
+    if let Some(api) = API.get() {
+        api.log(LogLevel::Info, "Sample plugin has been running for a second");
+    }
+}
\ No newline at end of file
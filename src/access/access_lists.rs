@@ -0,0 +1,271 @@
+use std::{net::IpAddr, path::Path, sync::Mutex};
+
+use chrono::Local;
+use uuid::Uuid;
+
+use super::entries::{ban_message, format_date, is_expired, IpBanEntry, PlayerBanEntry, WhitelistEntry, FOREVER, IP_BAN_HEADER, PLAYER_BAN_HEADER};
+use super::list_file::ListFile;
+
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// Whitelist, player bans and IP bans, stored in the same files vanilla uses.
+pub struct AccessLists {
+    whitelist: Mutex<ListFile<WhitelistEntry>>,
+    banned_players: Mutex<ListFile<PlayerBanEntry>>,
+    banned_ips: Mutex<ListFile<IpBanEntry>>,
+}
+
+impl AccessLists {
+    pub fn load(directory: &Path) -> Self {
+        Self {
+            whitelist: Mutex::new(ListFile::load(&directory.join(WHITELIST_FILE))),
+            banned_players: Mutex::new(ListFile::load(&directory.join(BANNED_PLAYERS_FILE))),
+            banned_ips: Mutex::new(ListFile::load(&directory.join(BANNED_IPS_FILE))),
+        }
+    }
+
+    pub fn reload(&self) -> Result<(), String> {
+        self.whitelist.lock().unwrap().reload()?;
+        self.banned_players.lock().unwrap().reload()?;
+        self.banned_ips.lock().unwrap().reload()
+    }
+
+    /// Returns the disconnect message if `ip` is banned.
+    pub fn check_ip(&self, ip: IpAddr) -> Result<(), String> {
+        let mut banned_ips = self.banned_ips.lock().unwrap();
+        banned_ips.reload_if_changed();
+        banned_ips.remove_where(|entry| is_expired(&entry.expires, Local::now()));
+
+        let ip = ip.to_string();
+        match banned_ips.entries().iter().find(|entry| entry.ip == ip) {
+            Some(entry) => Err(ban_message(IP_BAN_HEADER, &entry.reason, &entry.expires)),
+            None => Ok(())
+        }
+    }
+
+    /// Returns the disconnect message if the player is banned or isn't whitelisted (when `whitelist_enabled`).
+    pub fn check_player(&self, uuid: Uuid, name: &str, whitelist_enabled: bool) -> Result<(), String> {
+        let mut banned_players = self.banned_players.lock().unwrap();
+        banned_players.reload_if_changed();
+        banned_players.remove_where(|entry| is_expired(&entry.expires, Local::now()));
+
+        if let Some(entry) = banned_players.entries().iter().find(|entry| Self::matches(&entry.uuid, &entry.name, uuid, name)) {
+            return Err(ban_message(PLAYER_BAN_HEADER, &entry.reason, &entry.expires));
+        }
+        drop(banned_players);
+
+        if whitelist_enabled && !self.is_whitelisted(uuid, name) {
+            return Err("You are not white-listed on this server!".to_owned());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid, name: &str) -> bool {
+        let mut whitelist = self.whitelist.lock().unwrap();
+        whitelist.reload_if_changed();
+        whitelist.entries().iter().any(|entry| Self::matches(&entry.uuid, &entry.name, uuid, name))
+    }
+
+    pub fn whitelist_add(&self, uuid: Uuid, name: &str) -> bool {
+        let mut whitelist = self.whitelist.lock().unwrap();
+        whitelist.reload_if_changed();
+        if whitelist.entries().iter().any(|entry| Self::matches(&entry.uuid, &entry.name, uuid, name)) { return false; }
+
+        whitelist.push(WhitelistEntry {
+            uuid: uuid.hyphenated().to_string(),
+            name: name.to_owned(),
+        });
+        true
+    }
+
+    pub fn whitelist_remove(&self, name: &str) -> bool {
+        let mut whitelist = self.whitelist.lock().unwrap();
+        whitelist.reload_if_changed();
+        whitelist.remove_where(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn whitelist_names(&self) -> Vec<String> {
+        let mut whitelist = self.whitelist.lock().unwrap();
+        whitelist.reload_if_changed();
+        whitelist.entries().iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    /// Bans a player. `expires` is either a date in the vanilla format or `None` for a permanent ban.
+    pub fn ban_player(&self, uuid: Uuid, name: &str, reason: Option<String>, source: &str, expires: Option<String>) {
+        let mut banned_players = self.banned_players.lock().unwrap();
+        banned_players.reload_if_changed();
+        banned_players.remove_where(|entry| Self::matches(&entry.uuid, &entry.name, uuid, name));
+        banned_players.push(PlayerBanEntry {
+            uuid: uuid.hyphenated().to_string(),
+            name: name.to_owned(),
+            created: format_date(Local::now()),
+            source: source.to_owned(),
+            expires: expires.unwrap_or(FOREVER.to_owned()),
+            reason: reason.unwrap_or(DEFAULT_BAN_REASON.to_owned()),
+        });
+    }
+
+    pub fn pardon_player(&self, name: &str) -> bool {
+        let mut banned_players = self.banned_players.lock().unwrap();
+        banned_players.reload_if_changed();
+        banned_players.remove_where(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn ban_ip(&self, ip: IpAddr, reason: Option<String>, source: &str, expires: Option<String>) {
+        let ip = ip.to_string();
+        let mut banned_ips = self.banned_ips.lock().unwrap();
+        banned_ips.reload_if_changed();
+        banned_ips.remove_where(|entry| entry.ip == ip);
+        banned_ips.push(IpBanEntry {
+            ip,
+            created: format_date(Local::now()),
+            source: source.to_owned(),
+            expires: expires.unwrap_or(FOREVER.to_owned()),
+            reason: reason.unwrap_or(DEFAULT_BAN_REASON.to_owned()),
+        });
+    }
+
+    pub fn pardon_ip(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_string();
+        let mut banned_ips = self.banned_ips.lock().unwrap();
+        banned_ips.reload_if_changed();
+        banned_ips.remove_where(|entry| entry.ip == ip)
+    }
+
+    pub fn banned_player_names(&self) -> Vec<String> {
+        let mut banned_players = self.banned_players.lock().unwrap();
+        banned_players.reload_if_changed();
+        banned_players.entries().iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    pub fn banned_ips(&self) -> Vec<String> {
+        let mut banned_ips = self.banned_ips.lock().unwrap();
+        banned_ips.reload_if_changed();
+        banned_ips.entries().iter().map(|entry| entry.ip.clone()).collect()
+    }
+
+    /// Entries are matched by UUID, falling back to the name for hand written entries without one.
+    fn matches(entry_uuid: &str, entry_name: &str, uuid: Uuid, name: &str) -> bool {
+        match Uuid::parse_str(entry_uuid) {
+            Ok(entry_uuid) => entry_uuid == uuid,
+            Err(_) => entry_name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::Ipv4Addr, path::PathBuf};
+
+    use chrono::Duration;
+
+    use crate::utils::test_utils::temp_directory;
+
+    use super::*;
+
+    #[test]
+    fn test_creates_empty_files() {
+        let directory = temp_directory("access_empty");
+        AccessLists::load(&directory);
+
+        for file in [WHITELIST_FILE, BANNED_PLAYERS_FILE, BANNED_IPS_FILE] {
+            assert_eq!(fs::read_to_string(directory.join(file)).unwrap(), "[]");
+        }
+    }
+
+    #[test]
+    fn test_player_ban() {
+        let directory = temp_directory("access_player_ban");
+        let access_lists = AccessLists::load(&directory);
+        let uuid = Uuid::new_v4();
+
+        assert!(access_lists.check_player(uuid, "Griefer", false).is_ok());
+
+        access_lists.ban_player(uuid, "Griefer", Some("Griefing".to_owned()), "Server", None);
+        assert_eq!(access_lists.check_player(uuid, "Griefer", false), Err("You are banned from this server.\nReason: Griefing".to_owned()));
+
+        // Bans follow the UUID, not the name
+        assert!(access_lists.check_player(uuid, "NewName", false).is_err());
+        assert!(access_lists.check_player(Uuid::new_v4(), "Griefer", false).is_ok());
+
+        assert!(access_lists.pardon_player("griefer"));
+        assert!(access_lists.check_player(uuid, "Griefer", false).is_ok());
+        assert!(!access_lists.pardon_player("griefer"));
+    }
+
+    #[test]
+    fn test_expired_ban() {
+        let directory = temp_directory("access_expired_ban");
+        let access_lists = AccessLists::load(&directory);
+        let uuid = Uuid::new_v4();
+
+        access_lists.ban_player(uuid, "Griefer", None, "Server", Some(format_date(Local::now() - Duration::minutes(1))));
+        assert!(access_lists.check_player(uuid, "Griefer", false).is_ok());
+        assert!(access_lists.banned_player_names().is_empty());
+    }
+
+    #[test]
+    fn test_ip_ban() {
+        let directory = temp_directory("access_ip_ban");
+        let access_lists = AccessLists::load(&directory);
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+        access_lists.ban_ip(ip, None, "Server", None);
+        assert_eq!(access_lists.check_ip(ip), Err(format!("Your IP address is banned from this server.\nReason: {}", DEFAULT_BAN_REASON)));
+        assert!(access_lists.check_ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))).is_ok());
+
+        assert!(access_lists.pardon_ip(ip));
+        assert!(access_lists.check_ip(ip).is_ok());
+    }
+
+    #[test]
+    fn test_whitelist() {
+        let directory = temp_directory("access_whitelist");
+        let access_lists = AccessLists::load(&directory);
+        let uuid = Uuid::new_v4();
+
+        assert!(access_lists.check_player(uuid, "Friend", false).is_ok());
+        assert!(access_lists.check_player(uuid, "Friend", true).is_err());
+
+        assert!(access_lists.whitelist_add(uuid, "Friend"));
+        assert!(!access_lists.whitelist_add(uuid, "Friend"));
+        assert!(access_lists.check_player(uuid, "Friend", true).is_ok());
+
+        assert!(access_lists.whitelist_remove("Friend"));
+        assert!(access_lists.check_player(uuid, "Friend", true).is_err());
+    }
+
+    #[test]
+    fn test_hot_reload() {
+        let directory = temp_directory("access_hot_reload");
+        let access_lists = AccessLists::load(&directory);
+        let uuid = Uuid::new_v4();
+
+        // Make sure the modification time actually changes
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(directory.join(WHITELIST_FILE), format!(r#"[{{"uuid": "{}", "name": "Friend"}}]"#, uuid)).unwrap();
+        assert!(access_lists.is_whitelisted(uuid, "Friend"));
+
+        // Changes made by hand aren't lost by changing the list right after
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(directory.join(WHITELIST_FILE), format!(r#"[{{"uuid": "{}", "name": "Friend"}}, {{"uuid": "", "name": "Other"}}]"#, uuid)).unwrap();
+        assert!(access_lists.whitelist_add(Uuid::new_v4(), "Third"));
+        assert_eq!(access_lists.whitelist_names(), vec!["Friend", "Other", "Third"]);
+    }
+
+    #[test]
+    fn test_unreadable_file() {
+        let directory = temp_directory("access_unreadable");
+        fs::write(directory.join(BANNED_PLAYERS_FILE), "[{").unwrap();
+        let access_lists = AccessLists::load(&directory);
+
+        // The broken file is left alone instead of being replaced by an empty list
+        access_lists.ban_player(Uuid::new_v4(), "Griefer", None, "Server", None);
+        assert_eq!(fs::read_to_string(directory.join(BANNED_PLAYERS_FILE)).unwrap(), "[{");
+    }
+}
\ No newline at end of file
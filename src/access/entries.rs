@@ -0,0 +1,106 @@
+use chrono::{DateTime, FixedOffset, Local};
+use serde_derive::{Deserialize, Serialize};
+
+/// Date format used by all vanilla list files, e.g. `2024-08-10 15:30:00 +0200`.
+pub const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+pub const FOREVER: &str = "forever";
+pub const PLAYER_BAN_HEADER: &str = "You are banned from this server.";
+pub const IP_BAN_HEADER: &str = "Your IP address is banned from this server.";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WhitelistEntry {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerBanEntry {
+    pub uuid: String,
+    pub name: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IpBanEntry {
+    pub ip: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpEntry {
+    pub uuid: String,
+    pub name: String,
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit")]
+    pub bypasses_player_limit: bool,
+}
+
+pub fn format_date(date: DateTime<Local>) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+pub fn parse_expiry(expires: &str) -> Option<DateTime<FixedOffset>> {
+    if expires == FOREVER { return None; }
+    DateTime::parse_from_str(expires, DATE_FORMAT).ok()
+}
+
+pub fn is_expired(expires: &str, now: DateTime<Local>) -> bool {
+    match parse_expiry(expires) {
+        Some(expiry) => expiry <= now,
+        None => false,
+    }
+}
+
+/// Builds the disconnect message vanilla shows to banned players.
+pub fn ban_message(header: &str, reason: &str, expires: &str) -> String {
+    let mut message = format!("{}\nReason: {}", header, reason);
+
+    if let Some(expiry) = parse_expiry(expires) {
+        message += &format!("\nYour ban will be removed on {}", expiry.format(DATE_FORMAT));
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_expiry() {
+        assert!(parse_expiry(FOREVER).is_none());
+        assert!(parse_expiry("garbage").is_none());
+
+        let expiry = parse_expiry("2024-08-10 15:30:00 +0200").unwrap();
+        assert_eq!(expiry.to_rfc3339(), "2024-08-10T15:30:00+02:00");
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = Local.with_ymd_and_hms(2024, 8, 10, 12, 0, 0).unwrap();
+
+        assert!(!is_expired(FOREVER, now));
+        assert!(is_expired(&format_date(now - Duration::hours(1)), now));
+        assert!(!is_expired(&format_date(now + Duration::hours(1)), now));
+    }
+
+    #[test]
+    fn test_ban_message() {
+        assert_eq!(
+            ban_message("You are banned from this server.", "Griefing", FOREVER),
+            "You are banned from this server.\nReason: Griefing"
+        );
+        assert_eq!(
+            ban_message("You are banned from this server.", "Griefing", "2030-01-02 03:04:05 +0000"),
+            "You are banned from this server.\nReason: Griefing\nYour ban will be removed on 2030-01-02 03:04:05 +0000"
+        );
+    }
+}
\ No newline at end of file
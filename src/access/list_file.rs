@@ -0,0 +1,101 @@
+use std::{fs, path::{Path, PathBuf}, time::SystemTime};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{log, LOGGER};
+
+/// A JSON array of entries stored in a file, like vanilla's `whitelist.json`.
+/// The file is read again whenever its modification time changes, so it can be edited by hand.
+pub struct ListFile<T> {
+    path: PathBuf,
+    entries: Vec<T>,
+    modified: Option<SystemTime>,
+    /// Set while the file on disk couldn't be parsed, so saving doesn't overwrite it with what's left in memory.
+    unreadable: bool,
+}
+
+impl<T: Serialize + DeserializeOwned> ListFile<T> {
+    pub fn load(path: &Path) -> Self {
+        let mut list = Self {
+            path: path.to_path_buf(),
+            entries: Vec::new(),
+            modified: None,
+            unreadable: false,
+        };
+
+        if path.exists() {
+            if let Err(e) = list.reload() {
+                log!(error, "Failed to load {}: {}", path.display(), e);
+            }
+        }
+        else {
+            list.save();
+        }
+
+        list
+    }
+
+    pub fn reload(&mut self) -> Result<(), String> {
+        let data = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        self.modified = Self::modified_time(&self.path);
+        self.unreadable = true;
+        self.entries = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+        self.unreadable = false;
+        Ok(())
+    }
+
+    pub fn reload_if_changed(&mut self) {
+        let modified = Self::modified_time(&self.path);
+        if modified.is_none() || modified == self.modified { return; }
+
+        match self.reload() {
+            Ok(()) => log!(info, "Reloaded {}", self.path.display()),
+            Err(e) => log!(error, "Failed to reload {}: {}", self.path.display(), e),
+        }
+    }
+
+    pub fn save(&mut self) {
+        if self.unreadable {
+            log!(error, "Not saving {}, it couldn't be read. Fix or delete it to save changes again.", self.path.display());
+            return;
+        }
+
+        let data = match serde_json::to_string_pretty(&self.entries) {
+            Ok(data) => data,
+            Err(e) => {
+                log!(error, "Failed to serialize {}: {}", self.path.display(), e);
+                return;
+            }
+        };
+
+        if let Err(e) = fs::write(&self.path, data) {
+            log!(error, "Failed to write {}: {}", self.path.display(), e);
+            return;
+        }
+
+        self.modified = Self::modified_time(&self.path);
+    }
+
+    pub fn entries(&self) -> &[T] {
+        &self.entries
+    }
+
+    pub fn push(&mut self, entry: T) {
+        self.entries.push(entry);
+        self.save();
+    }
+
+    /// Removes all entries matching `predicate`, saving the file if anything was removed.
+    pub fn remove_where(&mut self, predicate: impl Fn(&T) -> bool) -> bool {
+        let length = self.entries.len();
+        self.entries.retain(|entry| !predicate(entry));
+
+        let removed = self.entries.len() != length;
+        if removed { self.save(); }
+        removed
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,4 @@
+pub mod access_lists;
+pub mod entries;
+pub mod list_file;
+pub mod permissions;
\ No newline at end of file
@@ -0,0 +1,328 @@
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}, sync::{Mutex, RwLock}};
+
+use serde_derive::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{custom_types::game_profile::GameProfile, log, LOGGER};
+
+use super::{entries::OpEntry, list_file::ListFile};
+
+pub const OPS_FILE: &str = "ops.json";
+pub const PERMISSIONS_FILE: &str = "permissions.toml";
+
+/// Highest op level, which also grants every node nobody registered a default for.
+pub const MAX_OP_LEVEL: u8 = 4;
+/// Group every player is a member of.
+pub const DEFAULT_GROUP: &str = "default";
+
+pub const BYPASS_PLAYER_LIMIT_PERMISSION: &str = "rustcraft.bypass.player_limit";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PermissionsFile {
+    #[serde(default)]
+    pub groups: HashMap<String, PermissionGroup>,
+    /// Keyed by UUID or player name.
+    #[serde(default)]
+    pub players: HashMap<String, PlayerPermissions>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PermissionGroup {
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PlayerPermissions {
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Operators (ops.json) plus permission nodes from permissions.toml.
+///
+/// A node like `rustcraft.command.kick` is resolved in this order, the first match wins:
+/// the player's own nodes, the player's groups (including inherited ones), the `default` group,
+/// and finally the op level the node was registered with. Within each step the exact node is
+/// checked before wildcards (`rustcraft.command.*`, `rustcraft.*`, `*`), and a `-` prefix denies the node.
+pub struct Permissions {
+    ops: Mutex<ListFile<OpEntry>>,
+    file_path: PathBuf,
+    file: RwLock<PermissionsFile>,
+    defaults: RwLock<HashMap<String, u8>>,
+}
+
+impl Permissions {
+    pub fn load(directory: &Path) -> Self {
+        let file_path = directory.join(PERMISSIONS_FILE);
+        if !file_path.exists() {
+            Self::write_default_file(&file_path);
+        }
+
+        let permissions = Self {
+            ops: Mutex::new(ListFile::load(&directory.join(OPS_FILE))),
+            file_path,
+            file: RwLock::new(PermissionsFile::default()),
+            defaults: RwLock::new(HashMap::new()),
+        };
+
+        if let Err(e) = permissions.reload() {
+            log!(error, "Failed to load {}: {}", PERMISSIONS_FILE, e);
+        }
+
+        permissions
+    }
+
+    pub fn reload(&self) -> Result<(), String> {
+        self.ops.lock().unwrap().reload()?;
+
+        let data = fs::read_to_string(&self.file_path).map_err(|e| e.to_string())?;
+        *self.file.write().unwrap() = toml::from_str(&data).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Sets the op level a node is granted at when no group or player entry mentions it.
+    pub fn register_default(&self, node: &str, op_level: u8) {
+        self.defaults.write().unwrap().insert(node.to_owned(), op_level);
+    }
+
+    pub fn has_permission(&self, profile: &GameProfile, node: &str) -> bool {
+        let file = self.file.read().unwrap();
+
+        let player = file.players.get(&profile.uuid.hyphenated().to_string())
+            .or_else(|| file.players.iter().find(|(key, _)| key.eq_ignore_ascii_case(&profile.name)).map(|(_, player)| player));
+
+        let mut visited = HashSet::new();
+        if let Some(player) = player {
+            if let Some(result) = evaluate(&player.permissions, node) {
+                return result;
+            }
+
+            for group in &player.groups {
+                if let Some(result) = Self::evaluate_group(&file, group, node, &mut visited) {
+                    return result;
+                }
+            }
+        }
+
+        if let Some(result) = Self::evaluate_group(&file, DEFAULT_GROUP, node, &mut visited) {
+            return result;
+        }
+        drop(file);
+
+        let required_level = *self.defaults.read().unwrap().get(node).unwrap_or(&MAX_OP_LEVEL);
+        self.op_level(profile.uuid) >= required_level
+    }
+
+    fn evaluate_group(file: &PermissionsFile, name: &str, node: &str, visited: &mut HashSet<String>) -> Option<bool> {
+        if !visited.insert(name.to_owned()) { return None; }
+        let group = file.groups.get(name)?;
+
+        if let Some(result) = evaluate(&group.permissions, node) {
+            return Some(result);
+        }
+
+        group.inherits.iter().find_map(|parent| Self::evaluate_group(file, parent, node, visited))
+    }
+
+    pub fn op_level(&self, uuid: Uuid) -> u8 {
+        let mut ops = self.ops.lock().unwrap();
+        ops.reload_if_changed();
+
+        let uuid = uuid.hyphenated().to_string();
+        ops.entries().iter().find(|entry| entry.uuid == uuid).map_or(0, |entry| entry.level)
+    }
+
+    pub fn is_op(&self, uuid: Uuid) -> bool {
+        self.op_level(uuid) > 0
+    }
+
+    pub fn can_bypass_player_limit(&self, profile: &GameProfile) -> bool {
+        let uuid = profile.uuid.hyphenated().to_string();
+        let mut ops = self.ops.lock().unwrap();
+        ops.reload_if_changed();
+        let bypasses = ops.entries().iter().any(|entry| entry.uuid == uuid && entry.bypasses_player_limit);
+        drop(ops);
+        bypasses || self.has_permission(profile, BYPASS_PLAYER_LIMIT_PERMISSION)
+    }
+
+    pub fn op(&self, profile: &GameProfile, level: u8) {
+        let uuid = profile.uuid.hyphenated().to_string();
+        let mut ops = self.ops.lock().unwrap();
+        ops.reload_if_changed();
+        ops.remove_where(|entry| entry.uuid == uuid);
+        ops.push(OpEntry {
+            uuid,
+            name: profile.name.clone(),
+            level: level.min(MAX_OP_LEVEL),
+            bypasses_player_limit: false,
+        });
+    }
+
+    pub fn deop(&self, name: &str) -> bool {
+        let mut ops = self.ops.lock().unwrap();
+        ops.reload_if_changed();
+        ops.remove_where(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn op_names(&self) -> Vec<String> {
+        let mut ops = self.ops.lock().unwrap();
+        ops.reload_if_changed();
+        ops.entries().iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    fn write_default_file(path: &Path) {
+        let mut file = PermissionsFile::default();
+        file.groups.insert(DEFAULT_GROUP.to_owned(), PermissionGroup::default());
+
+        match toml::to_string_pretty(&file) {
+            Ok(data) => {
+                if let Err(e) = fs::write(path, data) {
+                    log!(error, "Failed to write {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => log!(error, "Failed to serialize default permissions: {}", e)
+        }
+    }
+}
+
+/// Checks a list of nodes for `node`, the most specific match deciding the result.
+pub fn evaluate(permissions: &[String], node: &str) -> Option<bool> {
+    let mut candidates = vec![node.to_owned()];
+    let parts: Vec<&str> = node.split('.').collect();
+    for i in (1..parts.len()).rev() {
+        candidates.push(parts[..i].join(".") + ".*");
+    }
+    candidates.push("*".to_owned());
+
+    for candidate in candidates {
+        for permission in permissions {
+            if let Some(denied) = permission.strip_prefix('-') {
+                if denied == candidate { return Some(false); }
+            }
+            else if *permission == candidate {
+                return Some(true);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::utils::test_utils::temp_directory;
+
+    use super::*;
+
+    fn nodes(nodes: &[&str]) -> Vec<String> {
+        nodes.iter().map(|node| node.to_string()).collect()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let permissions = nodes(&["rustcraft.command.*", "-rustcraft.command.stop", "other.node"]);
+
+        assert_eq!(evaluate(&permissions, "rustcraft.command.kick"), Some(true));
+        assert_eq!(evaluate(&permissions, "rustcraft.command.stop"), Some(false));
+        assert_eq!(evaluate(&permissions, "other.node"), Some(true));
+        assert_eq!(evaluate(&permissions, "other.node.child"), None);
+        assert_eq!(evaluate(&permissions, "rustcraft.bypass.player_limit"), None);
+    }
+
+    #[test]
+    fn test_evaluate_specific_beats_wildcard() {
+        assert_eq!(evaluate(&nodes(&["*", "-a.b"]), "a.b"), Some(false));
+        assert_eq!(evaluate(&nodes(&["-a.*", "a.b.c"]), "a.b.c"), Some(true));
+        assert_eq!(evaluate(&nodes(&["-a.*", "a.b.c"]), "a.b.d"), Some(false));
+    }
+
+    #[test]
+    fn test_groups_and_defaults() {
+        let directory = temp_directory("permissions_groups");
+        fs::write(directory.join(PERMISSIONS_FILE), r#"
+            [groups.default]
+            permissions = ["rustcraft.command.list"]
+
+            [groups.moderator]
+            inherits = ["default", "admin"]
+            permissions = ["rustcraft.command.kick"]
+
+            [groups.admin]
+            inherits = ["moderator"]
+            permissions = ["*", "-rustcraft.command.stop"]
+
+            [players.Moderator]
+            groups = ["moderator"]
+            permissions = ["-rustcraft.command.list"]
+        "#).unwrap();
+
+        let permissions = Permissions::load(&directory);
+        permissions.register_default("rustcraft.command.help", 0);
+
+        let player = GameProfile::new(Uuid::new_v4(), "Player");
+        let moderator = GameProfile::new(Uuid::new_v4(), "Moderator");
+
+        assert!(permissions.has_permission(&player, "rustcraft.command.list"));
+        assert!(permissions.has_permission(&player, "rustcraft.command.help"));
+        assert!(!permissions.has_permission(&player, "rustcraft.command.kick"));
+
+        assert!(!permissions.has_permission(&moderator, "rustcraft.command.list"));
+        assert!(permissions.has_permission(&moderator, "rustcraft.command.kick"));
+        // Inherited through the admin group, despite the inheritance cycle
+        assert!(permissions.has_permission(&moderator, "rustcraft.command.ban"));
+        assert!(!permissions.has_permission(&moderator, "rustcraft.command.stop"));
+    }
+
+    #[test]
+    fn test_op_levels() {
+        let directory = temp_directory("permissions_ops");
+        let permissions = Permissions::load(&directory);
+        permissions.register_default("rustcraft.command.kick", 3);
+
+        let player = GameProfile::new(Uuid::new_v4(), "Player");
+        assert!(!permissions.has_permission(&player, "rustcraft.command.kick"));
+
+        permissions.op(&player, 3);
+        assert_eq!(permissions.op_level(player.uuid), 3);
+        assert!(permissions.has_permission(&player, "rustcraft.command.kick"));
+        // Unregistered nodes need the highest op level
+        assert!(!permissions.has_permission(&player, "some.plugin.node"));
+
+        permissions.op(&player, 4);
+        assert!(permissions.has_permission(&player, "some.plugin.node"));
+
+        assert!(permissions.deop("player"));
+        assert!(!permissions.is_op(player.uuid));
+    }
+
+    #[test]
+    fn test_op_keeps_hand_edits() {
+        let directory = temp_directory("permissions_hand_edits");
+        let permissions = Permissions::load(&directory);
+        let admin = Uuid::new_v4();
+
+        // Make sure the modification time actually changes
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(directory.join(OPS_FILE), format!(r#"[{{"uuid": "{}", "name": "Admin", "level": 4, "bypassesPlayerLimit": true}}]"#, admin)).unwrap();
+        permissions.op(&GameProfile::new(Uuid::new_v4(), "Player"), 2);
+
+        assert_eq!(permissions.op_names(), vec!["Admin", "Player"]);
+        assert!(permissions.can_bypass_player_limit(&GameProfile::new(admin, "Admin")));
+    }
+
+    #[test]
+    fn test_default_file() {
+        let directory = temp_directory("permissions_default_file");
+        Permissions::load(&directory);
+
+        let file: PermissionsFile = toml::from_str(&fs::read_to_string(directory.join(PERMISSIONS_FILE)).unwrap()).unwrap();
+        assert!(file.groups.contains_key(DEFAULT_GROUP));
+        assert_eq!(fs::read_to_string(directory.join(OPS_FILE)).unwrap(), "[]");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,500 @@
+use bytes::{BufMut, BytesMut};
+use rand::seq::SliceRandom;
+
+use crate::{player_list::{OnlinePlayer, PlayerList}, utils::{errors::CommandSyntaxError, packet_utils::write_varint}};
+
+use super::{command_sender::CommandSender, string_reader::StringReader};
+
+// IDs in the `minecraft:command_argument_type` registry
+pub const PARSER_BOOL: i32 = 0;
+pub const PARSER_FLOAT: i32 = 1;
+pub const PARSER_DOUBLE: i32 = 2;
+pub const PARSER_INTEGER: i32 = 3;
+pub const PARSER_LONG: i32 = 4;
+pub const PARSER_STRING: i32 = 5;
+pub const PARSER_ENTITY: i32 = 6;
+pub const PARSER_GAME_PROFILE: i32 = 7;
+pub const PARSER_BLOCK_POS: i32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StringKind {
+    /// A single unquoted word.
+    SingleWord,
+    /// A word, or a phrase in quotes.
+    QuotablePhrase,
+    /// Everything up to the end of the input.
+    GreedyPhrase,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgumentType {
+    Bool,
+    Integer { min: Option<i32>, max: Option<i32> },
+    Long { min: Option<i64>, max: Option<i64> },
+    Float { min: Option<f32>, max: Option<f32> },
+    Double { min: Option<f64>, max: Option<f64> },
+    String(StringKind),
+    /// `single` rejects selectors that can match more than one target.
+    Entity { single: bool, players_only: bool },
+    GameProfile,
+    BlockPos,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Entity(EntitySelector),
+    GameProfile(EntitySelector),
+    BlockPos(BlockPosArgument),
+}
+
+fn check_bounds<T: PartialOrd + std::fmt::Display>(kind: &str, value: T, min: Option<T>, max: Option<T>) -> Result<T, String> {
+    if let Some(min) = min.filter(|min| value < *min) {
+        return Err(format!("{} must not be less than {}, found {}", kind, min, value));
+    }
+
+    if let Some(max) = max.filter(|max| value > *max) {
+        return Err(format!("{} must not be more than {}, found {}", kind, max, value));
+    }
+
+    Ok(value)
+}
+
+fn out_of_bounds(reader: &StringReader, start: usize, message: &str) -> CommandSyntaxError {
+    CommandSyntaxError::new(message, reader.input(), start)
+}
+
+/// Flags byte followed by whichever bounds are present, as used by all numeric parsers.
+fn write_bounds<T>(buf: &mut BytesMut, min: Option<T>, max: Option<T>, put: fn(&mut BytesMut, T)) {
+    buf.put_u8(min.is_some() as u8 | (max.is_some() as u8) << 1);
+    if let Some(min) = min { put(buf, min); }
+    if let Some(max) = max { put(buf, max); }
+}
+
+impl ArgumentType {
+    pub fn integer() -> Self {
+        Self::Integer { min: None, max: None }
+    }
+
+    pub fn integer_between(min: i32, max: i32) -> Self {
+        Self::Integer { min: Some(min), max: Some(max) }
+    }
+
+    pub fn word() -> Self {
+        Self::String(StringKind::SingleWord)
+    }
+
+    /// A word, or a phrase in quotes.
+    pub fn string() -> Self {
+        Self::String(StringKind::QuotablePhrase)
+    }
+
+    pub fn greedy_string() -> Self {
+        Self::String(StringKind::GreedyPhrase)
+    }
+
+    pub fn player() -> Self {
+        Self::Entity { single: true, players_only: true }
+    }
+
+    pub fn players() -> Self {
+        Self::Entity { single: false, players_only: true }
+    }
+
+    pub fn parse(&self, reader: &mut StringReader) -> Result<ArgumentValue, CommandSyntaxError> {
+        let start = reader.cursor();
+        Ok(match *self {
+            Self::Bool => ArgumentValue::Bool(reader.read_boolean()?),
+            Self::Integer { min, max } => ArgumentValue::Integer(check_bounds("Integer", reader.read_int()?, min, max).map_err(|message| out_of_bounds(reader, start, &message))?),
+            Self::Long { min, max } => ArgumentValue::Long(check_bounds("Long", reader.read_long()?, min, max).map_err(|message| out_of_bounds(reader, start, &message))?),
+            Self::Float { min, max } => ArgumentValue::Float(check_bounds("Float", reader.read_float()?, min, max).map_err(|message| out_of_bounds(reader, start, &message))?),
+            Self::Double { min, max } => ArgumentValue::Double(check_bounds("Double", reader.read_double()?, min, max).map_err(|message| out_of_bounds(reader, start, &message))?),
+            Self::String(StringKind::SingleWord) => ArgumentValue::String(reader.read_unquoted_string().to_owned()),
+            Self::String(StringKind::QuotablePhrase) => ArgumentValue::String(reader.read_string()?),
+            Self::String(StringKind::GreedyPhrase) => {
+                let text = reader.remaining().to_owned();
+                reader.set_cursor(reader.input().len());
+                ArgumentValue::String(text)
+            },
+            Self::Entity { single, players_only } => {
+                let selector = EntitySelector::parse(reader)?;
+                if single && !selector.is_single() {
+                    reader.set_cursor(start);
+                    return Err(reader.error("Only one entity is allowed, but the provided selector allows more than one"));
+                }
+                if players_only && !selector.is_players_only() {
+                    reader.set_cursor(start);
+                    return Err(reader.error("Only players may be affected by this command, but the provided selector includes entities"));
+                }
+                ArgumentValue::Entity(selector)
+            },
+            Self::GameProfile => ArgumentValue::GameProfile(EntitySelector::parse(reader)?),
+            Self::BlockPos => ArgumentValue::BlockPos(BlockPosArgument::parse(reader)?),
+        })
+    }
+
+    pub fn parser_id(&self) -> i32 {
+        match self {
+            Self::Bool => PARSER_BOOL,
+            Self::Float { .. } => PARSER_FLOAT,
+            Self::Double { .. } => PARSER_DOUBLE,
+            Self::Integer { .. } => PARSER_INTEGER,
+            Self::Long { .. } => PARSER_LONG,
+            Self::String(_) => PARSER_STRING,
+            Self::Entity { .. } => PARSER_ENTITY,
+            Self::GameProfile => PARSER_GAME_PROFILE,
+            Self::BlockPos => PARSER_BLOCK_POS,
+        }
+    }
+
+    /// Parser properties as they follow the parser ID in the Commands packet.
+    pub fn properties(&self) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        match *self {
+            Self::Bool | Self::GameProfile | Self::BlockPos => {},
+            Self::Float { min, max } => write_bounds(&mut buf, min, max, |buf, n| buf.put_f32(n)),
+            Self::Double { min, max } => write_bounds(&mut buf, min, max, |buf, n| buf.put_f64(n)),
+            Self::Integer { min, max } => write_bounds(&mut buf, min, max, |buf, n| buf.put_i32(n)),
+            Self::Long { min, max } => write_bounds(&mut buf, min, max, |buf, n| buf.put_i64(n)),
+            Self::String(kind) => write_varint(&mut buf, kind as i32),
+            Self::Entity { single, players_only } => buf.put_u8(single as u8 | (players_only as u8) << 1),
+        }
+
+        buf.to_vec()
+    }
+
+    /// Whether the client should ask the server for suggestions instead of working them out itself.
+    pub fn needs_server_suggestions(&self) -> bool {
+        matches!(self, Self::Entity { .. } | Self::GameProfile)
+    }
+
+    pub fn suggestions(&self, players: &PlayerList) -> Vec<String> {
+        let mut names: Vec<String> = players.profiles().into_iter().map(|profile| profile.name).collect();
+        names.sort();
+
+        match self {
+            Self::Bool => vec!["true".to_owned(), "false".to_owned()],
+            Self::Entity { players_only, .. } => {
+                let selectors = if *players_only { ["@p", "@a", "@r", "@s"].as_slice() } else { ["@p", "@a", "@r", "@s", "@e"].as_slice() };
+                names.extend(selectors.iter().map(|selector| selector.to_string()));
+                names
+            },
+            Self::GameProfile => names,
+            Self::BlockPos => vec!["~".to_owned(), "~ ~".to_owned(), "~ ~ ~".to_owned()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectorTarget {
+    /// `@p`
+    NearestPlayer,
+    /// `@a`
+    AllPlayers,
+    /// `@r`
+    RandomPlayer,
+    /// `@e`
+    AllEntities,
+    /// `@s`
+    Executor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntitySelector {
+    Name(String),
+    Selector { target: SelectorTarget, limit: Option<usize>, name: Option<String> },
+}
+
+impl EntitySelector {
+    pub fn parse(reader: &mut StringReader) -> Result<Self, CommandSyntaxError> {
+        if reader.peek() != Some('@') {
+            let name = reader.read_word();
+            if name.is_empty() {
+                return Err(reader.error("Expected a player name or selector"));
+            }
+            return Ok(Self::Name(name.to_owned()));
+        }
+
+        reader.skip();
+        let target = match reader.peek() {
+            Some('p') => SelectorTarget::NearestPlayer,
+            Some('a') => SelectorTarget::AllPlayers,
+            Some('r') => SelectorTarget::RandomPlayer,
+            Some('e') => SelectorTarget::AllEntities,
+            Some('s') => SelectorTarget::Executor,
+            Some(c) => return Err(reader.error(&format!("Unknown selector type '@{}'", c))),
+            None => return Err(reader.error("Missing selector type")),
+        };
+        reader.skip();
+
+        let mut limit = None;
+        let mut name = None;
+        if reader.peek() == Some('[') {
+            reader.skip();
+            reader.skip_whitespace();
+
+            while reader.peek() != Some(']') {
+                let option_start = reader.cursor();
+                let option = reader.read_unquoted_string();
+                reader.skip_whitespace();
+                reader.expect('=')?;
+                reader.skip_whitespace();
+
+                match option {
+                    "limit" => {
+                        let value_start = reader.cursor();
+                        let value = reader.read_int()?;
+                        if value < 1 {
+                            reader.set_cursor(value_start);
+                            return Err(reader.error("Limit must be at least 1"));
+                        }
+                        limit = Some(value as usize);
+                    },
+                    "name" => name = Some(reader.read_string()?),
+                    _ => {
+                        reader.set_cursor(option_start);
+                        return Err(reader.error(&format!("Unknown option '{}'", option)));
+                    }
+                }
+
+                reader.skip_whitespace();
+                match reader.peek() {
+                    Some(',') => {
+                        reader.skip();
+                        reader.skip_whitespace();
+                    },
+                    Some(']') => {},
+                    _ => return Err(reader.error("Expected end of options")),
+                }
+            }
+            reader.skip();
+        }
+
+        Ok(Self::Selector { target, limit, name })
+    }
+
+    pub fn is_single(&self) -> bool {
+        match self {
+            Self::Name(_) => true,
+            Self::Selector { target, limit, .. } => match target {
+                SelectorTarget::AllPlayers | SelectorTarget::AllEntities => *limit == Some(1),
+                _ => limit.is_none_or(|limit| limit == 1),
+            },
+        }
+    }
+
+    pub fn is_players_only(&self) -> bool {
+        !matches!(self, Self::Selector { target: SelectorTarget::AllEntities, .. })
+    }
+
+    /// Finds the matching players. Players are the only entities there are, so `@e` matches them too.
+    pub fn resolve(&self, sender: &CommandSender, players: &PlayerList) -> Vec<OnlinePlayer> {
+        let (target, limit, name) = match self {
+            Self::Name(name) => return players.find_by_name(name).into_iter().collect(),
+            Self::Selector { target, limit, name } => (*target, *limit, name),
+        };
+
+        let mut candidates = match target {
+            SelectorTarget::Executor => match sender {
+                CommandSender::Player(player) => vec![player.clone()],
+                CommandSender::Console => Vec::new(),
+            },
+            _ => {
+                let mut candidates = players.players();
+                candidates.sort_by(|a, b| a.profile.name.cmp(&b.profile.name));
+                candidates
+            }
+        };
+
+        if let Some(name) = name {
+            candidates.retain(|player| player.profile.name.eq_ignore_ascii_case(name));
+        }
+
+        match target {
+            SelectorTarget::RandomPlayer => candidates.shuffle(&mut rand::thread_rng()),
+            // Without positions, the nearest player to a player is themselves
+            SelectorTarget::NearestPlayer => if let CommandSender::Player(sender) = sender {
+                if let Some(index) = candidates.iter().position(|player| player.profile.uuid == sender.profile.uuid) {
+                    candidates.swap(0, index);
+                }
+            },
+            _ => {},
+        }
+
+        let default_limit = match target {
+            SelectorTarget::NearestPlayer | SelectorTarget::RandomPlayer => Some(1),
+            _ => None,
+        };
+
+        if let Some(limit) = limit.or(default_limit) {
+            candidates.truncate(limit);
+        }
+
+        candidates
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoordinateKind {
+    Absolute,
+    /// `~`, relative to the source's position.
+    Relative,
+    /// `^`, relative to where the source is looking.
+    Local,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordinate {
+    pub kind: CoordinateKind,
+    pub value: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockPosArgument {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinate {
+    fn parse(reader: &mut StringReader) -> Result<Self, CommandSyntaxError> {
+        let kind = match reader.peek() {
+            Some('~') => CoordinateKind::Relative,
+            Some('^') => CoordinateKind::Local,
+            Some(_) => CoordinateKind::Absolute,
+            None => return Err(reader.error("Expected a coordinate")),
+        };
+
+        if kind == CoordinateKind::Absolute {
+            return Ok(Self { kind, value: reader.read_int()? as f64 });
+        }
+
+        reader.skip();
+        let value = if reader.peek().is_some_and(|c| c != ' ') { reader.read_double()? } else { 0.0 };
+        Ok(Self { kind, value })
+    }
+}
+
+impl BlockPosArgument {
+    pub fn parse(reader: &mut StringReader) -> Result<Self, CommandSyntaxError> {
+        let start = reader.cursor();
+        let x = Coordinate::parse(reader)?;
+        reader.expect(' ').map_err(|_| reader.error("Incomplete (expected 3 coordinates)"))?;
+        let y = Coordinate::parse(reader)?;
+        reader.expect(' ').map_err(|_| reader.error("Incomplete (expected 3 coordinates)"))?;
+        let z = Coordinate::parse(reader)?;
+
+        let local = [x, y, z].iter().filter(|coordinate| coordinate.kind == CoordinateKind::Local).count();
+        if local != 0 && local != 3 {
+            reader.set_cursor(start);
+            return Err(reader.error("Cannot mix world & local coordinates (everything must either use ^ or not)"));
+        }
+
+        Ok(Self { x, y, z })
+    }
+
+    /// Works out the block position for a source at `origin` looking in the direction of `rotation` (yaw, pitch in degrees).
+    pub fn resolve(&self, origin: (f64, f64, f64), rotation: (f32, f32)) -> (i32, i32, i32) {
+        let (x, y, z) = if self.x.kind == CoordinateKind::Local {
+            // Same math as vanilla's LocalCoordinates: x is left, y is up and z is forwards
+            let (yaw, pitch) = ((rotation.0 + 90.0).to_radians() as f64, (-rotation.1).to_radians() as f64);
+            let forwards = (yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+            let up_pitch = (-rotation.1 + 90.0).to_radians() as f64;
+            let up = (yaw.cos() * up_pitch.cos(), up_pitch.sin(), yaw.sin() * up_pitch.cos());
+            let left = (
+                -(forwards.1 * up.2 - forwards.2 * up.1),
+                -(forwards.2 * up.0 - forwards.0 * up.2),
+                -(forwards.0 * up.1 - forwards.1 * up.0),
+            );
+
+            (
+                origin.0 + forwards.0 * self.z.value + up.0 * self.y.value + left.0 * self.x.value,
+                origin.1 + forwards.1 * self.z.value + up.1 * self.y.value + left.1 * self.x.value,
+                origin.2 + forwards.2 * self.z.value + up.2 * self.y.value + left.2 * self.x.value,
+            )
+        }
+        else {
+            let resolve = |coordinate: Coordinate, origin: f64| match coordinate.kind {
+                CoordinateKind::Relative => origin + coordinate.value,
+                _ => coordinate.value,
+            };
+            (resolve(self.x, origin.0), resolve(self.y, origin.1), resolve(self.z, origin.2))
+        };
+
+        (x.floor() as i32, y.floor() as i32, z.floor() as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argument_type: ArgumentType, input: &str) -> Result<ArgumentValue, CommandSyntaxError> {
+        argument_type.parse(&mut StringReader::new(input))
+    }
+
+    #[test]
+    fn test_integer_bounds() {
+        assert_eq!(parse(ArgumentType::integer_between(1, 10), "5").unwrap(), ArgumentValue::Integer(5));
+        assert_eq!(parse(ArgumentType::integer_between(1, 10), "0").unwrap_err().message, "Integer must not be less than 1, found 0");
+        assert_eq!(parse(ArgumentType::integer_between(1, 10), "11").unwrap_err().message, "Integer must not be more than 10, found 11");
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(parse(ArgumentType::word(), "one two").unwrap(), ArgumentValue::String("one".to_owned()));
+        assert_eq!(parse(ArgumentType::string(), "\"one two\" three").unwrap(), ArgumentValue::String("one two".to_owned()));
+        assert_eq!(parse(ArgumentType::greedy_string(), "one two").unwrap(), ArgumentValue::String("one two".to_owned()));
+    }
+
+    #[test]
+    fn test_entity_selectors() {
+        assert_eq!(parse(ArgumentType::player(), "Notch").unwrap(), ArgumentValue::Entity(EntitySelector::Name("Notch".to_owned())));
+        assert_eq!(
+            parse(ArgumentType::players(), "@a[limit=2, name=\"jeb_\"]").unwrap(),
+            ArgumentValue::Entity(EntitySelector::Selector { target: SelectorTarget::AllPlayers, limit: Some(2), name: Some("jeb_".to_owned()) })
+        );
+
+        assert!(parse(ArgumentType::player(), "@a").is_err());
+        assert!(parse(ArgumentType::player(), "@a[limit=1]").is_ok());
+        assert!(parse(ArgumentType::players(), "@e").is_err());
+        assert!(parse(ArgumentType::Entity { single: false, players_only: false }, "@e").is_ok());
+        assert_eq!(parse(ArgumentType::players(), "@a[distance=..5]").unwrap_err().message, "Unknown option 'distance'");
+        assert_eq!(parse(ArgumentType::players(), "@x").unwrap_err().message, "Unknown selector type '@x'");
+    }
+
+    #[test]
+    fn test_block_pos() {
+        let relative = match parse(ArgumentType::BlockPos, "~ ~1 ~-0.5").unwrap() {
+            ArgumentValue::BlockPos(position) => position,
+            value => panic!("unexpected value {:?}", value),
+        };
+        assert_eq!(relative.resolve((10.5, 64.0, -3.2), (0.0, 0.0)), (10, 65, -4));
+
+        let absolute = BlockPosArgument::parse(&mut StringReader::new("1 2 -3")).unwrap();
+        assert_eq!(absolute.resolve((100.0, 100.0, 100.0), (0.0, 0.0)), (1, 2, -3));
+
+        // Facing south (yaw 0) forwards is +z and left is +x
+        let local = BlockPosArgument::parse(&mut StringReader::new("^1 ^ ^2")).unwrap();
+        assert_eq!(local.resolve((0.5, 0.5, 0.5), (0.0, 0.0)), (1, 0, 2));
+
+        assert!(parse(ArgumentType::BlockPos, "~ ^ ~").is_err());
+        assert!(parse(ArgumentType::BlockPos, "1 2").is_err());
+        assert!(parse(ArgumentType::BlockPos, "1.5 2 3").is_err());
+    }
+
+    #[test]
+    fn test_properties() {
+        assert_eq!(ArgumentType::integer_between(1, 10).properties(), vec![0x03, 0, 0, 0, 1, 0, 0, 0, 10]);
+        assert_eq!(ArgumentType::integer().properties(), vec![0x00]);
+        assert_eq!(ArgumentType::greedy_string().properties(), vec![2]);
+        assert_eq!(ArgumentType::player().properties(), vec![0x03]);
+        assert!(ArgumentType::BlockPos.properties().is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,379 @@
+use std::net::IpAddr;
+
+use crate::{access::{access_lists::DEFAULT_BAN_REASON, entries::{ban_message, FOREVER, IP_BAN_HEADER, PLAYER_BAN_HEADER}}, custom_types::position::Position, server::ServerData, utils::logger::LogLevel, world::{chunk::ChunkPos, chunk_manager::TicketKind}, CONFIG, LOGGER};
+
+use super::{arguments::ArgumentType, command_manager::{CommandContext, CommandManager}, node::{argument, command, literal}};
+
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "verbose", "debug"];
+
+pub fn register_all(manager: &mut CommandManager) {
+    manager.register(command("help", 0)
+        .describe("Lists all commands")
+        .executes(help)
+        .then(argument("command", ArgumentType::word()).executes(help)));
+
+    manager.register(command("list", 0)
+        .describe("Lists the players online")
+        .executes(list));
+
+    manager.register(command("plugins", 0)
+        .describe("Lists the loaded plugins")
+        .executes(plugins));
+
+    manager.register(command("say", 2)
+        .describe("Broadcasts a message")
+        .then(argument("message", ArgumentType::greedy_string()).executes(say)));
+
+    manager.register(command("reload", 2)
+        .describe("Reloads the access lists and permissions")
+        .executes(reload));
+
+    manager.register(command("kick", 3)
+        .describe("Kicks players")
+        .then(argument("targets", ArgumentType::players()).executes(kick)
+            .then(argument("reason", ArgumentType::greedy_string()).executes(kick))));
+
+    manager.register(command("ban", 3)
+        .describe("Bans players")
+        .then(argument("targets", ArgumentType::GameProfile).executes(ban)
+            .then(argument("reason", ArgumentType::greedy_string()).executes(ban))));
+
+    manager.register(command("ban-ip", 3)
+        .describe("Bans an IP address")
+        .then(argument("target", ArgumentType::string()).executes(ban_ip)
+            .suggests(|_, server| server.players.profiles().into_iter().map(|profile| profile.name).collect())
+            .then(argument("reason", ArgumentType::greedy_string()).executes(ban_ip))));
+
+    manager.register(command("pardon", 3)
+        .describe("Removes a player ban")
+        .then(argument("targets", ArgumentType::word()).executes(pardon)
+            .suggests(|_, server| server.access_lists.banned_player_names())));
+
+    manager.register(command("pardon-ip", 3)
+        .describe("Removes an IP ban")
+        .then(argument("target", ArgumentType::string()).executes(pardon_ip)
+            .suggests(|_, server| server.access_lists.banned_ips())));
+
+    manager.register(command("banlist", 3)
+        .describe("Lists bans")
+        .executes(banlist)
+        .then(literal("players").executes(banlist_players))
+        .then(literal("ips").executes(banlist_ips)));
+
+    manager.register(command("whitelist", 3)
+        .describe("Manages the whitelist")
+        .then(literal("add")
+            .then(argument("targets", ArgumentType::GameProfile).executes(whitelist_add)))
+        .then(literal("remove")
+            .then(argument("targets", ArgumentType::GameProfile).executes(whitelist_remove)
+                .suggests(|_, server| server.access_lists.whitelist_names())))
+        .then(literal("list").executes(whitelist_list))
+        .then(literal("reload").executes(whitelist_reload)));
+
+    manager.register(command("op", 3)
+        .describe("Makes players operators")
+        .then(argument("targets", ArgumentType::GameProfile).executes(op)));
+
+    manager.register(command("deop", 3)
+        .describe("Removes operator status from players")
+        .then(argument("targets", ArgumentType::GameProfile).executes(deop)
+            .suggests(|_, server| server.permissions.op_names())));
+
+    manager.register(command("loglevel", 4)
+        .describe("Shows or changes the log level")
+        .executes(loglevel)
+        .then(argument("level", ArgumentType::word()).executes(loglevel)
+            .suggests(|_, _| LOG_LEVELS.iter().map(|level| level.to_string()).collect())));
+
+    manager.register(command("tps", 2)
+        .describe("Shows the ticks per second and milliseconds per tick")
+        .executes(tps));
+
+    manager.register(command("forceload", 2)
+        .describe("Keeps chunks loaded")
+        .then(literal("add")
+            .then(argument("x", ArgumentType::integer())
+                .then(argument("z", ArgumentType::integer()).executes(forceload_add))))
+        .then(literal("remove")
+            .then(argument("x", ArgumentType::integer())
+                .then(argument("z", ArgumentType::integer()).executes(forceload_remove))))
+        .then(literal("query").executes(forceload_query)));
+
+    manager.register(command("stop", 4)
+        .describe("Stops the server")
+        .executes(stop));
+}
+
+fn refresh_commands(server: &ServerData, name: &str) {
+    if let Some(player) = server.players.find_by_name(name) {
+        player.handle.refresh_commands();
+    }
+}
+
+fn help(context: &CommandContext) -> Result<(), String> {
+    let permissions = &context.server.permissions;
+
+    if let Some(name) = context.get_string("command") {
+        let command = context.server.commands.find(name)
+            .filter(|command| command.can_use(context.sender, permissions))
+            .ok_or(format!("Unknown command: {}", name))?;
+
+        context.reply(&format!("/{}", command.usage(context.sender, permissions)));
+        return Ok(());
+    }
+
+    let mut commands: Vec<_> = context.server.commands.commands().filter(|command| command.can_use(context.sender, permissions)).collect();
+    commands.sort_by_key(|command| command.name());
+
+    for command in commands {
+        match &command.description {
+            Some(description) => context.reply(&format!("/{} - {}", command.usage(context.sender, permissions), description)),
+            None => context.reply(&format!("/{}", command.usage(context.sender, permissions))),
+        }
+    }
+
+    Ok(())
+}
+
+fn list(context: &CommandContext) -> Result<(), String> {
+    let mut names: Vec<String> = context.server.players.profiles().into_iter().map(|profile| profile.name).collect();
+    names.sort();
+
+    context.reply(&format!("There are {} of a max of {} players online: {}", names.len(), CONFIG.server.max_players, names.join(", ")));
+    Ok(())
+}
+
+fn plugins(context: &CommandContext) -> Result<(), String> {
+    let plugins = context.server.plugins.plugins();
+    context.reply(&format!("Plugins ({}): {}", plugins.len(), plugins.join(", ")));
+    Ok(())
+}
+
+fn say(context: &CommandContext) -> Result<(), String> {
+    let message = format!("[{}] {}", context.sender.name(), context.get_string("message").unwrap_or_default());
+    context.server.players.broadcast_message(&message);
+    crate::log!(info, "{}", message);
+    Ok(())
+}
+
+fn reload(context: &CommandContext) -> Result<(), String> {
+    context.server.access_lists.reload().map_err(|e| format!("Failed to reload the access lists: {}", e))?;
+    context.server.permissions.reload().map_err(|e| format!("Failed to reload the permissions: {}", e))?;
+
+    for player in context.server.players.players() {
+        player.handle.refresh_commands();
+    }
+
+    context.reply("Reloaded the access lists and permissions");
+    Ok(())
+}
+
+fn kick(context: &CommandContext) -> Result<(), String> {
+    let reason = context.get_string("reason").unwrap_or("Kicked by an operator");
+
+    for player in context.get_players("targets")? {
+        player.handle.disconnect(reason);
+        context.reply(&format!("Kicked {}: {}", player.profile.name, reason));
+    }
+
+    Ok(())
+}
+
+fn ban(context: &CommandContext) -> Result<(), String> {
+    let reason = context.get_string("reason").map(str::to_owned);
+
+    context.with_game_profiles("targets", move |sender, server, profiles| {
+        for profile in profiles {
+            server.access_lists.ban_player(profile.uuid, &profile.name, reason.clone(), &sender.name(), None);
+            if let Some(player) = server.players.find_by_name(&profile.name) {
+                player.handle.disconnect(&ban_message(PLAYER_BAN_HEADER, reason.as_deref().unwrap_or(DEFAULT_BAN_REASON), FOREVER));
+            }
+
+            sender.send_message(&format!("Banned {}", profile.name));
+        }
+    })
+}
+
+fn ban_ip(context: &CommandContext) -> Result<(), String> {
+    let target = context.get_string("target").unwrap_or_default();
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => context.server.players.find_by_name(target).map(|player| player.ip).ok_or("Invalid IP address or unknown player".to_owned())?,
+    };
+    let reason = context.get_string("reason").map(str::to_owned);
+
+    context.server.access_lists.ban_ip(ip, reason.clone(), &context.sender.name(), None);
+    for player in context.server.players.find_by_ip(ip) {
+        player.handle.disconnect(&ban_message(IP_BAN_HEADER, reason.as_deref().unwrap_or(DEFAULT_BAN_REASON), FOREVER));
+    }
+
+    context.reply(&format!("Banned IP {}", ip));
+    Ok(())
+}
+
+fn pardon(context: &CommandContext) -> Result<(), String> {
+    let name = context.get_string("targets").unwrap_or_default();
+    if !context.server.access_lists.pardon_player(name) {
+        return Err("Nothing changed. The player isn't banned".to_owned());
+    }
+
+    context.reply(&format!("Unbanned {}", name));
+    Ok(())
+}
+
+fn pardon_ip(context: &CommandContext) -> Result<(), String> {
+    let ip = context.get_string("target").unwrap_or_default().parse::<IpAddr>().map_err(|_| "Invalid IP address".to_owned())?;
+    if !context.server.access_lists.pardon_ip(ip) {
+        return Err("Nothing changed. That IP isn't banned".to_owned());
+    }
+
+    context.reply(&format!("Unbanned IP {}", ip));
+    Ok(())
+}
+
+fn banlist(context: &CommandContext) -> Result<(), String> {
+    banlist_players(context)?;
+    banlist_ips(context)
+}
+
+fn banlist_players(context: &CommandContext) -> Result<(), String> {
+    let names = context.server.access_lists.banned_player_names();
+    context.reply(&format!("There are {} ban(s): {}", names.len(), names.join(", ")));
+    Ok(())
+}
+
+fn banlist_ips(context: &CommandContext) -> Result<(), String> {
+    let ips = context.server.access_lists.banned_ips();
+    context.reply(&format!("There are {} IP ban(s): {}", ips.len(), ips.join(", ")));
+    Ok(())
+}
+
+fn whitelist_add(context: &CommandContext) -> Result<(), String> {
+    context.with_game_profiles("targets", |sender, server, profiles| {
+        for profile in profiles {
+            if server.access_lists.whitelist_add(profile.uuid, &profile.name) {
+                sender.send_message(&format!("Added {} to the whitelist", profile.name));
+            }
+            else {
+                sender.send_message(&format!("{} is already whitelisted", profile.name));
+            }
+        }
+    })
+}
+
+fn whitelist_remove(context: &CommandContext) -> Result<(), String> {
+    context.with_game_profiles("targets", |sender, server, profiles| {
+        for profile in profiles {
+            if server.access_lists.whitelist_remove(&profile.name) {
+                sender.send_message(&format!("Removed {} from the whitelist", profile.name));
+            }
+            else {
+                sender.send_message(&format!("{} is not whitelisted", profile.name));
+            }
+        }
+    })
+}
+
+fn whitelist_list(context: &CommandContext) -> Result<(), String> {
+    let names = context.server.access_lists.whitelist_names();
+    context.reply(&format!("There are {} whitelisted player(s): {}", names.len(), names.join(", ")));
+    Ok(())
+}
+
+fn whitelist_reload(context: &CommandContext) -> Result<(), String> {
+    context.server.access_lists.reload().map_err(|e| format!("Failed to reload the whitelist: {}", e))?;
+    context.reply("Reloaded the whitelist");
+    Ok(())
+}
+
+fn op(context: &CommandContext) -> Result<(), String> {
+    context.with_game_profiles("targets", |sender, server, profiles| {
+        for profile in profiles {
+            server.permissions.op(&profile, CONFIG.server.op_permission_level);
+            refresh_commands(server, &profile.name);
+            sender.send_message(&format!("Made {} a server operator", profile.name));
+        }
+    })
+}
+
+fn deop(context: &CommandContext) -> Result<(), String> {
+    context.with_game_profiles("targets", |sender, server, profiles| {
+        for profile in profiles {
+            if server.permissions.deop(&profile.name) {
+                refresh_commands(server, &profile.name);
+                sender.send_message(&format!("Made {} no longer a server operator", profile.name));
+            }
+            else {
+                sender.send_message(&format!("Nothing changed. {} is not an operator", profile.name));
+            }
+        }
+    })
+}
+
+fn loglevel(context: &CommandContext) -> Result<(), String> {
+    match context.get_string("level") {
+        Some(level) => {
+            let level: LogLevel = level.parse()?;
+            context.reply(&format!("Log level set to {}", level));
+            LOGGER.set_level(level);
+        },
+        None => context.reply(&format!("Log level is {}", LOGGER.level())),
+    }
+
+    Ok(())
+}
+
+fn tps(context: &CommandContext) -> Result<(), String> {
+    let stats = context.server.tick_stats.lock().unwrap();
+    let [tps_1m, tps_5m, tps_15m] = stats.tps();
+    let (average, min, max) = stats.mspt();
+    drop(stats);
+
+    context.reply(&format!("TPS from last 1m, 5m, 15m: {:.2}, {:.2}, {:.2}", tps_1m, tps_5m, tps_15m));
+    context.reply(&format!("MSPT from last 100 ticks (avg/min/max): {:.2}/{:.2}/{:.2}", average, min, max));
+    Ok(())
+}
+
+/// The chunk of the block column given by the `x` and `z` arguments.
+fn column_chunk(context: &CommandContext) -> ChunkPos {
+    let (x, z) = (context.get_integer("x").unwrap_or_default(), context.get_integer("z").unwrap_or_default());
+    ChunkPos::of(&Position::new(x as i64, 0, z as i64))
+}
+
+fn forceload_add(context: &CommandContext) -> Result<(), String> {
+    let pos = column_chunk(context);
+    if context.server.chunks.ticket_count(pos, TicketKind::Forced) > 0 {
+        return Err(format!("Chunk {} is already force loaded", pos));
+    }
+
+    context.server.add_chunk_ticket(pos, TicketKind::Forced);
+    context.reply(&format!("Chunk {} is now force loaded", pos));
+    Ok(())
+}
+
+fn forceload_remove(context: &CommandContext) -> Result<(), String> {
+    let pos = column_chunk(context);
+    if context.server.chunks.ticket_count(pos, TicketKind::Forced) == 0 {
+        return Err(format!("Chunk {} isn't force loaded", pos));
+    }
+
+    context.server.chunks.remove_ticket(pos, TicketKind::Forced);
+    context.reply(&format!("Chunk {} is no longer force loaded", pos));
+    Ok(())
+}
+
+fn forceload_query(context: &CommandContext) -> Result<(), String> {
+    let forced = context.server.chunks.ticketed(TicketKind::Forced);
+    match forced.is_empty() {
+        true => context.reply("No chunks are force loaded"),
+        false => context.reply(&format!("Force loaded chunks: {}", forced.iter().map(|pos| format!("[{}]", pos)).collect::<Vec<_>>().join(", "))),
+    }
+
+    Ok(())
+}
+
+fn stop(context: &CommandContext) -> Result<(), String> {
+    context.reply("Stopping the server");
+    let _ = context.server.shutdown.send(());
+    Ok(())
+}
\ No newline at end of file
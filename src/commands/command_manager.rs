@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{access::permissions::Permissions, custom_types::game_profile::GameProfile, log, network::packets::play::clientbound::commands::{CommandNodeData, CommandNodeKind, PlayClientboundCommands}, player_list::OnlinePlayer, server::ServerData, utils::{errors::CommandSyntaxError, mojauth::lookup_profile}, CONFIG, LOGGER};
+
+use super::{arguments::{ArgumentValue, BlockPosArgument, EntitySelector}, command_sender::CommandSender, node::{CommandExecutor, CommandNode, NodeKind}, string_reader::StringReader};
+
+pub const ASK_SERVER_SUGGESTIONS: &str = "minecraft:ask_server";
+
+pub struct CommandContext<'a> {
+    pub sender: &'a CommandSender,
+    pub server: &'a ServerData,
+    pub input: &'a str,
+    arguments: HashMap<String, ArgumentValue>,
+}
+
+impl CommandContext<'_> {
+    pub fn reply(&self, message: &str) {
+        self.sender.send_message(message);
+    }
+
+    pub fn argument(&self, name: &str) -> Option<&ArgumentValue> {
+        self.arguments.get(name)
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<&str> {
+        match self.argument(name) {
+            Some(ArgumentValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.argument(name) {
+            Some(ArgumentValue::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_integer(&self, name: &str) -> Option<i32> {
+        match self.argument(name) {
+            Some(ArgumentValue::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_long(&self, name: &str) -> Option<i64> {
+        match self.argument(name) {
+            Some(ArgumentValue::Long(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_double(&self, name: &str) -> Option<f64> {
+        match self.argument(name) {
+            Some(ArgumentValue::Double(value)) => Some(*value),
+            Some(ArgumentValue::Float(value)) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn get_block_pos(&self, name: &str) -> Option<BlockPosArgument> {
+        match self.argument(name) {
+            Some(ArgumentValue::BlockPos(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Resolves an entity argument to the players it matches, failing if there are none.
+    pub fn get_players(&self, name: &str) -> Result<Vec<OnlinePlayer>, String> {
+        let selector = match self.argument(name) {
+            Some(ArgumentValue::Entity(selector) | ArgumentValue::GameProfile(selector)) => selector,
+            _ => return Err(format!("Missing argument '{}'", name)),
+        };
+
+        let players = selector.resolve(self.sender, &self.server.players);
+        if players.is_empty() {
+            return Err("No player was found".to_owned());
+        }
+
+        Ok(players)
+    }
+
+    /// Resolves a game profile argument and hands the profiles to `then` on the tick thread. A plain name doesn't have to
+    /// belong to an online player. Looking one up with Mojang happens on the async pool, so `then` may run a few ticks later.
+    pub fn with_game_profiles(&self, name: &str, then: impl FnOnce(&CommandSender, &ServerData, Vec<GameProfile>) + Send + 'static) -> Result<(), String> {
+        let player_name = match self.argument(name) {
+            Some(ArgumentValue::GameProfile(EntitySelector::Name(player_name))) => player_name,
+            _ => {
+                then(self.sender, self.server, self.get_players(name)?.into_iter().map(|player| player.profile).collect());
+                return Ok(());
+            }
+        };
+
+        if let Some(player) = self.server.players.find_by_name(player_name) {
+            then(self.sender, self.server, vec![player.profile]);
+            return Ok(());
+        }
+
+        if !CONFIG.server.online_mode {
+            then(self.sender, self.server, vec![GameProfile::offline(player_name)]);
+            return Ok(());
+        }
+
+        let sender = self.sender.clone();
+        let player_name = player_name.clone();
+        self.server.scheduler.run_async(move || lookup_game_profile(&player_name), move |server, profile| match profile {
+            Ok(profile) => then(&sender, server, vec![profile]),
+            Err(message) => sender.send_message(&message),
+        });
+
+        Ok(())
+    }
+}
+
+fn lookup_game_profile(name: &str) -> Result<GameProfile, String> {
+    let response = lookup_profile(name).map_err(|_| format!("That player does not exist: {}", name))?;
+    let uuid = Uuid::parse_str(&response.id).map_err(|_| format!("That player does not exist: {}", name))?;
+    Ok(GameProfile::new(uuid, &response.name))
+}
+
+#[derive(Debug)]
+pub struct ParsedCommand {
+    pub executor: CommandExecutor,
+    pub arguments: HashMap<String, ArgumentValue>,
+}
+
+/// Suggestions replacing the input from byte `start` onwards.
+#[derive(Debug, PartialEq)]
+pub struct Suggestions {
+    pub start: usize,
+    pub matches: Vec<String>,
+}
+
+/// Matches a literal at the cursor, which has to be followed by a space or the end of the input.
+fn read_literal(reader: &mut StringReader, literal: &str) -> bool {
+    let remaining = reader.remaining();
+    let matches = remaining.get(..literal.len()).is_some_and(|word| word.eq_ignore_ascii_case(literal))
+        && remaining[literal.len()..].chars().next().is_none_or(|c| c == ' ');
+
+    if matches {
+        reader.set_cursor(reader.cursor() + literal.len());
+    }
+    matches
+}
+
+/// The command tree, the root's children being the commands themselves.
+pub struct CommandManager {
+    root: CommandNode,
+}
+
+impl CommandManager {
+    pub fn new() -> Self {
+        Self {
+            root: CommandNode::new(NodeKind::Root),
+        }
+    }
+
+    /// Registers a command, replacing any command with the same name.
+    pub fn register(&mut self, node: CommandNode) {
+        self.root.add_child(node);
+    }
+
+    /// Registers the default op level of every permission node used in the tree.
+    pub fn register_permissions(&self, permissions: &Permissions) {
+        fn register(node: &CommandNode, permissions: &Permissions) {
+            if let Some(requirement) = &node.requirement {
+                permissions.register_default(&requirement.permission, requirement.op_level);
+            }
+            for child in &node.children {
+                register(child, permissions);
+            }
+        }
+
+        register(&self.root, permissions);
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &CommandNode> {
+        self.root.children.iter()
+    }
+
+    pub fn find(&self, name: &str) -> Option<&CommandNode> {
+        self.root.children.iter().find(|node| node.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn parse(&self, sender: &CommandSender, permissions: &Permissions, input: &str) -> Result<ParsedCommand, CommandSyntaxError> {
+        let mut reader = StringReader::new(input);
+        let mut arguments = HashMap::new();
+        let executor = Self::parse_children(&self.root, &mut reader, sender, permissions, &mut arguments)?;
+
+        Ok(ParsedCommand { executor, arguments })
+    }
+
+    /// Tries every child the sender can use until one of them parses the rest of the input.
+    /// If none does, the error that got the furthest wins.
+    fn parse_children(node: &CommandNode, reader: &mut StringReader, sender: &CommandSender, permissions: &Permissions, arguments: &mut HashMap<String, ArgumentValue>) -> Result<CommandExecutor, CommandSyntaxError> {
+        let start = reader.cursor();
+        let mut furthest_error: Option<CommandSyntaxError> = None;
+        let mut keep_error = |error: CommandSyntaxError| {
+            if furthest_error.as_ref().is_none_or(|furthest| error.cursor > furthest.cursor) {
+                furthest_error = Some(error);
+            }
+        };
+
+        for child in node.visible_children(sender, permissions) {
+            reader.set_cursor(start);
+
+            let value = match &child.kind {
+                NodeKind::Literal(name) => {
+                    if !read_literal(reader, name) { continue; }
+                    None
+                },
+                NodeKind::Argument { argument_type, .. } => match argument_type.parse(reader) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        keep_error(e);
+                        continue;
+                    }
+                },
+                NodeKind::Root => continue,
+            };
+
+            if reader.can_read() && reader.peek() != Some(' ') {
+                keep_error(reader.error("Expected whitespace to end one argument, but found trailing data"));
+                continue;
+            }
+
+            let mut child_arguments = arguments.clone();
+            if let Some(value) = value {
+                child_arguments.insert(child.name().to_owned(), value);
+            }
+
+            let result = if reader.can_read() {
+                reader.skip();
+                Self::parse_children(child, reader, sender, permissions, &mut child_arguments)
+            }
+            else {
+                child.executor.ok_or_else(|| reader.error("Unknown or incomplete command, see below for error"))
+            };
+
+            match result {
+                Ok(executor) => {
+                    *arguments = child_arguments;
+                    return Ok(executor);
+                },
+                Err(e) => keep_error(e),
+            }
+        }
+
+        reader.set_cursor(start);
+        let message = if node.children.is_empty() { "Incorrect argument for command" } else { "Unknown or incomplete command, see below for error" };
+        Err(furthest_error.unwrap_or_else(|| reader.error(message)))
+    }
+
+    pub fn dispatch(&self, sender: &CommandSender, server: &ServerData, line: &str) {
+        let line = line.trim();
+        let input = line.strip_prefix('/').unwrap_or(line);
+        if input.is_empty() { return; }
+
+        if let CommandSender::Player(_) = sender {
+            log!(info, "{} issued server command: /{}", sender.name(), input);
+        }
+
+        let parsed = match self.parse(sender, &server.permissions, input) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                sender.send_message(&e.to_string());
+                return;
+            }
+        };
+
+        let context = CommandContext { sender, server, input, arguments: parsed.arguments };
+        if let Err(message) = (parsed.executor)(&context) {
+            context.reply(&message);
+        }
+    }
+
+    /// Works out what the last, partially typed argument could be completed to.
+    /// `provider` lists the candidates for an argument node.
+    pub fn complete(&self, sender: &CommandSender, permissions: &Permissions, input: &str, provider: &dyn Fn(&CommandNode) -> Vec<String>) -> Suggestions {
+        let mut reader = StringReader::new(input);
+        let mut node = &self.root;
+
+        // Walk down as long as whole arguments followed by a space can be parsed
+        'walk: loop {
+            let start = reader.cursor();
+            for child in node.visible_children(sender, permissions) {
+                reader.set_cursor(start);
+                let matched = match &child.kind {
+                    NodeKind::Literal(name) => read_literal(&mut reader, name),
+                    NodeKind::Argument { argument_type, .. } => argument_type.parse(&mut reader).is_ok(),
+                    NodeKind::Root => false,
+                };
+
+                if matched && reader.peek() == Some(' ') {
+                    reader.skip();
+                    node = child;
+                    continue 'walk;
+                }
+            }
+
+            reader.set_cursor(start);
+            break;
+        }
+
+        let partial = reader.remaining().to_lowercase();
+        let mut matches = Vec::new();
+        for child in node.visible_children(sender, permissions) {
+            let candidates = match &child.kind {
+                NodeKind::Literal(name) => vec![name.clone()],
+                _ => provider(child),
+            };
+
+            for candidate in candidates {
+                if candidate.to_lowercase().starts_with(&partial) && !matches.contains(&candidate) {
+                    matches.push(candidate);
+                }
+            }
+        }
+
+        matches.sort_by_key(|candidate| candidate.to_lowercase());
+        Suggestions { start: reader.cursor(), matches }
+    }
+
+    pub fn suggest(&self, sender: &CommandSender, server: &ServerData, input: &str) -> Suggestions {
+        self.complete(sender, &server.permissions, input, &|node| match &node.kind {
+            NodeKind::Argument { suggestions: Some(provider), .. } => provider(sender, server),
+            NodeKind::Argument { argument_type, .. } => argument_type.suggestions(&server.players),
+            _ => Vec::new(),
+        })
+    }
+
+    /// Builds the Commands packet, leaving out everything the sender isn't allowed to use.
+    pub fn serialize(&self, sender: &CommandSender, permissions: &Permissions) -> PlayClientboundCommands {
+        let mut nodes = Vec::new();
+        let root_index = Self::serialize_node(&self.root, sender, permissions, &mut nodes);
+
+        PlayClientboundCommands { nodes, root_index }
+    }
+
+    fn serialize_node(node: &CommandNode, sender: &CommandSender, permissions: &Permissions, nodes: &mut Vec<CommandNodeData>) -> i32 {
+        let kind = match &node.kind {
+            NodeKind::Root => CommandNodeKind::Root,
+            NodeKind::Literal(name) => CommandNodeKind::Literal(name.clone()),
+            NodeKind::Argument { name, argument_type, suggestions } => CommandNodeKind::Argument {
+                name: name.clone(),
+                parser_id: argument_type.parser_id(),
+                properties: argument_type.properties(),
+                suggestions_type: (suggestions.is_some() || argument_type.needs_server_suggestions()).then(|| ASK_SERVER_SUGGESTIONS.to_owned()),
+            },
+        };
+
+        let index = nodes.len();
+        nodes.push(CommandNodeData { kind, executable: node.executor.is_some(), children: Vec::new() });
+
+        let children = node.visible_children(sender, permissions)
+            .map(|child| Self::serialize_node(child, sender, permissions, nodes))
+            .collect();
+        nodes[index].children = children;
+
+        index as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::commands::{arguments::ArgumentType, node::{argument, command, literal}};
+
+    use crate::utils::test_utils::temp_directory;
+
+    use super::*;
+
+    fn noop(_: &CommandContext) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn other(_: &CommandContext) -> Result<(), String> {
+        Err("other".to_owned())
+    }
+
+    fn manager() -> CommandManager {
+        let mut manager = CommandManager::new();
+        manager.register(command("kick", 3)
+            .then(argument("targets", ArgumentType::players()).executes(noop)
+                .then(argument("reason", ArgumentType::greedy_string()).executes(other))));
+        manager.register(command("whitelist", 3)
+            .then(literal("add").then(argument("targets", ArgumentType::GameProfile).executes(noop)))
+            .then(literal("list").executes(other)));
+        manager.register(literal("give")
+            .then(argument("count", ArgumentType::integer_between(1, 64)).executes(noop)));
+        manager
+    }
+
+    #[test]
+    fn test_parse() {
+        let permissions = Permissions::load(&temp_directory("commands_parse"));
+        let manager = manager();
+        let console = CommandSender::Console;
+
+        let parsed = manager.parse(&console, &permissions, "kick Notch being rude").unwrap();
+        assert!(std::ptr::fn_addr_eq(parsed.executor, other as CommandExecutor));
+        assert_eq!(parsed.arguments.get("reason"), Some(&ArgumentValue::String("being rude".to_owned())));
+        assert_eq!(parsed.arguments.get("targets"), Some(&ArgumentValue::Entity(EntitySelector::Name("Notch".to_owned()))));
+
+        assert!(std::ptr::fn_addr_eq(manager.parse(&console, &permissions, "KICK @a").unwrap().executor, noop as CommandExecutor));
+        assert!(std::ptr::fn_addr_eq(manager.parse(&console, &permissions, "whitelist list").unwrap().executor, other as CommandExecutor));
+
+        let error = manager.parse(&console, &permissions, "give 65").unwrap_err();
+        assert_eq!(error.message, "Integer must not be more than 64, found 65");
+        assert_eq!(error.cursor, 5);
+
+        let error = manager.parse(&console, &permissions, "whitelist").unwrap_err();
+        assert_eq!(error.message, "Unknown or incomplete command, see below for error");
+        assert_eq!(error.cursor, 9);
+
+        assert_eq!(manager.parse(&console, &permissions, "unknown").unwrap_err().cursor, 0);
+        assert_eq!(manager.parse(&console, &permissions, "whitelist list extra").unwrap_err().message, "Incorrect argument for command");
+    }
+
+    #[test]
+    fn test_permissions() {
+        let permissions = Permissions::load(&temp_directory("commands_permissions"));
+        let manager = manager();
+        manager.register_permissions(&permissions);
+
+        let (handle, _commands) = crate::network::connection_handle::ConnectionHandle::new();
+        let player = CommandSender::Player(OnlinePlayer {
+            profile: GameProfile::new(Uuid::new_v4(), "Player"),
+            ip: "127.0.0.1".parse().unwrap(),
+            handle,
+        });
+
+        assert!(manager.parse(&player, &permissions, "kick Notch").is_err());
+        assert!(manager.parse(&player, &permissions, "give 1").is_ok());
+
+        // Only the root and the give command get sent to the player
+        let packet = manager.serialize(&player, &permissions);
+        assert_eq!(packet.nodes.len(), 3);
+
+        if let CommandSender::Player(player) = &player {
+            permissions.op(&player.profile, 3);
+        }
+        assert!(manager.parse(&player, &permissions, "kick Notch").is_ok());
+        assert_eq!(manager.serialize(&player, &permissions).nodes.len(), 10);
+    }
+
+    #[test]
+    fn test_complete() {
+        let permissions = Permissions::load(&temp_directory("commands_complete"));
+        let manager = manager();
+        let console = CommandSender::Console;
+        let provider = |_: &CommandNode| vec!["Notch".to_owned(), "jeb_".to_owned()];
+
+        assert_eq!(manager.complete(&console, &permissions, "", &provider), Suggestions { start: 0, matches: vec!["give".to_owned(), "kick".to_owned(), "whitelist".to_owned()] });
+        assert_eq!(manager.complete(&console, &permissions, "whitelist ", &provider), Suggestions { start: 10, matches: vec!["add".to_owned(), "list".to_owned()] });
+        assert_eq!(manager.complete(&console, &permissions, "whitelist add n", &provider), Suggestions { start: 14, matches: vec!["Notch".to_owned()] });
+        assert_eq!(manager.complete(&console, &permissions, "kick J", &provider), Suggestions { start: 5, matches: vec!["jeb_".to_owned()] });
+    }
+
+    #[test]
+    fn test_serialize() {
+        let permissions = Permissions::load(&temp_directory("commands_serialize"));
+        let packet = manager().serialize(&CommandSender::Console, &permissions);
+
+        assert_eq!(packet.root_index, 0);
+        assert_eq!(packet.nodes[0].children, vec![1, 4, 8]);
+        match &packet.nodes[2].kind {
+            CommandNodeKind::Argument { name, parser_id, suggestions_type, .. } => {
+                assert_eq!(name, "targets");
+                assert_eq!(*parser_id, 6);
+                assert_eq!(suggestions_type.as_deref(), Some(ASK_SERVER_SUGGESTIONS));
+            },
+            _ => panic!("expected an argument node"),
+        }
+        assert!(packet.nodes[2].executable);
+        assert!(!packet.nodes[1].executable);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::{access::permissions::Permissions, log, player_list::OnlinePlayer, LOGGER};
+
+#[derive(Clone)]
+pub enum CommandSender {
+    Console,
+    Player(OnlinePlayer),
+}
+
+impl CommandSender {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Console => "Server".to_owned(),
+            Self::Player(player) => player.profile.name.clone(),
+        }
+    }
+
+    pub fn send_message(&self, message: &str) {
+        match self {
+            Self::Console => {
+                for line in message.lines() {
+                    log!(info, "{}", line);
+                }
+            },
+            Self::Player(player) => player.handle.send_message(message),
+        }
+    }
+
+    /// The console is allowed to do everything.
+    pub fn has_permission(&self, permissions: &Permissions, node: &str) -> bool {
+        match self {
+            Self::Console => true,
+            Self::Player(player) => permissions.has_permission(&player.profile, node),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,6 @@
+pub mod arguments;
+pub mod builtin;
+pub mod command_manager;
+pub mod command_sender;
+pub mod node;
+pub mod string_reader;
\ No newline at end of file
@@ -0,0 +1,135 @@
+use crate::{access::permissions::Permissions, server::ServerData};
+
+use super::{arguments::ArgumentType, command_manager::CommandContext, command_sender::CommandSender};
+
+pub type CommandExecutor = fn(&CommandContext) -> Result<(), String>;
+pub type SuggestionProvider = fn(&CommandSender, &ServerData) -> Vec<String>;
+
+pub enum NodeKind {
+    Root,
+    Literal(String),
+    Argument { name: String, argument_type: ArgumentType, suggestions: Option<SuggestionProvider> },
+}
+
+/// Permission node a command node needs, and the op level it's granted at by default.
+pub struct Requirement {
+    pub permission: String,
+    pub op_level: u8,
+}
+
+/// A node in the command tree, built the same way as with Brigadier:
+/// `literal("kick").then(argument("targets", ArgumentType::players()).executes(kick))`.
+pub struct CommandNode {
+    pub kind: NodeKind,
+    pub children: Vec<CommandNode>,
+    pub executor: Option<CommandExecutor>,
+    pub requirement: Option<Requirement>,
+    pub description: Option<String>,
+}
+
+pub fn literal(name: &str) -> CommandNode {
+    CommandNode::new(NodeKind::Literal(name.to_owned()))
+}
+
+/// A command's root literal, guarded by the `rustcraft.command.<name>` permission node.
+pub fn command(name: &str, op_level: u8) -> CommandNode {
+    literal(name).requires(&format!("rustcraft.command.{}", name), op_level)
+}
+
+pub fn argument(name: &str, argument_type: ArgumentType) -> CommandNode {
+    CommandNode::new(NodeKind::Argument { name: name.to_owned(), argument_type, suggestions: None })
+}
+
+impl CommandNode {
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+            executor: None,
+            requirement: None,
+            description: None,
+        }
+    }
+
+    pub fn then(mut self, child: CommandNode) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    pub fn executes(mut self, executor: CommandExecutor) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    pub fn requires(mut self, permission: &str, op_level: u8) -> Self {
+        self.requirement = Some(Requirement { permission: permission.to_owned(), op_level });
+        self
+    }
+
+    pub fn describe(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    /// Overrides the suggestions of an argument node.
+    pub fn suggests(mut self, provider: SuggestionProvider) -> Self {
+        if let NodeKind::Argument { suggestions, .. } = &mut self.kind {
+            *suggestions = Some(provider);
+        }
+        self
+    }
+
+    /// Adds a child, replacing any existing child with the same name.
+    pub fn add_child(&mut self, child: CommandNode) {
+        self.children.retain(|existing| existing.name() != child.name());
+
+        // Literals are tried before arguments, like in Brigadier
+        let index = match child.kind {
+            NodeKind::Literal(_) => self.children.iter().position(|node| !node.is_literal()).unwrap_or(self.children.len()),
+            _ => self.children.len(),
+        };
+        self.children.insert(index, child);
+    }
+
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            NodeKind::Root => "",
+            NodeKind::Literal(name) | NodeKind::Argument { name, .. } => name,
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        matches!(self.kind, NodeKind::Literal(_))
+    }
+
+    pub fn can_use(&self, sender: &CommandSender, permissions: &Permissions) -> bool {
+        self.requirement.as_ref().is_none_or(|requirement| sender.has_permission(permissions, &requirement.permission))
+    }
+
+    pub fn visible_children<'a>(&'a self, sender: &'a CommandSender, permissions: &'a Permissions) -> impl Iterator<Item = &'a CommandNode> {
+        self.children.iter().filter(move |child| child.can_use(sender, permissions))
+    }
+
+    fn usage_token(&self) -> String {
+        match &self.kind {
+            NodeKind::Argument { name, .. } => format!("<{}>", name),
+            _ => self.name().to_owned(),
+        }
+    }
+
+    /// Short usage text in the style of Brigadier's smart usage, e.g. `whitelist (add|remove|list)`.
+    pub fn usage(&self, sender: &CommandSender, permissions: &Permissions) -> String {
+        let children: Vec<&CommandNode> = self.visible_children(sender, permissions).collect();
+        let (open, close) = if self.executor.is_some() { ("[", "]") } else { ("(", ")") };
+
+        match children.as_slice() {
+            [] => self.usage_token(),
+            [child] if self.executor.is_some() => format!("{} [{}]", self.usage_token(), child.usage(sender, permissions)),
+            [child] => format!("{} {}", self.usage_token(), child.usage(sender, permissions)),
+            children => {
+                let tokens: Vec<String> = children.iter().map(|child| child.usage_token()).collect();
+                format!("{} {}{}{}", self.usage_token(), open, tokens.join("|"), close)
+            }
+        }
+    }
+}
\ No newline at end of file
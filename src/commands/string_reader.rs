@@ -0,0 +1,212 @@
+use crate::utils::errors::CommandSyntaxError;
+
+/// Cursor over a command line, modeled after Brigadier's `StringReader`. The cursor is a byte index.
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.skip();
+        }
+    }
+
+    pub fn error(&self, message: &str) -> CommandSyntaxError {
+        CommandSyntaxError::new(message, self.input, self.cursor)
+    }
+
+    pub fn expect(&mut self, c: char) -> Result<(), CommandSyntaxError> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("Expected '{}'", c)));
+        }
+
+        self.skip();
+        Ok(())
+    }
+
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.cursor;
+        while self.peek().is_some_and(&predicate) {
+            self.skip();
+        }
+
+        &self.input[start..self.cursor]
+    }
+
+    pub fn is_allowed_in_unquoted_string(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+    }
+
+    pub fn read_unquoted_string(&mut self) -> &'a str {
+        self.read_while(Self::is_allowed_in_unquoted_string)
+    }
+
+    /// Reads up to the next space, whatever the characters are.
+    pub fn read_word(&mut self) -> &'a str {
+        self.read_while(|c| c != ' ')
+    }
+
+    pub fn read_quoted_string(&mut self) -> Result<String, CommandSyntaxError> {
+        let quote = match self.peek() {
+            Some(c @ ('"' | '\'')) => c,
+            _ => return Err(self.error("Expected quote to start a string")),
+        };
+        self.skip();
+
+        let mut result = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.skip();
+            if escaped {
+                if c != quote && c != '\\' {
+                    self.cursor -= c.len_utf8();
+                    return Err(self.error(&format!("Invalid escape sequence '{}' in quoted string", c)));
+                }
+                result.push(c);
+                escaped = false;
+            }
+            else if c == '\\' {
+                escaped = true;
+            }
+            else if c == quote {
+                return Ok(result);
+            }
+            else {
+                result.push(c);
+            }
+        }
+
+        Err(self.error("Unclosed quoted string"))
+    }
+
+    /// Reads a quoted string, or an unquoted one if it doesn't start with a quote.
+    pub fn read_string(&mut self) -> Result<String, CommandSyntaxError> {
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted_string(),
+            _ => Ok(self.read_unquoted_string().to_owned()),
+        }
+    }
+
+    fn read_number<T: std::str::FromStr>(&mut self, kind: &str) -> Result<T, CommandSyntaxError> {
+        let start = self.cursor;
+        let number = self.read_while(|c| c.is_ascii_digit() || c == '.' || c == '-');
+        if number.is_empty() {
+            return Err(self.error(&format!("Expected {}", kind)));
+        }
+
+        number.parse().map_err(|_| {
+            self.cursor = start;
+            self.error(&format!("Invalid {} '{}'", kind, number))
+        })
+    }
+
+    pub fn read_int(&mut self) -> Result<i32, CommandSyntaxError> {
+        self.read_number("integer")
+    }
+
+    pub fn read_long(&mut self) -> Result<i64, CommandSyntaxError> {
+        self.read_number("long")
+    }
+
+    pub fn read_float(&mut self) -> Result<f32, CommandSyntaxError> {
+        self.read_number("float")
+    }
+
+    pub fn read_double(&mut self) -> Result<f64, CommandSyntaxError> {
+        self.read_number("double")
+    }
+
+    pub fn read_boolean(&mut self) -> Result<bool, CommandSyntaxError> {
+        let start = self.cursor;
+        match self.read_unquoted_string() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            "" => Err(self.error("Expected bool")),
+            value => {
+                self.cursor = start;
+                Err(self.error(&format!("Invalid bool, expected true or false but found '{}'", value)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_strings() {
+        let mut reader = StringReader::new("hello \"quoted \\\"string\\\"\" 'single' rest of it");
+        assert_eq!(reader.read_string().unwrap(), "hello");
+        reader.skip();
+        assert_eq!(reader.read_string().unwrap(), "quoted \"string\"");
+        reader.skip();
+        assert_eq!(reader.read_string().unwrap(), "single");
+        reader.skip();
+        assert_eq!(reader.remaining(), "rest of it");
+
+        assert!(StringReader::new("\"unclosed").read_quoted_string().is_err());
+        assert!(StringReader::new("\"bad \\escape\"").read_quoted_string().is_err());
+    }
+
+    #[test]
+    fn test_read_numbers() {
+        assert_eq!(StringReader::new("-42 rest").read_int().unwrap(), -42);
+        assert_eq!(StringReader::new("1.5").read_double().unwrap(), 1.5);
+        assert_eq!(StringReader::new("9000000000").read_long().unwrap(), 9_000_000_000);
+
+        let error = StringReader::new("1.5").read_int().unwrap_err();
+        assert_eq!(error.message, "Invalid integer '1.5'");
+        assert_eq!(error.cursor, 0);
+        assert_eq!(StringReader::new("abc").read_int().unwrap_err().message, "Expected integer");
+    }
+
+    #[test]
+    fn test_read_boolean() {
+        assert!(StringReader::new("true").read_boolean().unwrap());
+        assert!(!StringReader::new("false").read_boolean().unwrap());
+        assert!(StringReader::new("yes").read_boolean().is_err());
+    }
+
+    #[test]
+    fn test_error_context() {
+        let error = CommandSyntaxError::new("Unknown command", "gamemode creative Notch", 18);
+        assert_eq!(error.to_string(), "Unknown command\n... creative <--[HERE]");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,51 @@
+use std::thread;
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::{commands::command_sender::CommandSender, log, server::ServerData, LOGGER};
+
+pub const HISTORY_FILE: &str = ".console_history";
+
+/// Reads commands from stdin on its own thread. Log lines are printed above the prompt while it's active.
+pub fn start_console(server_data: ServerData) {
+    thread::spawn(move || {
+        let mut editor = match DefaultEditor::new() {
+            Ok(editor) => editor,
+            Err(e) => {
+                log!(error, "Failed to start the console: {}", e);
+                return;
+            }
+        };
+
+        let _ = editor.load_history(HISTORY_FILE);
+        if let Ok(printer) = editor.create_external_printer() {
+            LOGGER.set_printer(Some(Box::new(printer)));
+        }
+
+        loop {
+            match editor.readline("> ") {
+                Ok(line) => {
+                    if line.trim().is_empty() { continue; }
+
+                    let _ = editor.add_history_entry(line.as_str());
+                    let _ = editor.append_history(HISTORY_FILE);
+                    server_data.run_on_tick(move |server| server.commands.dispatch(&CommandSender::Console, server, &line));
+                },
+                Err(ReadlineError::Interrupted) => {
+                    let _ = server_data.shutdown.send(());
+                    break;
+                },
+                Err(ReadlineError::Eof) => {
+                    log!(info, "Console input closed");
+                    break;
+                },
+                Err(e) => {
+                    log!(error, "Failed to read console input: {}", e);
+                    break;
+                }
+            }
+        }
+
+        LOGGER.set_printer(None);
+    });
+}
\ No newline at end of file
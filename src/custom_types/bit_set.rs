@@ -0,0 +1,52 @@
+/// A growable set of bits, sent as its longs like Java's `BitSet`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.words.get(index / 64).is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        let word = index / 64;
+        if word >= self.words.len() {
+            if !value { return; }
+            self.words.resize(word + 1, 0);
+        }
+
+        match value {
+            true => self.words[word] |= 1 << (index % 64),
+            false => self.words[word] &= !(1 << (index % 64)),
+        }
+    }
+
+    /// The longs up to the last one with a bit set.
+    pub fn words(&self) -> &[u64] {
+        let length = self.words.iter().rposition(|&word| word != 0).map_or(0, |last| last + 1);
+        &self.words[..length]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_set() {
+        let mut bits = BitSet::new();
+        bits.set(3, true);
+        bits.set(70, true);
+        assert!(bits.get(3) && bits.get(70) && !bits.get(4) && !bits.get(1000));
+        assert_eq!(bits.words(), &[1 << 3, 1 << 6]);
+
+        bits.set(70, false);
+        bits.set(500, false);
+        assert_eq!(bits.words(), &[1 << 3]);
+    }
+}
\ No newline at end of file
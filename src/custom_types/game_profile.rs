@@ -0,0 +1,44 @@
+use std::fmt;
+
+use md5::{Digest, Md5};
+use uuid::{Builder, Uuid};
+
+/// The identity of a player that finished logging in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameProfile {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+impl GameProfile {
+    pub fn new(uuid: Uuid, name: &str) -> Self {
+        Self {
+            uuid,
+            name: name.to_owned(),
+        }
+    }
+
+    /// The profile vanilla uses for `name` in offline mode (`UUID.nameUUIDFromBytes("OfflinePlayer:" + name)`).
+    pub fn offline(name: &str) -> Self {
+        let hash: [u8; 16] = Md5::new().chain_update(format!("OfflinePlayer:{}", name)).finalize().into();
+        Self::new(Builder::from_md5_bytes(hash).into_uuid(), name)
+    }
+}
+
+impl fmt::Display for GameProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.name, self.uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid() {
+        // Same UUIDs vanilla gives these players in offline mode
+        assert_eq!(GameProfile::offline("Notch").uuid.to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+        assert_eq!(GameProfile::offline("jeb_").uuid.to_string(), "a762f560-4fce-3236-812a-b80efff0b62b");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+/// Where an entity is and which way it's looking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Location {
+    pub fn new(x: f64, y: f64, z: f64, yaw: f32, pitch: f32) -> Self {
+        Self { x, y, z, yaw, pitch }
+    }
+
+    pub fn with_position(&self, x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z, ..*self }
+    }
+
+    pub fn with_rotation(&self, yaw: f32, pitch: f32) -> Self {
+        Self { yaw, pitch, ..*self }
+    }
+}
\ No newline at end of file
@@ -1,3 +1,6 @@
+pub mod bit_set;
 pub mod bitmasks;
+pub mod game_profile;
 pub mod identifier;
+pub mod location;
 pub mod position;
\ No newline at end of file
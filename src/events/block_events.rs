@@ -0,0 +1,67 @@
+use crate::{custom_types::position::Position, player_list::OnlinePlayer};
+
+use super::event_bus::Event;
+
+/// A player finished breaking a block. Cancelling it keeps the block in place.
+pub struct BlockBreakEvent {
+    pub player: OnlinePlayer,
+    pub position: Position,
+    cancelled: bool,
+}
+
+impl BlockBreakEvent {
+    pub fn new(player: OnlinePlayer, position: Position) -> Self {
+        Self { player, position, cancelled: false }
+    }
+}
+
+impl Event for BlockBreakEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// A player right-clicked a block, to use it or to place what they're holding against it.
+/// The server doesn't track held items yet, so it never places anything by itself; handlers can at `placed_position`.
+pub struct BlockInteractEvent {
+    pub player: OnlinePlayer,
+    pub position: Position,
+    /// The face that was clicked, in the protocol's order: down, up, north, south, west, east.
+    pub face: i32,
+    pub off_hand: bool,
+    cancelled: bool,
+}
+
+impl BlockInteractEvent {
+    pub fn new(player: OnlinePlayer, position: Position, face: i32, off_hand: bool) -> Self {
+        Self { player, position, face, off_hand, cancelled: false }
+    }
+
+    /// Where a block placed against the clicked face ends up.
+    pub fn placed_position(&self) -> Position {
+        let (x, y, z) = (self.position.x(), self.position.y(), self.position.z());
+        match self.face {
+            0 => Position::new(x, y - 1, z),
+            1 => Position::new(x, y + 1, z),
+            2 => Position::new(x, y, z - 1),
+            3 => Position::new(x, y, z + 1),
+            4 => Position::new(x - 1, y, z),
+            5 => Position::new(x + 1, y, z),
+            _ => self.position,
+        }
+    }
+}
+
+impl Event for BlockInteractEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
\ No newline at end of file
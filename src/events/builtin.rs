@@ -0,0 +1,15 @@
+use crate::{log, utils::packet_utils::read_string, LOGGER};
+
+use super::{connection_events::PluginMessageEvent, event_bus::{EventBus, EventPriority}};
+
+/// Handlers for what the server does with events by itself.
+pub fn register_all(events: &EventBus) {
+    events.register(EventPriority::Monitor, |event: &mut PluginMessageEvent| {
+        if event.channel == "minecraft:brand" {
+            match read_string(&mut event.data.as_slice()) {
+                Ok(brand) => log!(verbose, "{}'s brand is '{}'", event.player.profile.name, brand),
+                Err(e) => log!(debug, "{} sent an invalid brand: {}", event.player.profile.name, e),
+            }
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::net::IpAddr;
+
+use uuid::Uuid;
+
+use crate::{custom_types::game_profile::GameProfile, player_list::OnlinePlayer};
+
+use super::event_bus::Event;
+
+/// A client asked for the server list entry. Cancelling it sends no response at all.
+pub struct StatusPingEvent {
+    pub address: IpAddr,
+    pub motd: String,
+    pub version_name: String,
+    pub protocol: i32,
+    pub max_players: i32,
+    pub online_players: usize,
+    /// The players shown when hovering over the player count.
+    pub sample: Vec<GameProfile>,
+    cancelled: bool,
+}
+
+impl StatusPingEvent {
+    pub fn new(address: IpAddr, motd: String, version_name: String, protocol: i32, max_players: i32, online_players: usize, sample: Vec<GameProfile>) -> Self {
+        Self { address, motd, version_name, protocol, max_players, online_players, sample, cancelled: false }
+    }
+}
+
+impl Event for StatusPingEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// A client sent Login Start. The name and UUID are what the client claims, they aren't authenticated yet.
+/// `kick_message` already holds the server's own verdict, e.g. for a banned IP.
+pub struct PlayerPreLoginEvent {
+    pub name: String,
+    pub uuid: Uuid,
+    pub address: IpAddr,
+    pub kick_message: Option<String>,
+}
+
+/// A player was authenticated and is about to be added to the player list.
+/// `kick_message` already holds the server's own verdict for bans, the whitelist and the player limit.
+pub struct PlayerLoginEvent {
+    pub profile: GameProfile,
+    pub address: IpAddr,
+    pub kick_message: Option<String>,
+}
+
+macro_rules! impl_login_result {
+    ($event:ty) => {
+        impl $event {
+            pub fn allow(&mut self) {
+                self.kick_message = None;
+            }
+
+            pub fn disallow(&mut self, message: &str) {
+                self.kick_message = Some(message.to_owned());
+            }
+
+            pub fn is_allowed(&self) -> bool {
+                self.kick_message.is_none()
+            }
+        }
+
+        impl Event for $event {}
+    };
+}
+
+impl_login_result!(PlayerPreLoginEvent);
+impl_login_result!(PlayerLoginEvent);
+
+/// A plugin message from a client, during configuration or play.
+pub struct PluginMessageEvent {
+    pub player: OnlinePlayer,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl Event for PluginMessageEvent {}
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::{any::{Any, TypeId}, collections::HashMap, sync::{atomic::{AtomicU64, Ordering}, Arc, RwLock}};
+
+/// Something that happened which handlers can react to, and for cancellable events, veto.
+pub trait Event: Any + Send {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    /// Does nothing for events that can't be cancelled.
+    fn set_cancelled(&mut self, _cancelled: bool) {}
+}
+
+/// Handlers run from `Lowest` to `Monitor`, so higher priorities get the final say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventPriority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    Highest,
+    /// Only looks at the outcome, and gets called even for cancelled events. Shouldn't change anything.
+    Monitor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(u64);
+
+type Handler = Arc<dyn Fn(&mut dyn Any) + Send + Sync>;
+
+struct RegisteredHandler {
+    id: HandlerId,
+    priority: EventPriority,
+    handler: Handler,
+}
+
+/// Hands events to the handlers registered for their type. Events are posted from whichever thread they happen on,
+/// mostly the tick thread, so handlers have to be quick.
+pub struct EventBus {
+    handlers: RwLock<HashMap<TypeId, Vec<RegisteredHandler>>>,
+    next_id: AtomicU64,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            handlers: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn register<E: Event>(&self, priority: EventPriority, handler: impl Fn(&mut E) + Send + Sync + 'static) -> HandlerId {
+        let id = HandlerId(self.next_id.fetch_add(1, Ordering::SeqCst));
+        let handler: Handler = Arc::new(move |event: &mut dyn Any| {
+            if let Some(event) = event.downcast_mut::<E>() {
+                handler(event);
+            }
+        });
+
+        let mut handlers = self.handlers.write().unwrap();
+        let handlers = handlers.entry(TypeId::of::<E>()).or_default();
+        // Handlers with the same priority run in the order they were registered
+        let index = handlers.partition_point(|registered| registered.priority <= priority);
+        handlers.insert(index, RegisteredHandler { id, priority, handler });
+        id
+    }
+
+    /// Returns `false` if there was no such handler.
+    pub fn unregister(&self, id: HandlerId) -> bool {
+        let mut handlers = self.handlers.write().unwrap();
+        for registered in handlers.values_mut() {
+            if let Some(index) = registered.iter().position(|handler| handler.id == id) {
+                registered.remove(index);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Runs the handlers for `event`. Once it's cancelled, only `Monitor` handlers see it.
+    pub fn post<E: Event>(&self, event: &mut E) {
+        // Copied out, so handlers can register handlers themselves
+        let handlers: Vec<(EventPriority, Handler)> = match self.handlers.read().unwrap().get(&TypeId::of::<E>()) {
+            Some(handlers) => handlers.iter().map(|registered| (registered.priority, Arc::clone(&registered.handler))).collect(),
+            None => return,
+        };
+
+        for (priority, handler) in handlers {
+            if event.is_cancelled() && priority != EventPriority::Monitor { continue; }
+            handler(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    struct TestEvent {
+        calls: Vec<&'static str>,
+        cancelled: bool,
+    }
+
+    impl Event for TestEvent {
+        fn is_cancelled(&self) -> bool {
+            self.cancelled
+        }
+
+        fn set_cancelled(&mut self, cancelled: bool) {
+            self.cancelled = cancelled;
+        }
+    }
+
+    struct OtherEvent;
+
+    impl Event for OtherEvent {}
+
+    fn post(bus: &EventBus) -> TestEvent {
+        let mut event = TestEvent { calls: Vec::new(), cancelled: false };
+        bus.post(&mut event);
+        event
+    }
+
+    #[test]
+    fn test_priorities() {
+        let bus = EventBus::new();
+        bus.register(EventPriority::Monitor, |event: &mut TestEvent| event.calls.push("monitor"));
+        bus.register(EventPriority::High, |event: &mut TestEvent| event.calls.push("high"));
+        bus.register(EventPriority::Lowest, |event: &mut TestEvent| event.calls.push("lowest"));
+        bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("normal 1"));
+        bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("normal 2"));
+        bus.register(EventPriority::Normal, |_: &mut OtherEvent| panic!("wrong event type"));
+
+        assert_eq!(post(&bus).calls, vec!["lowest", "normal 1", "normal 2", "high", "monitor"]);
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let bus = EventBus::new();
+        bus.register(EventPriority::Low, |event: &mut TestEvent| event.set_cancelled(true));
+        bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("normal"));
+        bus.register(EventPriority::Monitor, |event: &mut TestEvent| event.calls.push("monitor"));
+
+        let event = post(&bus);
+        assert!(event.is_cancelled());
+        assert_eq!(event.calls, vec!["monitor"]);
+    }
+
+    #[test]
+    fn test_unregister() {
+        let bus = EventBus::new();
+        let first = bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("first"));
+        bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("second"));
+
+        assert!(bus.unregister(first));
+        assert!(!bus.unregister(first));
+        assert_eq!(post(&bus).calls, vec!["second"]);
+    }
+
+    #[test]
+    fn test_register_from_handler() {
+        let bus = Arc::new(EventBus::new());
+        let registered = Arc::new(Mutex::new(false));
+
+        let inner_bus = Arc::clone(&bus);
+        let inner_registered = Arc::clone(&registered);
+        bus.register(EventPriority::Normal, move |_: &mut OtherEvent| {
+            let mut registered = inner_registered.lock().unwrap();
+            if !*registered {
+                inner_bus.register(EventPriority::Normal, |event: &mut TestEvent| event.calls.push("late"));
+                *registered = true;
+            }
+        });
+
+        bus.post(&mut OtherEvent);
+        assert_eq!(post(&bus).calls, vec!["late"]);
+    }
+}
\ No newline at end of file
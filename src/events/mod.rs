@@ -0,0 +1,6 @@
+pub mod block_events;
+pub mod builtin;
+pub mod connection_events;
+pub mod event_bus;
+pub mod player_events;
+pub mod tick_events;
\ No newline at end of file
@@ -0,0 +1,66 @@
+use crate::{custom_types::location::Location, player_list::OnlinePlayer};
+
+use super::event_bus::Event;
+
+/// A player finished configuration and entered the game. A `join_message` is logged and broadcast.
+pub struct PlayerJoinEvent {
+    pub player: OnlinePlayer,
+    pub join_message: Option<String>,
+}
+
+impl Event for PlayerJoinEvent {}
+
+/// A player that had joined the game left. A `quit_message` is logged and broadcast to the remaining players.
+pub struct PlayerQuitEvent {
+    pub player: OnlinePlayer,
+    pub quit_message: Option<String>,
+}
+
+impl Event for PlayerQuitEvent {}
+
+/// A player sent a chat message. Cancelling it keeps it from being broadcast.
+pub struct PlayerChatEvent {
+    pub player: OnlinePlayer,
+    pub message: String,
+    cancelled: bool,
+}
+
+impl PlayerChatEvent {
+    pub fn new(player: OnlinePlayer, message: String) -> Self {
+        Self { player, message, cancelled: false }
+    }
+}
+
+impl Event for PlayerChatEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// A player moved or turned. Cancelling it teleports them back to `from`, changing `to` teleports them there.
+pub struct PlayerMoveEvent {
+    pub player: OnlinePlayer,
+    pub from: Location,
+    pub to: Location,
+    cancelled: bool,
+}
+
+impl PlayerMoveEvent {
+    pub fn new(player: OnlinePlayer, from: Location, to: Location) -> Self {
+        Self { player, from, to, cancelled: false }
+    }
+}
+
+impl Event for PlayerMoveEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,16 @@
+use super::event_bus::Event;
+
+/// Posted on the tick thread every tick, after queued packets and tasks ran. Hook world updates in here.
+pub struct WorldTickEvent {
+    /// Ticks since the server started.
+    pub tick: u64,
+}
+
+impl Event for WorldTickEvent {}
+
+/// Posted on the tick thread every tick, right after `WorldTickEvent`. Hook entity updates in here.
+pub struct EntityTickEvent {
+    pub tick: u64,
+}
+
+impl Event for EntityTickEvent {}
\ No newline at end of file
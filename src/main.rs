@@ -1,21 +1,28 @@
 #![allow(unused)]
+mod access;
+mod commands;
+mod console;
 mod crypto;
 mod custom_types;
+mod events;
 mod network;
+mod player_list;
+mod plugins;
 mod utils;
 mod server;
-
-use std::thread;
+mod tick;
+mod world;
 
 use chrono::Local;
 use crossbeam_channel::{bounded, select, Receiver};
 use once_cell::sync::Lazy;
-use utils::{config::{read_config, write_default_config, Config}, logger::{LogLevel, Logger}};
+use utils::{config::{read_config, write_default_config, Config}, logger::{LogLevel, Logger}, metrics::{start_metrics_server, Metrics}};
 use server::MinecraftServer;
 
 pub const VERSION: &str = "1.21";
 pub const PROTOCOL_VERSION: i32 = 767;
 pub const SESSION_HOST: &str = "https://sessionserver.mojang.com";
+pub const API_HOST: &str = "https://api.mojang.com";
 
 pub static CONFIG: Lazy<Config> = Lazy::new(|| {
     println!("Loading config.toml...");
@@ -26,13 +33,23 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| {
     read_config("config.toml").expect("Config file missing.")
 });
 
+#[cfg(not(test))]
 pub static LOGGER: Lazy<Logger> = Lazy::new(|| {
-    let mut logger = Logger::new(&format!("logs/{}.log", Local::now().format("%Y-%m-%d-%H-%M-%S")), LogLevel::Info);
+    let logger = Logger::new(&format!("logs/{}.log", Local::now().format("%Y-%m-%d-%H-%M-%S")), LogLevel::Info);
     let level = CONFIG.misc.log_level.clone();
     logger.set_level(level);
     logger
 });
 
+/// Tests log to the temp directory, without reading `config.toml`.
+#[cfg(test)]
+pub static LOGGER: Lazy<Logger> = Lazy::new(|| {
+    let path = std::env::temp_dir().join(format!("rustcraft_tests_{}.log", std::process::id()));
+    Logger::new(&path.to_string_lossy(), LogLevel::Debug)
+});
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
 fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
     let (sender, receiver) = bounded(100);
     ctrlc::set_handler(move || {
@@ -44,21 +61,29 @@ fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
 
 fn main() -> std::io::Result<()> {
     log!(info, "RustCraft Server ({} {}; Protocol {}) starting...", CONFIG.status.version_prefix, VERSION, PROTOCOL_VERSION);
-    log!(info, "Ctrl+C to exit");
+    log!(info, "Type \"stop\" or press Ctrl+C to exit");
 
-    let server = MinecraftServer::new(&CONFIG.server.ip, CONFIG.server.port);
-
-    thread::spawn(move || { server.start_listening() });
+    if CONFIG.metrics.enabled {
+        start_metrics_server(format!("{}:{}", CONFIG.metrics.ip, CONFIG.metrics.port));
+    }
 
     let ctrl_c_events = ctrl_channel().unwrap();
+    let mut server = MinecraftServer::new(&CONFIG.server.ip, CONFIG.server.port);
+    let shutdown_requests = server.shutdown_requests();
+    console::start_console(server.server_data().clone());
+
+    server.start_ticking();
+    server.start_listening();
 
     select! {
-        recv(ctrl_c_events) -> _ => {
-            println!();
-            log!(info, "Server closing...");
-        }
+        recv(ctrl_c_events) -> _ => println!(),
+        recv(shutdown_requests) -> _ => {},
     }
 
+    server.shutdown();
+
+    LOGGER.set_printer(None);
+
     println!("Goodbye!");
 
     Ok(())
@@ -0,0 +1,251 @@
+use std::{collections::{HashSet, VecDeque}, time::Instant};
+
+use crate::{tick::tick_stats::TICK_INTERVAL, world::chunk::ChunkPos};
+
+/// Chunks per tick until the client tells us how many it can handle, the same as vanilla.
+const INITIAL_CHUNKS_PER_TICK: f32 = 9.0;
+const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+/// Batches that may be in flight once the client acknowledged its first one.
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+/// Vanilla never goes below this, whatever the player set.
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
+/// The chunks around `center` out to `radius`, ring by ring, starting in the middle.
+pub fn spiral(center: ChunkPos, radius: i32) -> impl Iterator<Item = ChunkPos> {
+    std::iter::once(center).chain((1..=radius).flat_map(move |ring| {
+        let side = 2 * ring;
+        (0..4 * side).map(move |step| {
+            let (dx, dz) = match step / side {
+                0 => (-ring + step % side, -ring),
+                1 => (ring, -ring + step % side),
+                2 => (ring - step % side, ring),
+                _ => (-ring, ring - step % side),
+            };
+            ChunkPos::new(center.x + dx, center.z + dz)
+        })
+    }))
+}
+
+/// How the chunks in view changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct ViewChange {
+    /// Newly in view, nearest first.
+    pub entered: Vec<ChunkPos>,
+    /// No longer in view.
+    pub left: Vec<ChunkPos>,
+    /// Chunks the client has that left the view, which it should forget.
+    pub unloaded: Vec<ChunkPos>,
+}
+
+/// Which chunks a player has and which they still need, and how fast to send them.
+/// Chunks go out in batches that the client acknowledges, telling us how many chunks per tick it keeps up with.
+pub struct ChunkTracker {
+    /// Unknown until the player's position is.
+    center: Option<ChunkPos>,
+    view_distance: u8,
+    sent: HashSet<ChunkPos>,
+    /// In view but not sent yet, nearest first.
+    pending: VecDeque<ChunkPos>,
+    chunks_per_tick: f32,
+    batch_quota: f32,
+    unacknowledged_batches: u32,
+    max_unacknowledged_batches: u32,
+    next_tick: Instant,
+}
+
+impl ChunkTracker {
+    pub fn new(view_distance: u8, now: Instant) -> Self {
+        Self {
+            center: None,
+            view_distance: view_distance.max(MIN_VIEW_DISTANCE),
+            sent: HashSet::new(),
+            pending: VecDeque::new(),
+            chunks_per_tick: INITIAL_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            // Only one batch until the client answers, so a slow client isn't flooded right away
+            max_unacknowledged_batches: 1,
+            next_tick: now,
+        }
+    }
+
+    pub fn center(&self) -> Option<ChunkPos> {
+        self.center
+    }
+
+    pub fn view_distance(&self) -> u8 {
+        self.view_distance
+    }
+
+    pub fn has_chunk(&self, pos: ChunkPos) -> bool {
+        self.sent.contains(&pos)
+    }
+
+    /// Every chunk in view, nearest first.
+    pub fn view(&self) -> Vec<ChunkPos> {
+        match self.center {
+            Some(center) => spiral(center, self.view_distance as i32).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn set_center(&mut self, center: ChunkPos) -> ViewChange {
+        let before = self.view();
+        self.center = Some(center);
+        self.refresh(before)
+    }
+
+    pub fn set_view_distance(&mut self, view_distance: u8) -> ViewChange {
+        let before = self.view();
+        self.view_distance = view_distance.max(MIN_VIEW_DISTANCE);
+        self.refresh(before)
+    }
+
+    fn in_view(&self, pos: ChunkPos) -> bool {
+        let Some(center) = self.center else { return false; };
+        let distance = self.view_distance as i32;
+        (pos.x - center.x).abs() <= distance && (pos.z - center.z).abs() <= distance
+    }
+
+    fn refresh(&mut self, before: Vec<ChunkPos>) -> ViewChange {
+        let view = self.view();
+        let previous: HashSet<ChunkPos> = before.iter().copied().collect();
+        let entered = view.iter().copied().filter(|pos| !previous.contains(pos)).collect();
+
+        let mut left: Vec<ChunkPos> = before.into_iter().filter(|pos| !self.in_view(*pos)).collect();
+        left.sort_by_key(|pos| (pos.x, pos.z));
+        let unloaded: Vec<ChunkPos> = left.iter().copied().filter(|pos| self.sent.remove(pos)).collect();
+
+        self.pending = view.into_iter().filter(|pos| !self.sent.contains(pos)).collect();
+        ViewChange { entered, left, unloaded }
+    }
+
+    /// The chunks to send as one batch at `now`, at most one batch per tick. Only chunks that are `ready` are taken,
+    /// the others wait for a later batch. They count as sent right away.
+    pub fn poll(&mut self, now: Instant, ready: impl Fn(ChunkPos) -> bool) -> Vec<ChunkPos> {
+        if now < self.next_tick || self.pending.is_empty() || self.unacknowledged_batches >= self.max_unacknowledged_batches {
+            return Vec::new();
+        }
+        self.next_tick = now + TICK_INTERVAL;
+
+        self.batch_quota = (self.batch_quota + self.chunks_per_tick).min(self.chunks_per_tick.max(1.0));
+        if self.batch_quota < 1.0 { return Vec::new(); }
+
+        let count = self.batch_quota as usize;
+        let mut batch = Vec::new();
+        self.pending.retain(|pos| {
+            let take = batch.len() < count && ready(*pos);
+            if take { batch.push(*pos); }
+            !take
+        });
+        if batch.is_empty() { return batch; }
+
+        self.sent.extend(&batch);
+        self.batch_quota -= batch.len() as f32;
+        self.unacknowledged_batches += 1;
+        batch
+    }
+
+    /// The client received a batch and can take `chunks_per_tick` from now on.
+    pub fn batch_received(&mut self, chunks_per_tick: f32) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.chunks_per_tick = if chunks_per_tick.is_nan() { 0.01 } else { chunks_per_tick.clamp(0.01, MAX_CHUNKS_PER_TICK) };
+        if self.unacknowledged_batches == 0 {
+            self.batch_quota = 1.0;
+        }
+        self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spiral() {
+        let chunks: Vec<ChunkPos> = spiral(ChunkPos::new(3, -2), 2).collect();
+        assert_eq!(chunks.len(), 25);
+        assert_eq!(chunks.iter().collect::<HashSet<_>>().len(), 25);
+        assert_eq!(chunks[0], ChunkPos::new(3, -2));
+        // Every ring comes after the ones inside it
+        assert!(chunks[1..9].iter().all(|pos| (pos.x - 3).abs().max((pos.z + 2).abs()) == 1));
+        assert!(chunks[9..].iter().all(|pos| (pos.x - 3).abs().max((pos.z + 2).abs()) == 2));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let start = Instant::now();
+        let mut tracker = ChunkTracker::new(10, start);
+        assert!(tracker.poll(start, |_| true).is_empty());
+
+        tracker.set_center(ChunkPos::new(0, 0));
+        let first = tracker.poll(start, |_| true);
+        assert_eq!(first.len(), 9);
+        assert_eq!(first[0], ChunkPos::new(0, 0));
+        assert!(tracker.has_chunk(ChunkPos::new(1, 1)));
+
+        // Waits for the client to acknowledge the first batch, however much time passes
+        assert!(tracker.poll(start + TICK_INTERVAL * 10, |_| true).is_empty());
+        tracker.batch_received(25.0);
+        assert_eq!(tracker.poll(start + TICK_INTERVAL * 11, |_| true).len(), 25);
+        // Once per tick
+        assert!(tracker.poll(start + TICK_INTERVAL * 11, |_| true).is_empty());
+        assert_eq!(tracker.poll(start + TICK_INTERVAL * 12, |_| true).len(), 25);
+
+        let mut tracker = ChunkTracker::new(10, start);
+        tracker.set_center(ChunkPos::new(0, 0));
+        tracker.poll(start, |_| true);
+        tracker.batch_received(0.5);
+        let sent: usize = (1..=4).map(|tick| tracker.poll(start + TICK_INTERVAL * tick, |_| true).len()).sum();
+        assert_eq!(sent, 2);
+    }
+
+    #[test]
+    fn test_waits_for_chunks() {
+        let start = Instant::now();
+        let mut tracker = ChunkTracker::new(2, start);
+        assert_eq!(tracker.set_center(ChunkPos::new(0, 0)).entered, tracker.view());
+        tracker.batch_received(64.0);
+
+        // Chunks that aren't loaded yet are skipped, not dropped
+        let batch = tracker.poll(start, |pos| pos.x >= 0);
+        assert_eq!(batch.len(), 15);
+        assert!(tracker.poll(start + TICK_INTERVAL, |_| false).is_empty());
+        assert_eq!(tracker.poll(start + TICK_INTERVAL * 2, |_| true).len(), 10);
+
+        // Chunks that were never sent leave the view without being unloaded
+        let mut tracker = ChunkTracker::new(2, start);
+        tracker.set_center(ChunkPos::new(0, 0));
+        let change = tracker.set_center(ChunkPos::new(10, 10));
+        assert_eq!((change.entered.len(), change.left.len(), change.unloaded.len()), (25, 25, 0));
+    }
+
+    #[test]
+    fn test_move_and_view_distance() {
+        let start = Instant::now();
+        let mut tracker = ChunkTracker::new(1, start);
+        assert_eq!(tracker.view_distance(), MIN_VIEW_DISTANCE);
+
+        tracker.set_center(ChunkPos::new(0, 0));
+        tracker.batch_received(64.0);
+        assert_eq!(tracker.poll(start, |_| true).len(), 25);
+        assert!(tracker.poll(start + TICK_INTERVAL, |_| true).is_empty());
+
+        // Moving one chunk east drops the west column and needs the new east one
+        let change = tracker.set_center(ChunkPos::new(1, 0));
+        assert_eq!(change.left, (-2..=2).map(|z| ChunkPos::new(-2, z)).collect::<Vec<_>>());
+        assert_eq!(change.unloaded, change.left);
+        assert_eq!(change.entered.len(), 5);
+        let new = tracker.poll(start + TICK_INTERVAL * 2, |_| true);
+        assert_eq!(new, change.entered);
+        assert!(new.iter().all(|pos| pos.x == 3));
+
+        let change = tracker.set_view_distance(3);
+        assert!(change.left.is_empty());
+        assert_eq!(change.entered.len(), 49 - 25);
+        assert_eq!(tracker.poll(start + TICK_INTERVAL * 3, |_| true).len(), 49 - 25);
+        assert_eq!(tracker.set_view_distance(2).unloaded.len(), 49 - 25);
+        assert!(!tracker.has_chunk(ChunkPos::new(4, 3)));
+        assert!(tracker.has_chunk(ChunkPos::new(3, 2)));
+    }
+}
\ No newline at end of file
@@ -1,6 +1,5 @@
 use rand::Rng;
 use rand::thread_rng;
-use bytes::BytesMut;
 use json::object;
 use rsa::Pkcs1v15Encrypt;
 use rsa::pkcs8::EncodePublicKey;
@@ -14,13 +13,68 @@ use crate::crypto::aes_util::SimpleEncryptor;
 use crate::network::packets::configuration::clientbound::finish_configuration::ConfigurationClientboundFinishConfiguration;
 use crate::network::packets::login::clientbound::login_success::LoginClientboundLoginSuccess;
 use crate::network::packets::login::clientbound::login_success::LoginSuccessProperty;
+use crate::network::packets::play::clientbound::disconnect::PlayClientboundDisconnect;
+use crate::network::packets::play::clientbound::keep_alive::PlayClientboundKeepAlive;
+use crate::network::packets::play::clientbound::login::PlayClientboundLogin;
+use crate::network::packets::play::clientbound::player_info_remove::PlayClientboundPlayerInfoRemove;
+use crate::network::packets::play::clientbound::player_info_update::{ACTION_ADD_PLAYER, ACTION_UPDATE_LATENCY, ACTION_UPDATE_LISTED};
+use crate::network::packets::play::clientbound::system_chat_message::PlayClientboundSystemChatMessage;
+use crate::network::packets::play::clientbound::command_suggestions_response::PlayClientboundCommandSuggestionsResponse;
+use crate::network::packets::play::clientbound::acknowledge_block_change::PlayClientboundAcknowledgeBlockChange;
+use crate::network::packets::play::clientbound::block_update::PlayClientboundBlockUpdate;
+use crate::network::packets::play::clientbound::synchronize_player_position::PlayClientboundSynchronizePlayerPosition;
+use crate::network::packets::play::clientbound::chunk_batch_finished::PlayClientboundChunkBatchFinished;
+use crate::network::packets::play::clientbound::chunk_batch_start::PlayClientboundChunkBatchStart;
+use crate::network::packets::play::clientbound::chunk_data_and_update_light::PlayClientboundChunkDataAndUpdateLight;
+use crate::network::packets::play::clientbound::set_center_chunk::PlayClientboundSetCenterChunk;
+use crate::network::packets::play::clientbound::unload_chunk::PlayClientboundUnloadChunk;
+use crate::network::packets::play::clientbound::update_light::PlayClientboundUpdateLight;
+use crate::network::packets::play::serverbound::chunk_batch_received::PlayServerboundChunkBatchReceived;
+use crate::network::packets::play::serverbound::client_information::PlayServerboundClientInformation;
+use crate::network::packets::play::serverbound::chat_command::PlayServerboundChatCommand;
+use crate::network::packets::play::serverbound::chat_message::PlayServerboundChatMessage;
+use crate::network::packets::play::serverbound::player_action::PlayServerboundPlayerAction;
+use crate::network::packets::play::serverbound::plugin_message::PlayServerboundPluginMessage;
+use crate::network::packets::play::serverbound::set_player_position::PlayServerboundSetPlayerPosition;
+use crate::network::packets::play::serverbound::set_player_position_and_rotation::PlayServerboundSetPlayerPositionAndRotation;
+use crate::network::packets::play::serverbound::set_player_rotation::PlayServerboundSetPlayerRotation;
+use crate::network::packets::play::serverbound::use_item_on::PlayServerboundUseItemOn;
+use crate::network::packets::play::serverbound::command_suggestions_request::PlayServerboundCommandSuggestionsRequest;
+use crate::commands::command_sender::CommandSender;
+use crate::player_list::OnlinePlayer;
+use crate::network::packets::play::serverbound::keep_alive::PlayServerboundKeepAlive;
+use crate::custom_types::game_profile::GameProfile;
+use crate::custom_types::location::Location;
+use crate::custom_types::position::Position;
+use crate::world::chunk::ChunkPos;
+use crate::world::chunk_manager::TicketKind;
+use crate::world::chunk_section::AIR;
+use crate::world::generation::generator::{hashed_seed, GeneratorKind};
+use crate::world::light::LightData;
+use crate::world::registry::{self, dimension_types};
+use crate::events::block_events::{BlockBreakEvent, BlockInteractEvent};
+use crate::events::connection_events::{PlayerLoginEvent, PlayerPreLoginEvent, PluginMessageEvent, StatusPingEvent};
+use crate::events::event_bus::Event;
+use crate::events::player_events::{PlayerChatEvent, PlayerJoinEvent, PlayerMoveEvent, PlayerQuitEvent};
 use crate::utils::mojauth::authenticate_player;
-use crate::{log, network::packets::{handshaking::serverbound::handshake::{HandshakeNextState, HandshakingServerboundHandshake}, login::clientbound::encryption_request::LoginClientboundEncryptionRequest}, utils::{errors::PacketHandleError, packet_utils::read_varint}, CONFIG, LOGGER, server::ServerData};
+use crate::utils::username::validate_username;
+use crate::{log, network::packets::{handshaking::serverbound::handshake::{HandshakeNextState, HandshakingServerboundHandshake}, login::clientbound::encryption_request::LoginClientboundEncryptionRequest}, utils::{errors::{PacketHandleError, PacketReadError}, packet_utils::{read_frame_length, MAX_PACKET_SIZE}}, CONFIG, LOGGER, METRICS, server::{ServerData, UnauthenticatedSlot}, utils::metrics::Metrics};
 use core::fmt;
-use std::{io::{Read, Write}, net::{Shutdown, TcpStream}, sync::{Arc, Mutex}};
+use crossbeam_channel::Receiver;
+use std::{collections::HashMap, io::{ErrorKind, Read, Write}, net::{IpAddr, Shutdown, SocketAddr, TcpStream}, sync::{Arc, Mutex}, time::{Duration, Instant}};
 
+use super::chunk_tracker::{ChunkTracker, ViewChange};
+use super::connection_handle::{ConnectionCommand, ConnectionHandle};
+use super::connection_registry::{ConnectionRegistration, ConnectionRegistry};
+use super::keep_alive::{KeepAlive, KeepAliveAction};
+use super::rate_limit::PacketRateLimiter;
 use super::packets::configuration::clientbound::disconnect::ConfigurationClientboundDisconnect;
+use super::packets::configuration::clientbound::keep_alive::ConfigurationClientboundKeepAlive;
+use super::packets::configuration::clientbound::known_packs::{ConfigurationClientboundKnownPacks, KnownPack};
+use super::packets::configuration::clientbound::registry_data::ConfigurationClientboundRegistryData;
+use super::packets::configuration::serverbound::keep_alive::ConfigurationServerboundKeepAlive;
 use super::packets::configuration::serverbound::client_information::ConfigurationServerboundClientInformation;
+use super::packets::configuration::serverbound::known_packs::ConfigurationServerboundKnownPacks;
 use super::packets::configuration::serverbound::plugin_message::ConfigurationServerboundPluginMessage;
 use super::packets::login::serverbound::encryption_response::LoginServerboundEncryptionResponse;
 use super::{packet::{ClientboundPacket, PacketReader, ServerboundPacket}, packets::{status::{clientbound::{ping_response::StatusClientboundPingResponse, status_response::StatusClientboundStatusResponse}, serverbound::ping_request::StatusServerboundPingRequest}, login::{serverbound::login_start::LoginServerboundLoginStart, clientbound::disconnect::LoginClientboundDisconnect}}};
@@ -51,6 +105,8 @@ impl fmt::Display for ConnectionState {
 
 pub struct Connection {
     stream: Arc<Mutex<TcpStream>>,
+    /// Kept around, as the socket can't tell anymore once the peer hung up.
+    address: SocketAddr,
     state: Arc<Mutex<ConnectionState>>,
     server_data: ServerData,
     verify_token: Mutex<Option<Vec<u8>>>,
@@ -58,8 +114,32 @@ pub struct Connection {
     name: Mutex<Option<String>>,
     uuid: Mutex<Uuid>,
     pub connection_info: Arc<Mutex<Option<ConnectionInfo>>>,
+    keep_alive: KeepAlive,
+    state_entered_at: Instant,
+    packet_rate_limiter: PacketRateLimiter,
+    unauthenticated_slot: Option<UnauthenticatedSlot>,
+    profile: Option<GameProfile>,
+    /// Unknown until the player first tells us where they are.
+    location: Option<Location>,
+    next_teleport_id: i32,
+    chunk_tracker: ChunkTracker,
+    /// The light version of every chunk the client has, and the one light changes were last looked for at.
+    light_versions: HashMap<ChunkPos, u64>,
+    light_version: u64,
+    handle: ConnectionHandle,
+    commands: Receiver<ConnectionCommand>,
+    _registration: ConnectionRegistration,
+    closed: bool,
 }
 
+/// How long a single `read` may block, so timers like keep alive get a chance to run.
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// The only dimension there is.
+const OVERWORLD: &str = "minecraft:overworld";
+
+/// Player Action statuses.
+const FINISHED_DIGGING: i32 = 2;
+
 
 pub struct ConnectionInfo {
     pub protocol_version: i32,
@@ -87,9 +167,12 @@ impl EncryptionSetting {
 }
 
 impl Connection {
-    pub fn new(stream: TcpStream, server_data: &ServerData) -> Self {
+    pub fn new(stream: TcpStream, address: SocketAddr, server_data: &ServerData, unauthenticated_slot: UnauthenticatedSlot) -> Self {
+        let (handle, commands) = ConnectionHandle::new();
+
         Connection { 
             stream: Arc::new(Mutex::new(stream)),
+            address,
             state: Arc::new(Mutex::new(ConnectionState::Handshaking)), 
             server_data: server_data.clone(),
             verify_token: Mutex::new(None),
@@ -97,6 +180,20 @@ impl Connection {
             name: Mutex::new(None),
             uuid: Mutex::new(Uuid::new_v4()),
             connection_info: Arc::new(Mutex::new(None)),
+            keep_alive: KeepAlive::new(Instant::now()),
+            state_entered_at: Instant::now(),
+            packet_rate_limiter: PacketRateLimiter::new(CONFIG.network.max_packets_per_second, Instant::now()),
+            unauthenticated_slot: Some(unauthenticated_slot),
+            profile: None,
+            location: None,
+            next_teleport_id: 0,
+            chunk_tracker: ChunkTracker::new(CONFIG.world.view_distance, Instant::now()),
+            light_versions: HashMap::new(),
+            light_version: 0,
+            _registration: ConnectionRegistry::register(&server_data.connections, handle.clone()),
+            handle,
+            commands,
+            closed: false,
         }
     }
 
@@ -105,8 +202,12 @@ impl Connection {
 
         let mut buf = [0u8; 1024];
         let mut data_accumulator: Vec<u8> = Vec::new();
+
+        if let Err(e) = stream_binding.lock().unwrap().set_read_timeout(Some(READ_POLL_INTERVAL)) {
+            log!(warn, "Failed to set read timeout for {}: {}", self.get_addr(), e);
+        }
         
-        loop {
+        while !self.closed {
             let mut stream = stream_binding.lock().unwrap();
             match stream.read(&mut buf) {
                 Ok(0) => break,
@@ -116,37 +217,226 @@ impl Connection {
                     data_accumulator.extend_from_slice(slice);
                     drop(stream);
 
-                    while let Some(reader) = self.extract_packet_reader(&mut data_accumulator) {
+                    if data_accumulator.len() > self.max_buffer_size() {
+                        log!(warn, "{} sent too much incomplete data ({} bytes), closing connection", self.get_name(), data_accumulator.len());
+                        break;
+                    }
+
+                    loop {
+                        let reader = match self.extract_packet_reader(&mut data_accumulator) {
+                            Ok(Some(reader)) => reader,
+                            Ok(None) => break,
+                            Err(e) => {
+                                log!(warn, "{} sent a malformed packet frame: {}", self.get_name(), e);
+                                self.disconnect(format!("Malformed packet: {}", e));
+                                break;
+                            }
+                        };
+
                         let packet_id = reader.id();
                         log!(debug, "Received packet with ID 0x{:x?} from {}", &packet_id, self.get_name());
 
-                        if let Err(e) = self.handle_packet(reader) {
-                            log!(warn, "Failed to handle packet 0x{:x?} for {}: {}", packet_id, self.get_name(), e);
+                        if !self.packet_rate_limiter.allow(Instant::now()) {
+                            log!(warn, "{} exceeded the packet rate limit", self.get_name());
+                            Metrics::increment(&METRICS.packet_rate_kicks);
+                            self.disconnect("Kicked for exceeding packet rate limit".to_owned());
+                            break;
                         }
+
+                        match self.handle_packet(reader) {
+                            Ok(()) => {},
+                            Err(PacketHandleError::ReadError(e)) => {
+                                log!(warn, "Failed to read packet 0x{:x?} from {}: {}", packet_id, self.get_name(), e);
+                                self.disconnect(format!("Malformed packet: {}", e));
+                            },
+                            Err(e) => log!(warn, "Failed to handle packet 0x{:x?} for {}: {}", packet_id, self.get_name(), e),
+                        }
+
+                        if self.closed { break; }
                     }
                 }
-                Err(e) => log!(warn, "Error receiving data: {}", e)
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => drop(stream),
+                Err(e) => {
+                    log!(warn, "Error receiving data: {}", e);
+                    break;
+                }
             }
+
+            self.process_commands();
+            self.tick_state_deadline();
+            self.tick_keep_alive();
+        }
+
+        for pos in self.chunk_tracker.view() {
+            self.server_data.chunks.remove_ticket(pos, TicketKind::Player);
         }
     
+        let joined = *self.state.lock().unwrap() == ConnectionState::Play;
+        let player = self.online_player();
+        if let Some(profile) = self.profile.take() {
+            self.server_data.players.remove(profile.uuid);
+            if joined {
+                self.server_data.players.broadcast_packet(&PlayClientboundPlayerInfoRemove { uuids: vec![profile.uuid] }.build());
+            }
+        }
+
+        match player {
+            Some(player) if joined => {
+                self.server_data.run_on_tick(move |server| {
+                    let quit_message = format!("{} left the game", player.profile.name);
+                    let mut event = PlayerQuitEvent { player, quit_message: Some(quit_message) };
+                    server.events.post(&mut event);
+
+                    if let Some(message) = event.quit_message {
+                        log!(info, "{}", message);
+                        server.players.broadcast_message(&message);
+                    }
+                });
+            },
+            Some(player) => log!(info, "{} disconnected before joining the game", player.profile.name),
+            None => {},
+        }
+
         log!(verbose, "Client {} dropped", self.get_addr());
-        self.stream.lock().unwrap().shutdown(Shutdown::Both).unwrap();
-    }
-
-    fn extract_packet_reader(&self, data: &mut Vec<u8>) -> Option<PacketReader> {
-        let mut buf = BytesMut::from(&data[..]);
-        if let Ok(packet_length) = read_varint(&mut buf) {
-            if buf.len() >= packet_length as usize {
-                data.drain(..(data.len() - buf.len()));
-                let packet: Vec<u8> = data.drain(..packet_length as usize).collect();
-                
-                if let Ok(packet_reader) = PacketReader::new(&packet) {
-                    return Some(packet_reader)    
+        let _ = self.stream.lock().unwrap().shutdown(Shutdown::Both);
+    }
+
+    /// Handles everything other threads asked this connection to do through its `ConnectionHandle`.
+    fn process_commands(&mut self) {
+        while !self.closed {
+            let command = match self.commands.try_recv() {
+                Ok(command) => command,
+                Err(_) => return,
+            };
+
+            match command {
+                ConnectionCommand::SendPacket(data) => self.send_packet_bytes(&data),
+                ConnectionCommand::SendPlayPacket(data) => {
+                    if *self.state.lock().unwrap() == ConnectionState::Play {
+                        self.send_packet_bytes(&data);
+                    }
+                },
+                ConnectionCommand::SystemMessage(message) => {
+                    // System chat only exists in the play state
+                    if *self.state.lock().unwrap() == ConnectionState::Play {
+                        self.send_packet_bytes(&PlayClientboundSystemChatMessage { content: message, overlay: false }.build());
+                    }
+                },
+                ConnectionCommand::Disconnect(reason) => {
+                    log!(info, "Disconnecting {}: {}", self.get_name(), reason);
+                    self.disconnect(reason);
+                },
+                ConnectionCommand::RefreshCommands => {
+                    if *self.state.lock().unwrap() == ConnectionState::Play {
+                        self.send_commands();
+                    }
+                },
+                ConnectionCommand::Teleport(location) => {
+                    if *self.state.lock().unwrap() == ConnectionState::Play {
+                        self.teleport(location);
+                    }
+                },
+                ConnectionCommand::Tick => self.tick_chunks(),
+            }
+        }
+    }
+
+    fn max_buffer_size(&self) -> usize {
+        if self.unauthenticated_slot.is_some() { CONFIG.network.max_unauthenticated_buffer }
+        else { MAX_PACKET_SIZE + 3 }
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        if state == ConnectionState::Configuration {
+            self.unauthenticated_slot = None;
+            self.keep_alive = KeepAlive::new(Instant::now());
+        }
+
+        *self.state.lock().unwrap() = state;
+        self.state_entered_at = Instant::now();
+    }
+
+    fn state_timeout(state: &ConnectionState) -> Option<Duration> {
+        let seconds = match state {
+            ConnectionState::Handshaking => CONFIG.network.handshake_timeout,
+            ConnectionState::Status => CONFIG.network.status_timeout,
+            ConnectionState::Login => CONFIG.network.login_timeout,
+            ConnectionState::Configuration => CONFIG.network.configuration_timeout,
+            ConnectionState::Play => return None,
+        };
+
+        Some(Duration::from_secs(seconds))
+    }
+
+    fn tick_state_deadline(&mut self) {
+        if self.closed { return; }
+
+        let state = self.state.lock().unwrap().clone();
+        let Some(timeout) = Self::state_timeout(&state) else { return };
+        if self.state_entered_at.elapsed() < timeout { return; }
+
+        log!(info, "{} took too long in {} state, closing connection", self.get_name(), state);
+        match state {
+            ConnectionState::Login => self.disconnect("Took too long to log in".to_owned()),
+            ConnectionState::Configuration => self.disconnect("Timed out".to_owned()),
+            _ => self.closed = true,
+        }
+    }
+
+    fn tick_keep_alive(&mut self) {
+        if self.closed { return; }
+
+        let state = self.state.lock().unwrap().clone();
+        if state != ConnectionState::Configuration && state != ConnectionState::Play { return; }
+
+        match self.keep_alive.poll(Instant::now()) {
+            KeepAliveAction::Idle => {},
+            KeepAliveAction::Send(keep_alive_id) => {
+                log!(debug, "Sending keep alive {} to {}", keep_alive_id, self.get_name());
+                if state == ConnectionState::Configuration {
+                    self.send_packet_bytes(&ConfigurationClientboundKeepAlive { keep_alive_id }.build());
                 }
+                else {
+                    self.send_packet_bytes(&PlayClientboundKeepAlive { keep_alive_id }.build());
+                }
+            },
+            KeepAliveAction::TimedOut => {
+                log!(info, "{} lost connection: Timed out", self.get_name());
+                self.disconnect("Timed out".to_owned());
+            },
+        }
+    }
+
+    fn handle_keep_alive(&mut self, keep_alive_id: i64) {
+        if self.keep_alive.acknowledge(keep_alive_id, Instant::now()) {
+            log!(debug, "{} answered keep alive (latency {}ms)", self.get_name(), self.keep_alive.latency_ms());
+            if let Some(profile) = &self.profile {
+                self.server_data.players.set_latency(profile.uuid, self.keep_alive.latency_ms());
             }
         }
+        else {
+            log!(warn, "{} sent an unexpected keep alive ID {}", self.get_name(), keep_alive_id);
+            self.disconnect("Timed out".to_owned());
+        }
+    }
+
+    fn extract_packet_reader(&self, data: &mut Vec<u8>) -> Result<Option<PacketReader>, PacketReadError> {
+        let (header_length, packet_length) = match read_frame_length(data)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        if packet_length > MAX_PACKET_SIZE {
+            return Err(PacketReadError::PacketTooLarge(packet_length));
+        }
+
+        if data.len() < header_length + packet_length {
+            return Ok(None);
+        }
 
-        None
+        data.drain(..header_length);
+        let packet: Vec<u8> = data.drain(..packet_length).collect();
+        Ok(Some(PacketReader::new(&packet)?))
     }
 
     fn send_packet_bytes(&mut self, data: &[u8]) {
@@ -154,14 +444,24 @@ impl Connection {
         let mut data: Vec<u8> = data.to_vec();
         self.encryption_setting.encrypt(&mut data);
 
-        stream.write_all(&data).unwrap();
+        let result = stream.write_all(&data);
         drop(stream);
-        log!(debug, "Sent packet ({} bytes) to {}", data.len(), self.get_name());
+
+        match result {
+            Ok(()) => log!(debug, "Sent packet ({} bytes) to {}", data.len(), self.get_name()),
+            Err(e) => {
+                log!(debug, "Failed to send a packet to {}: {}", self.get_name(), e);
+                self.closed = true;
+            }
+        }
+    }
+
+    fn get_ip(&self) -> IpAddr {
+        self.address.ip()
     }
 
     fn get_addr(&self) -> String {
-        let addr = self.stream.lock().unwrap().peer_addr().unwrap();
-        format!("{}:{}", addr.ip(), addr.port())
+        format!("{}:{}", self.address.ip(), self.address.port())
     }
 
     fn get_name(&self) -> String {
@@ -197,12 +497,12 @@ impl Connection {
             },
             ConnectionState::Play => {
                 drop(state_ref);
-                Ok(())
+                Ok(self.handle_play_packet(reader)?)
             }
         }
     }
 
-    fn handle_handshaking_packet(&self, mut reader: PacketReader) -> Result<(), PacketHandleError> {
+    fn handle_handshaking_packet(&mut self, mut reader: PacketReader) -> Result<(), PacketHandleError> {
         match reader.id() {
             0x00 => {
                 log!(debug, "Handshake from {}:", self.get_addr());
@@ -212,17 +512,31 @@ impl Connection {
                 log!(debug, "\tserver_port = {}", packet.server_port);
                 log!(debug, "\tnext_state = {}", packet.next_state);
 
-                let mut connection_info = self.connection_info.lock().unwrap();
-                *connection_info = Some(ConnectionInfo {
+                *self.connection_info.lock().unwrap() = Some(ConnectionInfo {
                     protocol_version: packet.protocol_version,
                     server_address: packet.server_address,
                     server_port: packet.server_port,
                 });
 
-                let mut state = self.state.lock().unwrap();
                 match packet.next_state {
-                    HandshakeNextState::Status => *state = ConnectionState::Status,
-                    HandshakeNextState::Login => *state = ConnectionState::Login,
+                    HandshakeNextState::Status => {
+                        self.set_state(ConnectionState::Status);
+
+                        if !self.server_data.status_throttle.lock().unwrap().allow(self.get_ip(), Instant::now()) {
+                            log!(debug, "Throttled status request from {}", self.get_addr());
+                            Metrics::increment(&METRICS.status_requests_throttled);
+                            self.closed = true;
+                        }
+                    },
+                    HandshakeNextState::Login => {
+                        self.set_state(ConnectionState::Login);
+
+                        if !self.server_data.login_throttle.lock().unwrap().allow(self.get_ip(), Instant::now()) {
+                            log!(info, "Throttled login from {}", self.get_addr());
+                            Metrics::increment(&METRICS.connections_rejected_throttled);
+                            self.disconnect("Connection throttled! Please wait before reconnecting.".to_owned());
+                        }
+                    },
                     _ => log!(warn, "Weird 'next_state' ({}) when handling handshake packet from {}", packet.next_state, self.get_addr()),
                 }
             }
@@ -235,18 +549,37 @@ impl Connection {
     fn handle_status_packet(&mut self, mut reader: PacketReader) -> Result<(), PacketHandleError> {
         match reader.id() {
             0x00 => {
+                let mut event = StatusPingEvent::new(
+                    self.get_ip(),
+                    CONFIG.status.motd.clone(),
+                    CONFIG.status.version_prefix.clone() + " " + crate::VERSION,
+                    crate::PROTOCOL_VERSION,
+                    CONFIG.server.max_players,
+                    self.server_data.players.count(),
+                    self.server_data.players.profiles().into_iter().take(12).collect(),
+                );
+                self.server_data.events.post(&mut event);
+
+                if event.is_cancelled() {
+                    self.closed = true;
+                    return Ok(());
+                }
+
                 let json_status_response = object! {
                     version: {
-                        name: CONFIG.status.version_prefix.clone() + " " + crate::VERSION,
-                        protocol: crate::PROTOCOL_VERSION,
+                        name: event.version_name,
+                        protocol: event.protocol,
                     },
                     players: {
-                        max: CONFIG.server.max_players,
-                        online: 69,
-                        sample: []
+                        max: event.max_players,
+                        online: event.online_players,
+                        sample: event.sample.iter().map(|profile| object! {
+                            name: profile.name.clone(),
+                            id: profile.uuid.hyphenated().to_string(),
+                        }).collect::<Vec<_>>(),
                     },
                     description: {
-                        text: CONFIG.status.motd.clone(),
+                        text: event.motd,
                     },
                     enforcesSecureChat: false,
                 };
@@ -295,6 +628,28 @@ impl Connection {
                     }
                 }
 
+                let username = self.name.lock().unwrap().clone().unwrap_or_default();
+                let relaxed_usernames = CONFIG.server.relaxed_usernames && !CONFIG.server.online_mode;
+                if let Err(e) = validate_username(&username, relaxed_usernames) {
+                    log!(info, "Disconnecting {}: {}", self.get_name(), e);
+                    self.disconnect(e.to_string());
+                    return Ok(());
+                }
+
+                let mut event = PlayerPreLoginEvent {
+                    name: username,
+                    uuid: *self.uuid.lock().unwrap(),
+                    address: self.get_ip(),
+                    kick_message: self.server_data.access_lists.check_ip(self.get_ip()).err(),
+                };
+                self.server_data.events.post(&mut event);
+
+                if let Some(message) = event.kick_message {
+                    log!(info, "Disconnecting {}: {}", self.get_name(), message.replace('\n', " "));
+                    self.disconnect(message);
+                    return Ok(());
+                }
+
                 let public_key_der = self.server_data.public_key.to_public_key_der().unwrap();
                 let verify_token = Self::generate_verify_token(4);
 
@@ -313,7 +668,8 @@ impl Connection {
                 let verify_token = self.verify_token.lock().unwrap().clone();
                 match verify_token {
                     Some(verify_token) => {
-                        let decrypted_verify_token = self.server_data.private_key.decrypt(Pkcs1v15Encrypt, &packet.verify_token).unwrap();
+                        let decrypted_verify_token = self.server_data.private_key.decrypt(Pkcs1v15Encrypt, &packet.verify_token)
+                            .map_err(|e| PacketReadError::ConvertationIssue(format!("Couldn't decrypt the verify token: {}", e)))?;
 
                         if *verify_token != decrypted_verify_token {
                             log!(warn, "Verify tokens for {} didn't match.", self.get_name());
@@ -328,7 +684,12 @@ impl Connection {
                     }
                 };
 
-                let shared_secret = self.server_data.private_key.decrypt(Pkcs1v15Encrypt, &packet.shared_secret).unwrap();
+                let shared_secret = self.server_data.private_key.decrypt(Pkcs1v15Encrypt, &packet.shared_secret)
+                    .map_err(|e| PacketReadError::ConvertationIssue(format!("Couldn't decrypt the shared secret: {}", e)))?;
+                // AES-128, anything else would panic when setting up the cipher
+                if shared_secret.len() != 16 {
+                    return Err(PacketReadError::InvalidLength(shared_secret.len() as i32).into());
+                }
 
                 *self.verify_token.lock().unwrap() = None;
                 let (encryptor, decryptor) = aes_util::initialize(&shared_secret); // turn on encryption
@@ -346,8 +707,35 @@ impl Connection {
                     if let Some(username) = username {
                         match authenticate_player(username.to_owned(), &shared_secret, public_key_der.as_bytes()) {
                             Ok(response) => {
-                                let uuid = Uuid::parse_str(&response.id).unwrap();
-                                *self.uuid.lock().unwrap() = uuid;
+                                let uuid = match Uuid::parse_str(&response.id) {
+                                    Ok(uuid) => uuid,
+                                    Err(e) => {
+                                        log!(error, "Session server returned an invalid UUID '{}' for {}: {}", response.id, self.get_name(), e);
+                                        self.disconnect("Failed to authenticate".to_owned());
+                                        return Ok(());
+                                    }
+                                };
+
+                                let client_uuid = *self.uuid.lock().unwrap();
+                                if client_uuid != uuid {
+                                    log!(warn, "{} claimed UUID {}, but their profile has UUID {}", self.get_name(), client_uuid, uuid);
+                                    self.disconnect("Failed to verify username: your UUID doesn't match your profile".to_owned());
+                                    return Ok(());
+                                }
+
+                                if !response.name.eq_ignore_ascii_case(&username) {
+                                    log!(warn, "{} logged in as '{}', but their profile name is '{}'", self.get_name(), username, response.name);
+                                    self.disconnect("Failed to verify username: your name doesn't match your profile".to_owned());
+                                    return Ok(());
+                                }
+
+                                // The profile has the name with its real capitalization
+                                let username = response.name;
+                                *self.name.lock().unwrap() = Some(username.clone());
+
+                                if !self.admit_player(uuid, &username) {
+                                    return Ok(());
+                                }
 
                                 log!(verbose, "Authentication for {} succeeded!", self.get_name());
 
@@ -388,6 +776,10 @@ impl Connection {
                     let uuid = *self.uuid.lock().unwrap();
                     let username = (*self.name.lock().unwrap().clone().unwrap()).to_string();
 
+                    if !self.admit_player(uuid, &username) {
+                        return Ok(());
+                    }
+
                     let login_success_packet = LoginClientboundLoginSuccess {
                         uuid,
                         username,
@@ -399,8 +791,12 @@ impl Connection {
                 }
             }
             0x03 => {
-                *self.state.lock().unwrap() = ConnectionState::Configuration;
+                self.set_state(ConnectionState::Configuration);
                 log!(verbose, "Client {} reached Login Acknowledged!!!", self.get_name());
+
+                // The client answers with the packs it has, and configuration finishes once it does
+                let known_packs_packet = ConfigurationClientboundKnownPacks { known_packs: vec![KnownPack::core()] };
+                self.send_packet_bytes(&known_packs_packet.build());
             }
             _ => return Err(PacketHandleError::BadId(reader.id()))
         }
@@ -429,22 +825,53 @@ impl Connection {
                 log!(debug, "\tMain hand: {}", packet.main_hand);
                 log!(debug, "\tEnable text filtering: {}", packet.enable_text_filtering);
                 log!(debug, "\tAllow server listings: {}", packet.allow_server_listings);
-
-                let finish_configuration_packet = ConfigurationClientboundFinishConfiguration {};
-                self.send_packet_bytes(&finish_configuration_packet.build())
+                self.set_view_distance(packet.view_distance);
             },
             0x02 => {
                 let packet = ConfigurationServerboundPluginMessage::read(&mut reader)?;
-                log!(debug, "Recieved plugin message at '{}' ({} bytes): {:x?}", packet.channel, packet.data.len(), packet.data);
-
-                if packet.channel.to_string() == "minecraft:brand" {
-                    let brand = String::from_utf8(packet.data).unwrap();
-                    log!(verbose, "{}'s brand is '{}'", self.get_name(), brand);
-                }
+                self.post_plugin_message(packet.channel.to_string(), packet.data);
             }
             0x03 => {
-                *self.state.lock().unwrap() = ConnectionState::Play;
+                self.set_state(ConnectionState::Play);
                 log!(verbose, "Client {} reached Configuration Acknowledged!!!", self.get_name());
+                self.send_login();
+                self.send_commands();
+                self.send_player_info();
+                // Also sets the chunk center, so chunks start streaming right away
+                self.teleport(self.server_data.spawn_location());
+
+                if let Some(player) = self.online_player() {
+                    self.server_data.run_on_tick(move |server| {
+                        let join_message = format!("{} joined the game", player.profile.name);
+                        let mut event = PlayerJoinEvent { player, join_message: Some(join_message) };
+                        server.events.post(&mut event);
+
+                        if let Some(message) = event.join_message {
+                            log!(info, "{}", message);
+                            server.players.broadcast_message(&message);
+                        }
+                    });
+                }
+            }
+            0x04 => {
+                let packet = ConfigurationServerboundKeepAlive::read(&mut reader)?;
+                self.handle_keep_alive(packet.keep_alive_id);
+            }
+            0x07 => {
+                let packet = ConfigurationServerboundKnownPacks::read(&mut reader)?;
+                // Registry entries are sent without their data, which only works if the client has the same vanilla pack
+                if !packet.known_packs.contains(&KnownPack::core()) {
+                    self.disconnect("This server requires Minecraft 1.21".to_owned());
+                    return Ok(());
+                }
+
+                for (registry, entries) in registry::synced_registries() {
+                    let registry_data_packet = ConfigurationClientboundRegistryData { registry: registry.to_owned(), entries };
+                    self.send_packet_bytes(&registry_data_packet.build());
+                }
+
+                let finish_configuration_packet = ConfigurationClientboundFinishConfiguration {};
+                self.send_packet_bytes(&finish_configuration_packet.build())
             }
             _ => return Err(PacketHandleError::BadId(reader.id()))
         }
@@ -452,6 +879,349 @@ impl Connection {
         Ok(())
     }
 
+    fn handle_play_packet(&mut self, mut reader: PacketReader) -> Result<(), PacketHandleError> {
+        match reader.id() {
+            0x18 => {
+                let packet = PlayServerboundKeepAlive::read(&mut reader)?;
+                self.handle_keep_alive(packet.keep_alive_id);
+            }
+            0x04 => {
+                let packet = PlayServerboundChatCommand::read(&mut reader)?;
+                if let Some(sender) = self.command_sender() {
+                    self.server_data.run_on_tick(move |server| server.commands.dispatch(&sender, server, &packet.command));
+                }
+            }
+            0x0B => {
+                let packet = PlayServerboundCommandSuggestionsRequest::read(&mut reader)?;
+                if let Some(sender) = self.command_sender() {
+                    let handle = self.handle.clone();
+                    self.server_data.run_on_tick(move |server| {
+                        // The text includes the leading slash, the suggestions' start has to account for it
+                        let input = packet.text.strip_prefix('/').unwrap_or(&packet.text);
+                        let offset = packet.text.len() - input.len();
+                        let suggestions = server.commands.suggest(&sender, server, input);
+
+                        // The client counts in UTF-16 code units, not bytes
+                        let start = suggestions.start + offset;
+                        let response = PlayClientboundCommandSuggestionsResponse {
+                            transaction_id: packet.transaction_id,
+                            start: packet.text[..start].encode_utf16().count() as i32,
+                            length: packet.text[start..].encode_utf16().count() as i32,
+                            matches: suggestions.matches,
+                        };
+                        handle.send_packet(response.build());
+                    });
+                }
+            }
+            0x06 => {
+                let packet = PlayServerboundChatMessage::read(&mut reader)?;
+                if packet.message.chars().any(|c| c == '§' || c.is_control()) {
+                    self.disconnect("Illegal characters in chat".to_owned());
+                    return Ok(());
+                }
+
+                if let Some(player) = self.online_player() {
+                    self.server_data.run_on_tick(move |server| {
+                        let mut event = PlayerChatEvent::new(player, packet.message);
+                        server.events.post(&mut event);
+
+                        if !event.is_cancelled() {
+                            let message = format!("<{}> {}", event.player.profile.name, event.message);
+                            log!(info, "{}", message);
+                            server.players.broadcast_message(&message);
+                        }
+                    });
+                }
+            }
+            0x08 => {
+                let packet = PlayServerboundChunkBatchReceived::read(&mut reader)?;
+                self.chunk_tracker.batch_received(packet.chunks_per_tick);
+            }
+            0x0A => {
+                let packet = PlayServerboundClientInformation::read(&mut reader)?;
+                log!(debug, "{} changed their view distance to {}", self.get_name(), packet.information.view_distance);
+                self.set_view_distance(packet.information.view_distance);
+            }
+            0x12 => {
+                let packet = PlayServerboundPluginMessage::read(&mut reader)?;
+                self.post_plugin_message(packet.channel.to_string(), packet.data);
+            }
+            0x1A => {
+                let packet = PlayServerboundSetPlayerPosition::read(&mut reader)?;
+                self.handle_move(|location| location.with_position(packet.x, packet.y, packet.z));
+            }
+            0x1B => {
+                let packet = PlayServerboundSetPlayerPositionAndRotation::read(&mut reader)?;
+                self.handle_move(|location| location.with_position(packet.x, packet.y, packet.z).with_rotation(packet.yaw, packet.pitch));
+            }
+            0x1C => {
+                let packet = PlayServerboundSetPlayerRotation::read(&mut reader)?;
+                self.handle_move(|location| location.with_rotation(packet.yaw, packet.pitch));
+            }
+            0x24 => {
+                let packet = PlayServerboundPlayerAction::read(&mut reader)?;
+                if let Some(player) = self.online_player() {
+                    self.server_data.run_on_tick(move |server| {
+                        if packet.status == FINISHED_DIGGING {
+                            let handle = player.handle.clone();
+                            let mut event = BlockBreakEvent::new(player.clone(), packet.position);
+                            server.events.post(&mut event);
+
+                            if event.is_cancelled() {
+                                // Puts back the block the client already removed
+                                Self::send_block(server, &handle, &packet.position);
+                            }
+                            else if server.chunks.set_block(&packet.position, AIR).is_some_and(|old| old != AIR) {
+                                server.players.broadcast_packet(&PlayClientboundBlockUpdate { position: packet.position, block_state: AIR }.build());
+                            }
+                        }
+
+                        player.handle.send_packet(PlayClientboundAcknowledgeBlockChange { sequence: packet.sequence }.build());
+                    });
+                }
+            }
+            0x38 => {
+                let packet = PlayServerboundUseItemOn::read(&mut reader)?;
+                if let Some(player) = self.online_player() {
+                    self.server_data.run_on_tick(move |server| {
+                        let handle = player.handle.clone();
+                        let mut event = BlockInteractEvent::new(player, packet.position, packet.face, packet.hand == 1);
+                        server.events.post(&mut event);
+
+                        // Held items aren't tracked, so nothing gets placed. Resending both blocks undoes what the client predicted.
+                        Self::send_block(server, &handle, &event.position);
+                        Self::send_block(server, &handle, &event.placed_position());
+                        handle.send_packet(PlayClientboundAcknowledgeBlockChange { sequence: packet.sequence }.build());
+                    });
+                }
+            }
+            _ => log!(debug, "Ignoring unhandled play packet 0x{:x?} from {}", reader.id(), self.get_name())
+        }
+
+        Ok(())
+    }
+
+    /// Checks bans, the whitelist and the player limit, lets event handlers weigh in, then adds the player to the player list.
+    /// Disconnects the player and returns `false` if they aren't allowed to join.
+    fn admit_player(&mut self, uuid: Uuid, username: &str) -> bool {
+        let profile = GameProfile::new(uuid, username);
+        let max_players = CONFIG.server.max_players.max(0) as usize;
+
+        let kick_message = match self.server_data.access_lists.check_player(uuid, username, CONFIG.server.whitelist) {
+            Err(message) => Some(message),
+            Ok(()) if self.server_data.players.count() >= max_players && !self.server_data.permissions.can_bypass_player_limit(&profile) => Some("The server is full!".to_owned()),
+            Ok(()) => None,
+        };
+
+        let mut event = PlayerLoginEvent { profile: profile.clone(), address: self.get_ip(), kick_message };
+        self.server_data.events.post(&mut event);
+
+        if let Some(message) = event.kick_message {
+            log!(info, "Disconnecting {}: {}", self.get_name(), message.replace('\n', " "));
+            self.disconnect(message);
+            return false;
+        }
+
+        let player = OnlinePlayer {
+            profile: profile.clone(),
+            ip: self.get_ip(),
+            handle: self.handle.clone(),
+        };
+
+        if !self.server_data.players.add(player) {
+            log!(info, "Disconnecting {}: already connected", self.get_name());
+            self.disconnect("You are already connected to this server!".to_owned());
+            return false;
+        }
+
+        log!(verbose, "{} logged in", profile);
+        self.profile = Some(profile);
+        true
+    }
+
+    fn online_player(&self) -> Option<OnlinePlayer> {
+        self.profile.as_ref().map(|profile| OnlinePlayer {
+            profile: profile.clone(),
+            ip: self.get_ip(),
+            handle: self.handle.clone(),
+        })
+    }
+
+    /// Puts the player into the overworld, using the dimension type sent in Registry Data during configuration.
+    fn send_login(&mut self) {
+        let packet = PlayClientboundLogin {
+            entity_id: self.server_data.next_entity_id(),
+            hardcore: false,
+            dimension_names: vec![OVERWORLD.to_owned()],
+            max_players: CONFIG.server.max_players,
+            view_distance: CONFIG.world.view_distance as i32,
+            simulation_distance: CONFIG.world.view_distance as i32,
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            do_limited_crafting: false,
+            dimension_type: dimension_types::OVERWORLD,
+            dimension_name: OVERWORLD.to_owned(),
+            hashed_seed: hashed_seed(CONFIG.world.seed),
+            game_mode: 0,
+            previous_game_mode: -1,
+            is_debug: false,
+            is_flat: CONFIG.world.generator == GeneratorKind::Flat,
+            portal_cooldown: 0,
+            enforces_secure_chat: false,
+        };
+        self.send_packet_bytes(&packet.build());
+    }
+
+    /// Fills the joining player's tab list and adds them to everyone else's.
+    fn send_player_info(&mut self) {
+        let Some(player) = self.online_player() else { return };
+        let players = Arc::clone(&self.server_data.players);
+        let actions = ACTION_ADD_PLAYER | ACTION_UPDATE_LISTED | ACTION_UPDATE_LATENCY;
+
+        let others: Vec<OnlinePlayer> = players.players().into_iter().filter(|other| other.profile.uuid != player.profile.uuid).collect();
+        let packet = players.info_update(&others, actions).build();
+        self.send_packet_bytes(&packet);
+        players.broadcast_packet(&players.info_update(&[player], actions).build());
+    }
+
+    fn command_sender(&self) -> Option<CommandSender> {
+        self.online_player().map(CommandSender::Player)
+    }
+
+    fn post_plugin_message(&mut self, channel: String, data: Vec<u8>) {
+        log!(debug, "Received plugin message at '{}' ({} bytes): {:x?}", channel, data.len(), data);
+
+        if let Some(player) = self.online_player() {
+            self.server_data.run_on_tick(move |server| server.events.post(&mut PluginMessageEvent { player, channel, data }));
+        }
+    }
+
+    /// Tells the player what's really at `position`, if the chunk is loaded.
+    fn send_block(server: &ServerData, handle: &ConnectionHandle, position: &Position) {
+        if let Some(Some(block_state)) = server.chunks.with_chunk(ChunkPos::of(position), |chunk| chunk.get_block(position)) {
+            handle.send_packet(PlayClientboundBlockUpdate { position: *position, block_state }.build());
+        }
+    }
+
+    /// Works out where the player moved to from their last location. Event handlers get to veto the move on the next tick,
+    /// which sends the player back.
+    fn handle_move(&mut self, moved: impl FnOnce(Location) -> Location) {
+        let Some(player) = self.online_player() else { return; };
+        let Some(from) = self.location else {
+            self.location = Some(moved(Location::new(0.0, 0.0, 0.0, 0.0, 0.0)));
+            self.update_chunk_center();
+            return;
+        };
+
+        let to = moved(from);
+        if to == from { return; }
+
+        self.location = Some(to);
+        self.update_chunk_center();
+
+        self.server_data.run_on_tick(move |server| {
+            let handle = player.handle.clone();
+            let mut event = PlayerMoveEvent::new(player, from, to);
+            server.events.post(&mut event);
+
+            if event.is_cancelled() {
+                handle.teleport(from);
+            }
+            else if event.to != to {
+                handle.teleport(event.to);
+            }
+        });
+    }
+
+    fn teleport(&mut self, location: Location) {
+        self.location = Some(location);
+        self.next_teleport_id = self.next_teleport_id.wrapping_add(1);
+
+        let packet = PlayClientboundSynchronizePlayerPosition {
+            x: location.x,
+            y: location.y,
+            z: location.z,
+            yaw: location.yaw,
+            pitch: location.pitch,
+            flags: 0,
+            teleport_id: self.next_teleport_id,
+        };
+        self.send_packet_bytes(&packet.build());
+        self.update_chunk_center();
+    }
+
+    /// Uses the player's view distance, as far as the server allows.
+    fn set_view_distance(&mut self, view_distance: i16) {
+        let view_distance = view_distance.clamp(0, CONFIG.world.view_distance as i16) as u8;
+        let change = self.chunk_tracker.set_view_distance(view_distance);
+        self.apply_view_change(change);
+    }
+
+    /// Centers the player's view on the chunk they're in, if they moved into another one.
+    fn update_chunk_center(&mut self) {
+        let Some(location) = self.location else { return; };
+        let center = ChunkPos::of(&Position::new(location.x.floor() as i64, 0, location.z.floor() as i64));
+        if self.chunk_tracker.center() == Some(center) { return; }
+
+        let change = self.chunk_tracker.set_center(center);
+        self.send_packet_bytes(&PlayClientboundSetCenterChunk { chunk_x: center.x, chunk_z: center.z }.build());
+        self.apply_view_change(change);
+    }
+
+    /// Keeps the chunks in view loaded, and lets go of the ones that aren't anymore.
+    fn apply_view_change(&mut self, change: ViewChange) {
+        for pos in change.entered {
+            self.server_data.add_chunk_ticket(pos, TicketKind::Player);
+        }
+        for pos in change.left {
+            self.server_data.chunks.remove_ticket(pos, TicketKind::Player);
+        }
+        for pos in change.unloaded {
+            self.light_versions.remove(&pos);
+            self.send_packet_bytes(&PlayClientboundUnloadChunk { chunk_x: pos.x, chunk_z: pos.z }.build());
+        }
+    }
+
+    /// Sends the light that changed in chunks the client has, then the next batch of chunks around the player,
+    /// when the client is ready for one.
+    fn tick_chunks(&mut self) {
+        if self.closed || *self.state.lock().unwrap() != ConnectionState::Play { return; }
+
+        let chunks = Arc::clone(&self.server_data.chunks);
+        let light_version = chunks.light_version();
+        if light_version != self.light_version {
+            self.light_version = light_version;
+            let changes: Vec<(ChunkPos, LightData, u64)> = self.light_versions.iter()
+                .filter_map(|(pos, version)| chunks.light_changes(*pos, *version).map(|(light, version)| (*pos, light, version)))
+                .collect();
+            for (pos, light, version) in changes {
+                self.light_versions.insert(pos, version);
+                self.send_packet_bytes(&PlayClientboundUpdateLight { chunk_x: pos.x, chunk_z: pos.z, light }.build());
+            }
+        }
+
+        // Chunks are loaded in the background, the ones that aren't there yet come in a later batch
+        let batch = self.chunk_tracker.poll(Instant::now(), |pos| chunks.is_loaded(pos));
+        if batch.is_empty() { return; }
+
+        self.send_packet_bytes(&PlayClientboundChunkBatchStart {}.build());
+        for pos in &batch {
+            if let Some((data, version)) = chunks.with_light(*pos, |chunk, light| PlayClientboundChunkDataAndUpdateLight::new(chunk, light.clone()).build()) {
+                self.light_versions.insert(*pos, version);
+                self.send_packet_bytes(&data);
+            }
+        }
+        self.send_packet_bytes(&PlayClientboundChunkBatchFinished { batch_size: batch.len() as i32 }.build());
+    }
+
+    /// Sends the command tree, trimmed down to what this player is allowed to use.
+    fn send_commands(&mut self) {
+        if let Some(sender) = self.command_sender() {
+            let packet = self.server_data.commands.serialize(&sender, &self.server_data.permissions);
+            self.send_packet_bytes(&packet.build());
+        }
+    }
+
     fn disconnect(&mut self, reason: String) {
         let connection_state = self.state.lock().unwrap().clone();
         match connection_state {
@@ -463,8 +1233,15 @@ impl Connection {
                 let config_disconnect_packet = ConfigurationClientboundDisconnect::from_string(reason);
                 self.send_packet_bytes(&config_disconnect_packet.build());
             }
-            _ => log!(error, "Invalid state ({}) while sending disconnect packet.", connection_state)
+            ConnectionState::Play => {
+                let play_disconnect_packet = PlayClientboundDisconnect::from_string(reason);
+                self.send_packet_bytes(&play_disconnect_packet.build());
+            }
+            // There's no disconnect packet before login, the socket just gets closed
+            ConnectionState::Handshaking | ConnectionState::Status => {},
         }
+
+        self.closed = true;
     }
 
     fn generate_verify_token(size: usize) -> Vec<u8> {
@@ -0,0 +1,58 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::custom_types::location::Location;
+
+pub enum ConnectionCommand {
+    SendPacket(Vec<u8>),
+    /// Dropped unless the connection is in the play state.
+    SendPlayPacket(Vec<u8>),
+    SystemMessage(String),
+    Disconnect(String),
+    /// Resends the command tree, e.g. after the player's permissions changed.
+    RefreshCommands,
+    /// Moves the player, e.g. back to where they were after a cancelled move.
+    Teleport(Location),
+    /// Sent by the tick loop every tick, to stream chunks at the server's pace.
+    Tick,
+}
+
+/// Lets other threads talk to a connection. Commands are picked up by the connection's own thread.
+#[derive(Clone)]
+pub struct ConnectionHandle {
+    sender: Sender<ConnectionCommand>,
+}
+
+impl ConnectionHandle {
+    pub fn new() -> (Self, Receiver<ConnectionCommand>) {
+        let (sender, receiver) = unbounded();
+        (Self { sender }, receiver)
+    }
+
+    pub fn send_packet(&self, data: Vec<u8>) {
+        let _ = self.sender.send(ConnectionCommand::SendPacket(data));
+    }
+
+    pub fn send_play_packet(&self, data: Vec<u8>) {
+        let _ = self.sender.send(ConnectionCommand::SendPlayPacket(data));
+    }
+
+    pub fn send_message(&self, message: &str) {
+        let _ = self.sender.send(ConnectionCommand::SystemMessage(message.to_owned()));
+    }
+
+    pub fn refresh_commands(&self) {
+        let _ = self.sender.send(ConnectionCommand::RefreshCommands);
+    }
+
+    pub fn teleport(&self, location: Location) {
+        let _ = self.sender.send(ConnectionCommand::Teleport(location));
+    }
+
+    pub fn tick(&self) {
+        let _ = self.sender.send(ConnectionCommand::Tick);
+    }
+
+    pub fn disconnect(&self, reason: &str) {
+        let _ = self.sender.send(ConnectionCommand::Disconnect(reason.to_owned()));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, thread, time::{Duration, Instant}};
+
+use super::connection_handle::ConnectionHandle;
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Every open connection, whatever state it's in, so all of them can be reached on shutdown.
+pub struct ConnectionRegistry {
+    connections: Mutex<HashMap<u64, ConnectionHandle>>,
+    next_id: AtomicU64,
+}
+
+/// Keeps a connection in the registry until it's dropped.
+pub struct ConnectionRegistration {
+    registry: Arc<ConnectionRegistry>,
+    id: u64,
+}
+
+impl ConnectionRegistry {
+    pub fn new() -> Self {
+        Self {
+            connections: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn register(registry: &Arc<ConnectionRegistry>, handle: ConnectionHandle) -> ConnectionRegistration {
+        let id = registry.next_id.fetch_add(1, Ordering::SeqCst);
+        registry.connections.lock().unwrap().insert(id, handle);
+
+        ConnectionRegistration { registry: Arc::clone(registry), id }
+    }
+
+    pub fn count(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+
+    pub fn tick_all(&self) {
+        for handle in self.connections.lock().unwrap().values() {
+            handle.tick();
+        }
+    }
+
+    pub fn disconnect_all(&self, reason: &str) {
+        for handle in self.connections.lock().unwrap().values() {
+            handle.disconnect(reason);
+        }
+    }
+
+    /// Waits for every connection to close. Returns `false` if some are still open after `timeout`.
+    pub fn wait_until_empty(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.count() > 0 {
+            if Instant::now() >= deadline { return false; }
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+
+        true
+    }
+}
+
+impl Drop for ConnectionRegistration {
+    fn drop(&mut self) {
+        self.registry.connections.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration() {
+        let registry = Arc::new(ConnectionRegistry::new());
+        let (handle, commands) = ConnectionHandle::new();
+
+        let registration = ConnectionRegistry::register(&registry, handle);
+        assert_eq!(registry.count(), 1);
+        assert!(!registry.wait_until_empty(Duration::from_millis(60)));
+
+        registry.disconnect_all("Server closed");
+        assert!(matches!(commands.try_recv(), Ok(crate::network::connection_handle::ConnectionCommand::Disconnect(reason)) if reason == "Server closed"));
+
+        drop(registration);
+        assert_eq!(registry.count(), 0);
+        assert!(registry.wait_until_empty(Duration::ZERO));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::time::{Duration, Instant};
+
+use rand::{thread_rng, Rng};
+
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub enum KeepAliveAction {
+    Idle,
+    Send(i64),
+    TimedOut,
+}
+
+pub struct KeepAlive {
+    last_sent: Instant,
+    pending: Option<(i64, Instant)>,
+    latency: Option<Duration>,
+}
+
+impl KeepAlive {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_sent: now,
+            pending: None,
+            latency: None,
+        }
+    }
+
+    /// Decides what should happen with the connection at `now`.
+    /// A new keep alive ID is generated every `KEEP_ALIVE_INTERVAL`, while a pending
+    /// one that stays unanswered for `KEEP_ALIVE_TIMEOUT` means the client timed out.
+    pub fn poll(&mut self, now: Instant) -> KeepAliveAction {
+        if let Some((_, sent_at)) = self.pending {
+            if now.duration_since(sent_at) >= KEEP_ALIVE_TIMEOUT {
+                return KeepAliveAction::TimedOut;
+            }
+            return KeepAliveAction::Idle;
+        }
+
+        if now.duration_since(self.last_sent) < KEEP_ALIVE_INTERVAL {
+            return KeepAliveAction::Idle;
+        }
+
+        let id = thread_rng().gen();
+        self.pending = Some((id, now));
+        self.last_sent = now;
+        KeepAliveAction::Send(id)
+    }
+
+    /// Returns `false` if the client answered with an ID we never sent (or nothing was pending).
+    pub fn acknowledge(&mut self, id: i64, now: Instant) -> bool {
+        match self.pending {
+            Some((pending_id, sent_at)) if pending_id == id => {
+                let elapsed = now.duration_since(sent_at);
+
+                // Same smoothing vanilla uses for the tab list ping
+                self.latency = Some(match self.latency {
+                    Some(latency) => (latency * 3 + elapsed) / 4,
+                    None => elapsed,
+                });
+                self.pending = None;
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn latency_ms(&self) -> i32 {
+        match self.latency {
+            Some(latency) => latency.as_millis().min(i32::MAX as u128) as i32,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_before_interval() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        assert!(matches!(keep_alive.poll(start + Duration::from_secs(5)), KeepAliveAction::Idle));
+    }
+
+    #[test]
+    fn test_send_and_acknowledge() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        let sent_at = start + KEEP_ALIVE_INTERVAL;
+        let id = match keep_alive.poll(sent_at) {
+            KeepAliveAction::Send(id) => id,
+            _ => panic!("Keep alive wasn't sent after the interval"),
+        };
+
+        // Nothing new is sent while one is pending
+        assert!(matches!(keep_alive.poll(sent_at + Duration::from_secs(1)), KeepAliveAction::Idle));
+
+        assert!(keep_alive.acknowledge(id, sent_at + Duration::from_millis(100)));
+        assert_eq!(keep_alive.latency_ms(), 100);
+    }
+
+    #[test]
+    fn test_wrong_id() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        let id = match keep_alive.poll(start + KEEP_ALIVE_INTERVAL) {
+            KeepAliveAction::Send(id) => id,
+            _ => panic!("Keep alive wasn't sent after the interval"),
+        };
+
+        assert!(!keep_alive.acknowledge(id.wrapping_add(1), start + KEEP_ALIVE_INTERVAL));
+        assert!(keep_alive.acknowledge(id, start + KEEP_ALIVE_INTERVAL));
+        assert!(!keep_alive.acknowledge(id, start + KEEP_ALIVE_INTERVAL));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        let sent_at = start + KEEP_ALIVE_INTERVAL;
+        assert!(matches!(keep_alive.poll(sent_at), KeepAliveAction::Send(_)));
+        assert!(matches!(keep_alive.poll(sent_at + KEEP_ALIVE_TIMEOUT - Duration::from_secs(1)), KeepAliveAction::Idle));
+        assert!(matches!(keep_alive.poll(sent_at + KEEP_ALIVE_TIMEOUT), KeepAliveAction::TimedOut));
+    }
+
+    #[test]
+    fn test_latency_smoothing() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+
+        let mut now = start;
+        for elapsed in [200, 100] {
+            now += KEEP_ALIVE_INTERVAL;
+            let id = match keep_alive.poll(now) {
+                KeepAliveAction::Send(id) => id,
+                _ => panic!("Keep alive wasn't sent after the interval"),
+            };
+            now += Duration::from_millis(elapsed);
+            keep_alive.acknowledge(id, now);
+        }
+
+        assert_eq!(keep_alive.latency_ms(), (200 * 3 + 100) / 4);
+    }
+}
\ No newline at end of file
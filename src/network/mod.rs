@@ -1,3 +1,8 @@
+pub mod chunk_tracker;
 pub mod connection;
+pub mod connection_handle;
+pub mod connection_registry;
+pub mod keep_alive;
 pub mod packet;
-pub mod packets;
\ No newline at end of file
+pub mod packets;
+pub mod rate_limit;
\ No newline at end of file
@@ -2,7 +2,7 @@ use bytes::{Buf, BufMut, BytesMut};
 use uuid::Uuid;
 use crate::custom_types::identifier::Identifier;
 use crate::utils::errors::PacketReadError;
-use crate::utils::packet_utils::{read_string, read_varint, read_varlong, write_string, write_varint, write_varlong};
+use crate::utils::packet_utils::{read_string, read_string_limited, read_varint, read_varlong, write_string, write_varint, write_varlong};
 
 pub struct PacketReader {
     packet_id: i32,
@@ -50,6 +50,10 @@ impl PacketReader {
         read_string(&mut self.data)
     }
 
+    pub fn read_string_limited(&mut self, max_length: usize) -> Result<String, PacketReadError> {
+        read_string_limited(&mut self.data, max_length)
+    }
+
     pub fn read_uuid(&mut self) -> Result<Uuid, PacketReadError> {
         if self.data.remaining() < 16 { return Err(PacketReadError::BufferUnderflow); }
         let encoded_uuid = self.data.get_u128();
@@ -156,6 +160,23 @@ impl PacketWriter {
         self
     }
 
+    /// Writes a plain text component as network NBT (a nameless root `TAG_String`).
+    pub fn write_nbt_text(&mut self, text: &str) -> &Self {
+        let bytes = text.as_bytes();
+        self.data.put_u8(0x08);
+        self.data.put_u16(bytes.len() as u16);
+        self.data.put_slice(bytes);
+        self
+    }
+
+    /// Writes network NBT, which has no name for the root tag.
+    pub fn write_nbt(&mut self, value: &nbt::Value) -> &Self {
+        self.data.put_u8(value.id());
+        // Only fails for lists mixing tag types
+        let _ = value.to_writer(&mut (&mut self.data).writer());
+        self
+    }
+
     pub fn write_boolean(&mut self, val: bool) -> &Self {
         if val { self.data.put_u8(0x01); }
         else { self.data.put_u8(0x00); }
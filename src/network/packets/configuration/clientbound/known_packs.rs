@@ -0,0 +1,39 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// A data pack, like `minecraft:core` at version `1.21`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownPack {
+    pub namespace: String,
+    pub id: String,
+    pub version: String,
+}
+
+impl KnownPack {
+    /// The vanilla pack the registries in data/ come from.
+    pub fn core() -> Self {
+        Self { namespace: "minecraft".to_owned(), id: "core".to_owned(), version: "1.21".to_owned() }
+    }
+}
+
+pub struct ConfigurationClientboundKnownPacks {
+    pub known_packs: Vec<KnownPack>,
+}
+
+impl ClientboundPacket for ConfigurationClientboundKnownPacks {
+    fn packet_id() -> i32 {
+        0x0E
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.known_packs.len() as i32);
+
+        for pack in &self.known_packs {
+            writer.write_string(&pack.namespace);
+            writer.write_string(&pack.id);
+            writer.write_string(&pack.version);
+        }
+
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
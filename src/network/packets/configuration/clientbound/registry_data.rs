@@ -0,0 +1,40 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// The entries of a data pack registry. They're sent without data, so the client takes it from the known packs.
+pub struct ConfigurationClientboundRegistryData {
+    pub registry: String,
+    pub entries: Vec<&'static str>,
+}
+
+impl ClientboundPacket for ConfigurationClientboundRegistryData {
+    fn packet_id() -> i32 {
+        0x07
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_string(&self.registry);
+        writer.write_varint(self.entries.len() as i32);
+
+        for entry in &self.entries {
+            writer.write_string(entry);
+            writer.write_boolean(false);
+        }
+
+        writer.build_uncompressed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let packet = ConfigurationClientboundRegistryData { registry: "a:b".to_owned(), entries: vec!["a:c", "a:d"] };
+
+        // Registry, entry count, then each entry without data
+        let expected = vec![16, 0x07, 3, b'a', b':', b'b', 2, 3, b'a', b':', b'c', 0, 3, b'a', b':', b'd', 0];
+        assert_eq!(packet.build(), expected);
+    }
+}
\ No newline at end of file
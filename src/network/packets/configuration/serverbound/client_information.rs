@@ -55,7 +55,7 @@ impl ServerboundPacket for ConfigurationServerboundClientInformation {
     fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
     where 
         Self: Sized {
-            let locale = reader.read_string()?;
+            let locale = reader.read_string_limited(16)?;
             let view_distance = reader.read_byte()? as i16;
             let chat_mode = match reader.read_varint()? {
                 0 => ClientChatMode::Enabled,
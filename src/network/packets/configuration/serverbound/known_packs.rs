@@ -0,0 +1,31 @@
+use crate::{network::{packet::{PacketReader, ServerboundPacket}, packets::configuration::clientbound::known_packs::KnownPack}, utils::errors::PacketReadError};
+
+/// The packs the client has out of the ones the server listed.
+pub struct ConfigurationServerboundKnownPacks {
+    pub known_packs: Vec<KnownPack>,
+}
+
+impl ServerboundPacket for ConfigurationServerboundKnownPacks {
+    fn packet_id() -> i32
+    where
+        Self: Sized {
+        0x07
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where
+        Self: Sized {
+            let count = reader.read_varint()?;
+            if !(0..=64).contains(&count) { return Err(PacketReadError::InvalidLength(count)); }
+
+            let mut known_packs = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                known_packs.push(KnownPack {
+                    namespace: reader.read_string()?,
+                    id: reader.read_string()?,
+                    version: reader.read_string()?,
+                });
+            }
+            Ok(Self { known_packs })
+    }
+}
\ No newline at end of file
@@ -38,7 +38,7 @@ impl ServerboundPacket for HandshakingServerboundHandshake {
     where 
         Self: Sized {
             let protocol_version = reader.read_varint()?;
-            let server_address = reader.read_string()?;
+            let server_address = reader.read_string_limited(255)?;
             let server_port = reader.read_ushort()?;
             let next_state = match reader.read_varint()? {
                 1 => HandshakeNextState::Status,
@@ -17,7 +17,7 @@ impl ServerboundPacket for LoginServerboundLoginStart {
     fn read(reader: &mut crate::network::packet::PacketReader) -> Result<Self, PacketReadError>
     where 
         Self: Sized {
-        let name = reader.read_string()?;
+        let name = reader.read_string_limited(16)?;
         let uuid = reader.read_uuid()?;
 
         Ok(Self {
@@ -4,12 +4,15 @@ pub mod configuration {
         pub mod feature_flags;
         pub mod finish_configuration;
         pub mod keep_alive;
+        pub mod known_packs;
         pub mod plugin_message;
+        pub mod registry_data;
     }
     pub mod serverbound {
         pub mod acknowledge_finish_configuration;
         pub mod client_information;
         pub mod keep_alive;
+        pub mod known_packs;
         pub mod plugin_message;
     }
 }
@@ -42,4 +45,40 @@ pub mod login {
         pub mod login_acknowledged;
         pub mod login_start;
     }
+}
+
+pub mod play {
+    pub mod clientbound {
+        pub mod acknowledge_block_change;
+        pub mod block_update;
+        pub mod chunk_batch_finished;
+        pub mod chunk_batch_start;
+        pub mod chunk_data_and_update_light;
+        pub mod command_suggestions_response;
+        pub mod commands;
+        pub mod disconnect;
+        pub mod keep_alive;
+        pub mod login;
+        pub mod player_info_remove;
+        pub mod player_info_update;
+        pub mod set_center_chunk;
+        pub mod synchronize_player_position;
+        pub mod system_chat_message;
+        pub mod unload_chunk;
+        pub mod update_light;
+    }
+    pub mod serverbound {
+        pub mod chat_command;
+        pub mod chat_message;
+        pub mod chunk_batch_received;
+        pub mod client_information;
+        pub mod command_suggestions_request;
+        pub mod keep_alive;
+        pub mod player_action;
+        pub mod plugin_message;
+        pub mod set_player_position;
+        pub mod set_player_position_and_rotation;
+        pub mod set_player_rotation;
+        pub mod use_item_on;
+    }
 }
\ No newline at end of file
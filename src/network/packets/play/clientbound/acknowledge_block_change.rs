@@ -0,0 +1,18 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// Tells the client the server is done with its block changes up to `sequence`.
+pub struct PlayClientboundAcknowledgeBlockChange {
+    pub sequence: i32,
+}
+
+impl ClientboundPacket for PlayClientboundAcknowledgeBlockChange {
+    fn packet_id() -> i32 {
+        0x05
+    }
+
+    fn build(&self) -> Vec<u8> {
+        PacketWriter::new(Self::packet_id())
+            .write_varint(self.sequence)
+            .build_uncompressed()
+    }
+}
\ No newline at end of file
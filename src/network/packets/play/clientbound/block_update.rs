@@ -0,0 +1,20 @@
+use crate::{custom_types::position::Position, network::packet::{ClientboundPacket, PacketWriter}};
+
+/// A single block changed.
+pub struct PlayClientboundBlockUpdate {
+    pub position: Position,
+    pub block_state: u32,
+}
+
+impl ClientboundPacket for PlayClientboundBlockUpdate {
+    fn packet_id() -> i32 {
+        0x09
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_long(self.position.encode());
+        writer.write_varint(self.block_state as i32);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// Ends a batch of chunks. The client answers with Chunk Batch Received.
+pub struct PlayClientboundChunkBatchFinished {
+    pub batch_size: i32,
+}
+
+impl ClientboundPacket for PlayClientboundChunkBatchFinished {
+    fn packet_id() -> i32 {
+        0x0C
+    }
+
+    fn build(&self) -> Vec<u8> {
+        PacketWriter::new(Self::packet_id())
+            .write_varint(self.batch_size)
+            .build_uncompressed()
+    }
+}
\ No newline at end of file
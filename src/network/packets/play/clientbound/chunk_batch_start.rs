@@ -0,0 +1,15 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// Sent before a batch of chunks, so the client can time how long receiving it took.
+pub struct PlayClientboundChunkBatchStart {
+}
+
+impl ClientboundPacket for PlayClientboundChunkBatchStart {
+    fn packet_id() -> i32 {
+        0x0D
+    }
+
+    fn build(&self) -> Vec<u8> {
+        PacketWriter::new(Self::packet_id()).build_uncompressed()
+    }
+}
\ No newline at end of file
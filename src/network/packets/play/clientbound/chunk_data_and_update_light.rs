@@ -0,0 +1,176 @@
+use crate::{
+    network::packet::{ClientboundPacket, PacketWriter},
+    world::{block_entity::BlockEntity, chunk::Chunk, heightmap::Heightmap, light::LightData},
+};
+
+const TAG_END: u8 = 0x00;
+const TAG_COMPOUND: u8 = 0x0A;
+const TAG_LONG_ARRAY: u8 = 0x0C;
+
+/// Sends a whole chunk along with its light.
+pub struct PlayClientboundChunkDataAndUpdateLight {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub motion_blocking: Heightmap,
+    pub world_surface: Heightmap,
+    /// Every section, as written by `Chunk::write_sections`.
+    pub data: Vec<u8>,
+    pub block_entities: Vec<BlockEntity>,
+    pub light: LightData,
+}
+
+impl PlayClientboundChunkDataAndUpdateLight {
+    pub fn new(chunk: &Chunk, light: LightData) -> Self {
+        let mut data = Vec::new();
+        chunk.write_sections(&mut data);
+
+        Self {
+            chunk_x: chunk.pos().x,
+            chunk_z: chunk.pos().z,
+            motion_blocking: chunk.heightmaps().motion_blocking.clone(),
+            world_surface: chunk.heightmaps().world_surface.clone(),
+            data,
+            block_entities: chunk.block_entities().to_vec(),
+            light,
+        }
+    }
+}
+
+impl ClientboundPacket for PlayClientboundChunkDataAndUpdateLight {
+    fn packet_id() -> i32 {
+        0x27
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_int(self.chunk_x);
+        writer.write_int(self.chunk_z);
+
+        // Written by hand, the nbt crate doesn't keep the order of compound entries
+        writer.write_ubyte(TAG_COMPOUND);
+        for (name, heightmap) in [("MOTION_BLOCKING", &self.motion_blocking), ("WORLD_SURFACE", &self.world_surface)] {
+            writer.write_ubyte(TAG_LONG_ARRAY);
+            writer.write_ushort(name.len() as u16);
+            writer.write_byte_array(name.as_bytes());
+            writer.write_int(heightmap.data().len() as i32);
+            for long in heightmap.data() {
+                writer.write_long(*long as i64);
+            }
+        }
+        writer.write_ubyte(TAG_END);
+
+        writer.write_varint(self.data.len() as i32);
+        writer.write_byte_array(&self.data);
+
+        writer.write_varint(self.block_entities.len() as i32);
+        for block_entity in &self.block_entities {
+            let position = &block_entity.position;
+            writer.write_ubyte((((position.x() & 15) << 4) | (position.z() & 15)) as u8);
+            writer.write_short(position.y() as i16);
+            writer.write_varint(block_entity.kind);
+            writer.write_nbt(&block_entity.data);
+        }
+
+        let mut light = Vec::new();
+        self.light.write(&mut light);
+        writer.write_byte_array(&light);
+        writer.build_uncompressed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{custom_types::position::Position, world::{chunk::ChunkPos, light::{LightArray, LIGHT_ARRAY_SIZE}}};
+
+    use super::*;
+
+    /// Packet ID, then the fields, with the length in front.
+    fn framed(body: Vec<u8>) -> Vec<u8> {
+        let mut packet = vec![];
+        let length = body.len() + 1;
+        packet.extend_from_slice(&[(length as u8 & 0x7F) | 0x80, (length >> 7) as u8]);
+        packet.push(0x27);
+        packet.extend(body);
+        packet
+    }
+
+    fn heightmaps(first_long: u64) -> Vec<u8> {
+        let mut nbt = vec![0x0A];
+        for name in ["MOTION_BLOCKING", "WORLD_SURFACE"] {
+            nbt.push(0x0C);
+            nbt.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            nbt.extend_from_slice(name.as_bytes());
+            nbt.extend_from_slice(&37i32.to_be_bytes());
+            nbt.extend_from_slice(&first_long.to_be_bytes());
+            nbt.extend_from_slice(&[0; 36 * 8]);
+        }
+        nbt.push(0x00);
+        nbt
+    }
+
+    #[test]
+    fn test_empty_chunk() {
+        let chunk = Chunk::new(ChunkPos::new(1, -2), 0);
+        let packet = PlayClientboundChunkDataAndUpdateLight::new(&chunk, LightData::new());
+
+        let mut body = vec![0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFE];
+        body.extend(heightmaps(0));
+        // 24 sections of 8 bytes: no blocks, single value air, single value biome 0
+        body.extend_from_slice(&[0xC0, 0x01]);
+        for _ in 0..24 {
+            body.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        // No block entities, 4 empty light masks, no light arrays
+        body.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(packet.build(), framed(body));
+    }
+
+    #[test]
+    fn test_chunk_with_block_entity_and_light() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), 0);
+        chunk.set_block(&Position::new(0, -64, 0), 1);
+        chunk.set_block_entity(BlockEntity {
+            position: Position::new(3, 70, 14),
+            kind: 7,
+            data: nbt::Value::Compound(HashMap::from([("Lock".to_owned(), nbt::Value::String("key".to_owned()))])),
+        });
+
+        let mut light = LightData::new();
+        light.sky_light[1] = Some(LightArray::filled(15));
+        let packet = PlayClientboundChunkDataAndUpdateLight::new(&chunk, light);
+
+        let mut body = vec![0, 0, 0, 0, 0, 0, 0, 0];
+        // The bottom block of column 0, 0 makes its height 1
+        body.extend(heightmaps(1));
+
+        // The first section has one stone block: 4 bits, palette [air, stone], first long 1
+        let mut section = vec![0, 1, 4, 2, 0, 1, 0x80, 0x02];
+        section.extend_from_slice(&1u64.to_be_bytes());
+        section.extend_from_slice(&[0; 255 * 8]);
+        section.extend_from_slice(&[0, 0, 0]);
+        let data_length = section.len() + 23 * 8;
+        body.extend_from_slice(&[(data_length as u8 & 0x7F) | 0x80, (data_length >> 7) as u8]);
+        body.extend(section);
+        for _ in 0..23 {
+            body.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        }
+
+        // One block entity at x 3, z 14, y 70 with type 7 and a compound holding a string
+        body.extend_from_slice(&[1, 0x3E, 0, 70, 7, 0x0A, 0x08, 0, 4]);
+        body.extend_from_slice(b"Lock");
+        body.extend_from_slice(&[0, 3]);
+        body.extend_from_slice(b"key");
+        body.push(0x00);
+
+        // Sky light for section 1 (the lowest one in the world), nothing else
+        body.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+        body.extend_from_slice(&[1, 0x80, 0x10]);
+        body.extend_from_slice(&[0xFF; LIGHT_ARRAY_SIZE]);
+        body.push(0);
+
+        assert_eq!(packet.build(), framed(body));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub struct PlayClientboundCommandSuggestionsResponse {
+    pub transaction_id: i32,
+    pub start: i32,
+    pub length: i32,
+    pub matches: Vec<String>,
+}
+
+impl ClientboundPacket for PlayClientboundCommandSuggestionsResponse {
+    fn packet_id() -> i32 {
+        0x10
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.transaction_id);
+        writer.write_varint(self.start);
+        writer.write_varint(self.length);
+        writer.write_varint(self.matches.len() as i32);
+        for suggestion in &self.matches {
+            writer.write_string(suggestion);
+            // No tooltip
+            writer.write_boolean(false);
+        }
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,72 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+const NODE_TYPE_ROOT: u8 = 0x00;
+const NODE_TYPE_LITERAL: u8 = 0x01;
+const NODE_TYPE_ARGUMENT: u8 = 0x02;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_HAS_SUGGESTIONS_TYPE: u8 = 0x10;
+
+pub enum CommandNodeKind {
+    Root,
+    Literal(String),
+    Argument {
+        name: String,
+        parser_id: i32,
+        properties: Vec<u8>,
+        suggestions_type: Option<String>,
+    },
+}
+
+pub struct CommandNodeData {
+    pub kind: CommandNodeKind,
+    pub executable: bool,
+    pub children: Vec<i32>,
+}
+
+pub struct PlayClientboundCommands {
+    pub nodes: Vec<CommandNodeData>,
+    pub root_index: i32,
+}
+
+impl ClientboundPacket for PlayClientboundCommands {
+    fn packet_id() -> i32 {
+        0x11
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.nodes.len() as i32);
+
+        for node in &self.nodes {
+            let mut flags = match &node.kind {
+                CommandNodeKind::Root => NODE_TYPE_ROOT,
+                CommandNodeKind::Literal(_) => NODE_TYPE_LITERAL,
+                CommandNodeKind::Argument { suggestions_type: None, .. } => NODE_TYPE_ARGUMENT,
+                CommandNodeKind::Argument { .. } => NODE_TYPE_ARGUMENT | FLAG_HAS_SUGGESTIONS_TYPE,
+            };
+            if node.executable { flags |= FLAG_EXECUTABLE; }
+            writer.write_ubyte(flags);
+
+            writer.write_varint(node.children.len() as i32);
+            for child in &node.children {
+                writer.write_varint(*child);
+            }
+
+            match &node.kind {
+                CommandNodeKind::Root => {},
+                CommandNodeKind::Literal(name) => { writer.write_string(name); },
+                CommandNodeKind::Argument { name, parser_id, properties, suggestions_type } => {
+                    writer.write_string(name);
+                    writer.write_varint(*parser_id);
+                    writer.write_byte_array(properties);
+                    if let Some(suggestions_type) = suggestions_type {
+                        writer.write_string(suggestions_type);
+                    }
+                }
+            }
+        }
+
+        writer.write_varint(self.root_index);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub struct PlayClientboundDisconnect {
+    pub reason: String
+}
+
+impl PlayClientboundDisconnect {
+    pub fn from_string(reason: String) -> Self {
+        Self { reason }
+    }
+}
+
+impl ClientboundPacket for PlayClientboundDisconnect {
+    fn packet_id() -> i32 {
+        0x1D
+    }
+
+    fn build(&self) -> Vec<u8> {
+        PacketWriter::new(Self::packet_id())
+            .write_nbt_text(&self.reason)
+            .build_uncompressed()
+    }
+}
@@ -0,0 +1,17 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub struct PlayClientboundKeepAlive {
+    pub keep_alive_id: i64,
+}
+
+impl ClientboundPacket for PlayClientboundKeepAlive {
+    fn packet_id() -> i32 {
+        0x26
+    }
+
+    fn build(&self) -> Vec<u8> {
+        PacketWriter::new(Self::packet_id())
+            .write_long(self.keep_alive_id)
+            .build_uncompressed()
+    }
+}
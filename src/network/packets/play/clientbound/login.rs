@@ -0,0 +1,60 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// The first packet in the play state, which puts the player into a world.
+pub struct PlayClientboundLogin {
+    pub entity_id: i32,
+    pub hardcore: bool,
+    pub dimension_names: Vec<String>,
+    pub max_players: i32,
+    pub view_distance: i32,
+    pub simulation_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub do_limited_crafting: bool,
+    /// ID in the `minecraft:dimension_type` registry sent during configuration.
+    pub dimension_type: i32,
+    pub dimension_name: String,
+    /// The first 8 bytes of the SHA-256 hash of the world seed, used for biome noise on the client.
+    pub hashed_seed: i64,
+    pub game_mode: u8,
+    /// -1 if there was none.
+    pub previous_game_mode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub portal_cooldown: i32,
+    pub enforces_secure_chat: bool,
+}
+
+impl ClientboundPacket for PlayClientboundLogin {
+    fn packet_id() -> i32 {
+        0x2B
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_int(self.entity_id);
+        writer.write_boolean(self.hardcore);
+        writer.write_varint(self.dimension_names.len() as i32);
+        for name in &self.dimension_names {
+            writer.write_string(name);
+        }
+        writer.write_varint(self.max_players);
+        writer.write_varint(self.view_distance);
+        writer.write_varint(self.simulation_distance);
+        writer.write_boolean(self.reduced_debug_info);
+        writer.write_boolean(self.enable_respawn_screen);
+        writer.write_boolean(self.do_limited_crafting);
+        writer.write_varint(self.dimension_type);
+        writer.write_string(&self.dimension_name);
+        writer.write_long(self.hashed_seed);
+        writer.write_ubyte(self.game_mode);
+        writer.write_byte(self.previous_game_mode);
+        writer.write_boolean(self.is_debug);
+        writer.write_boolean(self.is_flat);
+        // No death location
+        writer.write_boolean(false);
+        writer.write_varint(self.portal_cooldown);
+        writer.write_boolean(self.enforces_secure_chat);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
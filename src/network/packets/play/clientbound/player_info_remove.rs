@@ -0,0 +1,23 @@
+use uuid::Uuid;
+
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// Removes players from the client's tab list.
+pub struct PlayClientboundPlayerInfoRemove {
+    pub uuids: Vec<Uuid>,
+}
+
+impl ClientboundPacket for PlayClientboundPlayerInfoRemove {
+    fn packet_id() -> i32 {
+        0x3D
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.uuids.len() as i32);
+        for uuid in &self.uuids {
+            writer.write_uuid(*uuid);
+        }
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+use uuid::Uuid;
+
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub const ACTION_ADD_PLAYER: u8 = 0x01;
+pub const ACTION_UPDATE_LISTED: u8 = 0x08;
+pub const ACTION_UPDATE_LATENCY: u8 = 0x10;
+
+/// Adds players to the client's tab list or updates them. Only the fields of the set `actions` are sent.
+pub struct PlayClientboundPlayerInfoUpdate {
+    pub actions: u8,
+    pub entries: Vec<PlayerInfoEntry>,
+}
+
+pub struct PlayerInfoEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub listed: bool,
+    /// In milliseconds.
+    pub latency: i32,
+}
+
+impl ClientboundPacket for PlayClientboundPlayerInfoUpdate {
+    fn packet_id() -> i32 {
+        0x3E
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_ubyte(self.actions);
+        writer.write_varint(self.entries.len() as i32);
+
+        for entry in &self.entries {
+            writer.write_uuid(entry.uuid);
+            if self.actions & ACTION_ADD_PLAYER != 0 {
+                writer.write_string(&entry.name);
+                // No skin properties
+                writer.write_varint(0);
+            }
+            if self.actions & ACTION_UPDATE_LISTED != 0 {
+                writer.write_boolean(entry.listed);
+            }
+            if self.actions & ACTION_UPDATE_LATENCY != 0 {
+                writer.write_varint(entry.latency);
+            }
+        }
+
+        writer.build_uncompressed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let entry = PlayerInfoEntry { uuid: Uuid::from_u128(1), name: "Steve".to_owned(), listed: true, latency: 300 };
+        let packet = PlayClientboundPlayerInfoUpdate { actions: ACTION_ADD_PLAYER | ACTION_UPDATE_LATENCY, entries: vec![entry] };
+
+        // Actions and entry count, then the UUID, name, no properties and the latency as a VarInt, but not listed
+        let mut expected = vec![0x3E, 0x11, 1];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.extend_from_slice(&[5, b'S', b't', b'e', b'v', b'e', 0, 0xAC, 0x02]);
+        expected.insert(0, expected.len() as u8);
+
+        assert_eq!(packet.build(), expected);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// The chunk the client's view is centered on. Chunks it gets outside its view distance from there are ignored.
+pub struct PlayClientboundSetCenterChunk {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
+
+impl ClientboundPacket for PlayClientboundSetCenterChunk {
+    fn packet_id() -> i32 {
+        0x54
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.chunk_x);
+        writer.write_varint(self.chunk_z);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,30 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+/// Teleports the player. With no `flags` set, every value is absolute.
+pub struct PlayClientboundSynchronizePlayerPosition {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub flags: i8,
+    pub teleport_id: i32,
+}
+
+impl ClientboundPacket for PlayClientboundSynchronizePlayerPosition {
+    fn packet_id() -> i32 {
+        0x40
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_double(self.x);
+        writer.write_double(self.y);
+        writer.write_double(self.z);
+        writer.write_float(self.yaw);
+        writer.write_float(self.pitch);
+        writer.write_byte(self.flags);
+        writer.write_varint(self.teleport_id);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
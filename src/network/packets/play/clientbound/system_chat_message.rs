@@ -0,0 +1,19 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub struct PlayClientboundSystemChatMessage {
+    pub content: String,
+    pub overlay: bool,
+}
+
+impl ClientboundPacket for PlayClientboundSystemChatMessage {
+    fn packet_id() -> i32 {
+        0x6C
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_nbt_text(&self.content);
+        writer.write_boolean(self.overlay);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
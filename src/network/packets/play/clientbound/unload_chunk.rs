@@ -0,0 +1,20 @@
+use crate::network::packet::{ClientboundPacket, PacketWriter};
+
+pub struct PlayClientboundUnloadChunk {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
+
+impl ClientboundPacket for PlayClientboundUnloadChunk {
+    fn packet_id() -> i32 {
+        0x21
+    }
+
+    fn build(&self) -> Vec<u8> {
+        // Z comes first
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_int(self.chunk_z);
+        writer.write_int(self.chunk_x);
+        writer.build_uncompressed()
+    }
+}
\ No newline at end of file
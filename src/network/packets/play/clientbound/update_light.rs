@@ -0,0 +1,47 @@
+use crate::{network::packet::{ClientboundPacket, PacketWriter}, world::light::LightData};
+
+/// Updates the light of a chunk the client already has.
+pub struct PlayClientboundUpdateLight {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub light: LightData,
+}
+
+impl ClientboundPacket for PlayClientboundUpdateLight {
+    fn packet_id() -> i32 {
+        0x2A
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new(Self::packet_id());
+        writer.write_varint(self.chunk_x);
+        writer.write_varint(self.chunk_z);
+
+        let mut light = Vec::new();
+        self.light.write(&mut light);
+        writer.write_byte_array(&light);
+        writer.build_uncompressed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::light::LightArray;
+
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let mut light = LightData::new();
+        light.block_light[2] = Some(LightArray::filled(0));
+        let packet = PlayClientboundUpdateLight { chunk_x: -1, chunk_z: 300, light };
+
+        // Chunk X and Z as VarInts, then only an empty block light mask with bit 2 set
+        let mut expected = vec![0x2A, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0xAC, 0x02, 0, 0, 0, 1];
+        expected.extend_from_slice(&4u64.to_be_bytes());
+        expected.extend_from_slice(&[0, 0]);
+        expected.insert(0, expected.len() as u8);
+
+        assert_eq!(packet.build(), expected);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+/// A command without signed arguments, sent without the leading `/`.
+pub struct PlayServerboundChatCommand {
+    pub command: String,
+}
+
+impl ServerboundPacket for PlayServerboundChatCommand {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x04
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                command: reader.read_string()?
+            })
+    }
+}
\ No newline at end of file
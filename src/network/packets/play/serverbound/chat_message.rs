@@ -0,0 +1,26 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundChatMessage {
+    pub message: String,
+    pub timestamp: i64,
+    pub salt: i64,
+}
+
+impl ServerboundPacket for PlayServerboundChatMessage {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x06
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            // The signature and acknowledgements that follow only matter for secure chat
+            Ok(Self {
+                message: reader.read_string_limited(256)?,
+                timestamp: reader.read_long()?,
+                salt: reader.read_long()?,
+            })
+    }
+}
\ No newline at end of file
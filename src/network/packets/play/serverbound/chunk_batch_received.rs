@@ -0,0 +1,22 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundChunkBatchReceived {
+    /// How many chunks per tick the client wants from now on.
+    pub chunks_per_tick: f32,
+}
+
+impl ServerboundPacket for PlayServerboundChunkBatchReceived {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x08
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                chunks_per_tick: reader.read_float()?
+            })
+    }
+}
\ No newline at end of file
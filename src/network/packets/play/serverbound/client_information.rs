@@ -0,0 +1,22 @@
+use crate::{network::{packet::{ServerboundPacket, PacketReader}, packets::configuration::serverbound::client_information::ConfigurationServerboundClientInformation}, utils::errors::PacketReadError};
+
+/// Sent again whenever the player changes their settings, with the same fields as during configuration.
+pub struct PlayServerboundClientInformation {
+    pub information: ConfigurationServerboundClientInformation,
+}
+
+impl ServerboundPacket for PlayServerboundClientInformation {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x0A
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                information: ConfigurationServerboundClientInformation::read(reader)?
+            })
+    }
+}
\ No newline at end of file
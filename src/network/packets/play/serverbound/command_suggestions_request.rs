@@ -0,0 +1,24 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundCommandSuggestionsRequest {
+    pub transaction_id: i32,
+    /// Everything typed so far, including the leading `/`.
+    pub text: String,
+}
+
+impl ServerboundPacket for PlayServerboundCommandSuggestionsRequest {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x0B
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                transaction_id: reader.read_varint()?,
+                text: reader.read_string_limited(32500)?,
+            })
+    }
+}
\ No newline at end of file
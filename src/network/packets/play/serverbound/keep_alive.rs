@@ -0,0 +1,21 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundKeepAlive {
+    pub keep_alive_id: i64,
+}
+
+impl ServerboundPacket for PlayServerboundKeepAlive {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x18
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                keep_alive_id: reader.read_long()?
+            })
+    }
+}
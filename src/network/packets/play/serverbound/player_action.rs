@@ -0,0 +1,28 @@
+use crate::{custom_types::position::Position, network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundPlayerAction {
+    pub status: i32,
+    pub position: Position,
+    pub face: i8,
+    /// Acknowledged with Acknowledge Block Change.
+    pub sequence: i32,
+}
+
+impl ServerboundPacket for PlayServerboundPlayerAction {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x24
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                status: reader.read_varint()?,
+                position: Position::decode(reader.read_long()?),
+                face: reader.read_byte()?,
+                sequence: reader.read_varint()?,
+            })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundSetPlayerPosition {
+    pub x: f64,
+    /// The player's feet.
+    pub y: f64,
+    pub z: f64,
+    pub on_ground: bool,
+}
+
+impl ServerboundPacket for PlayServerboundSetPlayerPosition {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x1A
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                x: reader.read_double()?,
+                y: reader.read_double()?,
+                z: reader.read_double()?,
+                on_ground: reader.read_boolean()?,
+            })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundSetPlayerPositionAndRotation {
+    pub x: f64,
+    /// The player's feet.
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl ServerboundPacket for PlayServerboundSetPlayerPositionAndRotation {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x1B
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                x: reader.read_double()?,
+                y: reader.read_double()?,
+                z: reader.read_double()?,
+                yaw: reader.read_float()?,
+                pitch: reader.read_float()?,
+                on_ground: reader.read_boolean()?,
+            })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,25 @@
+use crate::{network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundSetPlayerRotation {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl ServerboundPacket for PlayServerboundSetPlayerRotation {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x1C
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                yaw: reader.read_float()?,
+                pitch: reader.read_float()?,
+                on_ground: reader.read_boolean()?,
+            })
+    }
+}
\ No newline at end of file
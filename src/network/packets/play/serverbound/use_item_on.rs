@@ -0,0 +1,37 @@
+use crate::{custom_types::position::Position, network::packet::{ServerboundPacket, PacketReader}, utils::errors::PacketReadError};
+
+pub struct PlayServerboundUseItemOn {
+    /// 0 for the main hand, 1 for the off hand.
+    pub hand: i32,
+    pub position: Position,
+    pub face: i32,
+    pub cursor_x: f32,
+    pub cursor_y: f32,
+    pub cursor_z: f32,
+    pub inside_block: bool,
+    /// Acknowledged with Acknowledge Block Change.
+    pub sequence: i32,
+}
+
+impl ServerboundPacket for PlayServerboundUseItemOn {
+    fn packet_id() -> i32 
+    where 
+        Self: Sized {
+        0x38
+    }
+
+    fn read(reader: &mut PacketReader) -> Result<Self, PacketReadError>
+    where 
+        Self: Sized {
+            Ok(Self {
+                hand: reader.read_varint()?,
+                position: Position::decode(reader.read_long()?),
+                face: reader.read_varint()?,
+                cursor_x: reader.read_float()?,
+                cursor_y: reader.read_float()?,
+                cursor_z: reader.read_float()?,
+                inside_block: reader.read_boolean()?,
+                sequence: reader.read_varint()?,
+            })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, net::IpAddr, time::{Duration, Instant}};
+
+/// Allows at most `max_events` per IP address within a sliding `window`.
+pub struct IpThrottle {
+    window: Duration,
+    max_events: usize,
+    events: HashMap<IpAddr, Vec<Instant>>,
+}
+
+impl IpThrottle {
+    pub fn new(window: Duration, max_events: usize) -> Self {
+        Self {
+            window,
+            max_events,
+            events: HashMap::new(),
+        }
+    }
+
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        let window = self.window;
+        if self.events.len() > 256 {
+            self.events.retain(|_, events| events.iter().any(|t| now.duration_since(*t) < window));
+        }
+
+        let events = self.events.entry(ip).or_default();
+        events.retain(|t| now.duration_since(*t) < window);
+
+        if events.len() >= self.max_events {
+            return false;
+        }
+
+        events.push(now);
+        true
+    }
+}
+
+/// Counts packets of a single connection in one second windows.
+pub struct PacketRateLimiter {
+    max_per_second: u32,
+    window_start: Instant,
+    count: u32,
+}
+
+impl PacketRateLimiter {
+    pub fn new(max_per_second: u32, now: Instant) -> Self {
+        Self {
+            max_per_second,
+            window_start: now,
+            count: 0,
+        }
+    }
+
+    pub fn allow(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.count = 0;
+        }
+
+        self.count += 1;
+        self.count <= self.max_per_second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn test_ip_throttle() {
+        let start = Instant::now();
+        let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let other_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut throttle = IpThrottle::new(Duration::from_secs(4), 2);
+
+        assert!(throttle.allow(ip, start));
+        assert!(throttle.allow(ip, start + Duration::from_secs(1)));
+        assert!(!throttle.allow(ip, start + Duration::from_secs(2)));
+        assert!(throttle.allow(other_ip, start + Duration::from_secs(2)));
+
+        // The first event left the window
+        assert!(throttle.allow(ip, start + Duration::from_secs(4)));
+        assert!(!throttle.allow(ip, start + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn test_ip_throttle_prunes_old_entries() {
+        let start = Instant::now();
+        let mut throttle = IpThrottle::new(Duration::from_secs(1), 1);
+
+        for i in 0..=256u32 {
+            assert!(throttle.allow(IpAddr::V4(Ipv4Addr::from(i)), start));
+        }
+
+        assert!(throttle.allow(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), start + Duration::from_secs(2)));
+        assert_eq!(throttle.events.len(), 1);
+    }
+
+    #[test]
+    fn test_packet_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = PacketRateLimiter::new(3, start);
+
+        assert!(limiter.allow(start));
+        assert!(limiter.allow(start));
+        assert!(limiter.allow(start + Duration::from_millis(500)));
+        assert!(!limiter.allow(start + Duration::from_millis(900)));
+
+        assert!(limiter.allow(start + Duration::from_secs(1)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, net::IpAddr, sync::Mutex};
+
+use uuid::Uuid;
+
+use crate::{custom_types::game_profile::GameProfile, network::{connection_handle::ConnectionHandle, packets::play::clientbound::player_info_update::{PlayClientboundPlayerInfoUpdate, PlayerInfoEntry}}};
+
+#[derive(Clone)]
+pub struct OnlinePlayer {
+    pub profile: GameProfile,
+    pub ip: IpAddr,
+    pub handle: ConnectionHandle,
+}
+
+/// Players that finished logging in.
+pub struct PlayerList {
+    players: Mutex<HashMap<Uuid, OnlinePlayer>>,
+    /// Keep alive round trip times in milliseconds, as shown in the tab list.
+    latencies: Mutex<HashMap<Uuid, i32>>,
+}
+
+impl PlayerList {
+    pub fn new() -> Self {
+        Self {
+            players: Mutex::new(HashMap::new()),
+            latencies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns `false` if a player with the same UUID is already online.
+    pub fn add(&self, player: OnlinePlayer) -> bool {
+        let mut players = self.players.lock().unwrap();
+        if players.contains_key(&player.profile.uuid) { return false; }
+
+        players.insert(player.profile.uuid, player);
+        true
+    }
+
+    pub fn remove(&self, uuid: Uuid) {
+        self.players.lock().unwrap().remove(&uuid);
+        self.latencies.lock().unwrap().remove(&uuid);
+    }
+
+    pub fn set_latency(&self, uuid: Uuid, latency: i32) {
+        if self.players.lock().unwrap().contains_key(&uuid) {
+            self.latencies.lock().unwrap().insert(uuid, latency);
+        }
+    }
+
+    pub fn latency(&self, uuid: Uuid) -> i32 {
+        self.latencies.lock().unwrap().get(&uuid).copied().unwrap_or(0)
+    }
+
+    pub fn count(&self) -> usize {
+        self.players.lock().unwrap().len()
+    }
+
+    pub fn profiles(&self) -> Vec<GameProfile> {
+        self.players.lock().unwrap().values().map(|player| player.profile.clone()).collect()
+    }
+
+    pub fn players(&self) -> Vec<OnlinePlayer> {
+        self.players.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<OnlinePlayer> {
+        self.players.lock().unwrap().values().find(|player| player.profile.name.eq_ignore_ascii_case(name)).cloned()
+    }
+
+    pub fn find_by_ip(&self, ip: IpAddr) -> Vec<OnlinePlayer> {
+        self.players.lock().unwrap().values().filter(|player| player.ip == ip).cloned().collect()
+    }
+
+    pub fn broadcast_message(&self, message: &str) {
+        for player in self.players.lock().unwrap().values() {
+            player.handle.send_message(message);
+        }
+    }
+
+    /// Sends `data` to every player in the play state.
+    pub fn broadcast_packet(&self, data: &[u8]) {
+        for player in self.players.lock().unwrap().values() {
+            player.handle.send_play_packet(data.to_vec());
+        }
+    }
+
+    /// Tab list entries of the given players with the given actions.
+    pub fn info_update(&self, players: &[OnlinePlayer], actions: u8) -> PlayClientboundPlayerInfoUpdate {
+        let entries = players.iter()
+            .map(|player| PlayerInfoEntry {
+                uuid: player.profile.uuid,
+                name: player.profile.name.clone(),
+                listed: true,
+                latency: self.latency(player.profile.uuid),
+            })
+            .collect();
+        PlayClientboundPlayerInfoUpdate { actions, entries }
+    }
+}
\ No newline at end of file
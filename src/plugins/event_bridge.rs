@@ -0,0 +1,64 @@
+use rustcraft_plugin_api::{self as api, EventKind};
+
+use crate::{
+    custom_types::game_profile::GameProfile,
+    events::{connection_events::{PlayerLoginEvent, StatusPingEvent}, event_bus::{Event, EventBus, EventPriority, HandlerId}, player_events::{PlayerChatEvent, PlayerJoinEvent, PlayerQuitEvent}},
+};
+
+const DEFAULT_KICK_MESSAGE: &str = "You are not allowed to join this server";
+
+/// An event as plugins see it. Whatever the handler leaves in `message` and `cancelled` is applied to the real event.
+pub struct PluginEvent<'a> {
+    pub kind: EventKind,
+    /// `None` for status pings.
+    pub player: Option<&'a GameProfile>,
+    pub message: Option<String>,
+    pub cancelled: bool,
+}
+
+pub fn priority(priority: api::EventPriority) -> EventPriority {
+    match priority {
+        api::EventPriority::Lowest => EventPriority::Lowest,
+        api::EventPriority::Low => EventPriority::Low,
+        api::EventPriority::Normal => EventPriority::Normal,
+        api::EventPriority::High => EventPriority::High,
+        api::EventPriority::Highest => EventPriority::Highest,
+        api::EventPriority::Monitor => EventPriority::Monitor,
+    }
+}
+
+/// Registers `handler` for the server event behind `kind`.
+pub fn register(events: &EventBus, kind: EventKind, event_priority: api::EventPriority, handler: impl Fn(&mut PluginEvent) + Send + Sync + 'static) -> HandlerId {
+    let call = move |player: Option<&GameProfile>, message: Option<String>, cancelled: bool| {
+        let mut event = PluginEvent { kind, player, message, cancelled };
+        handler(&mut event);
+        (event.message, event.cancelled)
+    };
+
+    let event_priority = priority(event_priority);
+    match kind {
+        EventKind::StatusPing => events.register(event_priority, move |event: &mut StatusPingEvent| {
+            let (motd, cancelled) = call(None, Some(event.motd.clone()), event.is_cancelled());
+            event.motd = motd.unwrap_or_default();
+            event.set_cancelled(cancelled);
+        }),
+        EventKind::PlayerLogin => events.register(event_priority, move |event: &mut PlayerLoginEvent| {
+            let (message, cancelled) = call(Some(&event.profile), event.kick_message.clone(), !event.is_allowed());
+            match cancelled {
+                true => event.disallow(message.as_deref().unwrap_or(DEFAULT_KICK_MESSAGE)),
+                false => event.allow(),
+            }
+        }),
+        EventKind::PlayerJoin => events.register(event_priority, move |event: &mut PlayerJoinEvent| {
+            event.join_message = call(Some(&event.player.profile), event.join_message.take(), false).0;
+        }),
+        EventKind::PlayerQuit => events.register(event_priority, move |event: &mut PlayerQuitEvent| {
+            event.quit_message = call(Some(&event.player.profile), event.quit_message.take(), false).0;
+        }),
+        EventKind::PlayerChat => events.register(event_priority, move |event: &mut PlayerChatEvent| {
+            let (message, cancelled) = call(Some(&event.player.profile), Some(event.message.clone()), event.is_cancelled());
+            event.message = message.unwrap_or_default();
+            event.set_cancelled(cancelled);
+        }),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+pub mod event_bridge;
+pub mod native_plugin;
+pub mod plugin_host;
+pub mod plugin_manager;
+pub mod wasm_plugin;
\ No newline at end of file
@@ -0,0 +1,65 @@
+use std::{collections::HashSet, path::Path, sync::Arc};
+
+use libloading::Library;
+use rustcraft_plugin_api::{InfoFn, LoadFn, PluginApi, UnloadFn, ABI_VERSION, ABI_VERSION_SYMBOL, INFO_SYMBOL, LOAD_SYMBOL, UNLOAD_SYMBOL};
+
+use crate::{events::event_bus::EventBus, player_list::PlayerList, server::ServerData, tick::scheduler::Scheduler, utils::errors::PluginLoadError};
+
+use super::plugin_host::{PluginCommand, PluginHost};
+
+/// A plugin loaded from a dynamic library.
+pub struct NativePlugin {
+    pub name: String,
+    pub version: String,
+    unload: UnloadFn,
+    // The plugin holds pointers to these until it's unloaded
+    api: Box<PluginApi>,
+    host: Box<PluginHost>,
+    // Dropped last, everything above points into it
+    library: Library,
+}
+
+fn symbol<T: Copy>(library: &Library, name: &[u8]) -> Result<T, PluginLoadError> {
+    unsafe { library.get::<T>(name) }
+        .map(|symbol| *symbol)
+        .map_err(|_| PluginLoadError::MissingSymbol(String::from_utf8_lossy(&name[..name.len() - 1]).into_owned()))
+}
+
+impl NativePlugin {
+    /// Opens the library, checks its ABI version and calls its load function.
+    /// Returns the plugin along with the commands it registered, which can't be named like any of `taken`.
+    pub fn load(path: &Path, taken: HashSet<String>, scheduler: &Arc<Scheduler<ServerData>>, events: &Arc<EventBus>, players: &Arc<PlayerList>) -> Result<(Self, Vec<PluginCommand>), PluginLoadError> {
+        // Running the library's initializers is the first thing that trusts the plugin
+        let library = unsafe { Library::new(path) }.map_err(|e| PluginLoadError::Library(e.to_string()))?;
+
+        let abi_version = unsafe { *symbol::<*const u32>(&library, ABI_VERSION_SYMBOL)? };
+        if abi_version != ABI_VERSION {
+            return Err(PluginLoadError::AbiMismatch(abi_version, ABI_VERSION));
+        }
+
+        let info = symbol::<InfoFn>(&library, INFO_SYMBOL)?();
+        let load = symbol::<LoadFn>(&library, LOAD_SYMBOL)?;
+        let unload = symbol::<UnloadFn>(&library, UNLOAD_SYMBOL)?;
+        let (name, version) = unsafe { (info.name.as_str().to_owned(), info.version.as_str().to_owned()) };
+
+        let host = Box::new(PluginHost::new(&name, Arc::clone(scheduler), Arc::clone(events), Arc::clone(players)));
+        let api = Box::new(host.api());
+
+        host.start_loading(taken);
+        let loaded = load(&*api);
+        let commands = host.finish_loading();
+
+        if !loaded {
+            host.clean_up();
+            return Err(PluginLoadError::LoadFailed);
+        }
+
+        Ok((Self { name, version, unload, api, host, library }, commands))
+    }
+
+    /// Undoes everything the plugin registered, then lets it clean up after itself.
+    pub fn unload(self) {
+        self.host.clean_up();
+        (self.unload)();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,235 @@
+use std::{collections::{HashMap, HashSet}, ffi::c_void, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}};
+
+use rustcraft_plugin_api::{self as api, CommandCallback, EventCallback, EventData, EventKind, PluginApi, StrRef, TaskCallback, ABI_VERSION};
+
+use crate::{
+    commands::command_manager::CommandContext,
+    events::event_bus::{EventBus, HandlerId},
+    player_list::PlayerList,
+    server::ServerData,
+    tick::scheduler::{Scheduler, TaskHandle},
+    utils::logger::LogLevel,
+    LOGGER,
+};
+
+use super::event_bridge::{self, PluginEvent};
+
+/// A pointer handed to us by a plugin, which it promised to keep valid while it's loaded.
+#[derive(Clone, Copy)]
+pub struct UserData(pub *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    pub fn as_ptr(self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// A command a plugin registered while loading.
+#[derive(Clone)]
+pub struct PluginCommand {
+    pub name: String,
+    pub description: String,
+    pub op_level: u8,
+    pub callback: CommandCallback,
+    pub user_data: UserData,
+}
+
+impl PluginCommand {
+    pub fn execute(&self, context: &CommandContext, arguments: &str) {
+        let sender = context.sender.name();
+        (self.callback)(self.user_data.as_ptr(), context as *const CommandContext as *const c_void, StrRef::new(&sender), StrRef::new(arguments));
+    }
+}
+
+/// What the server keeps track of for one plugin, so everything it registered can be undone when it's unloaded.
+/// The plugin gets a pointer to this as the API's `host`.
+pub struct PluginHost {
+    pub name: String,
+    scheduler: Arc<Scheduler<ServerData>>,
+    events: Arc<EventBus>,
+    players: Arc<PlayerList>,
+    /// Names that can't be registered, and what the plugin registered. Only there while the plugin is loading.
+    loading: Mutex<Option<(HashSet<String>, Vec<PluginCommand>)>>,
+    handlers: Mutex<Vec<HandlerId>>,
+    tasks: Arc<Mutex<HashMap<u64, TaskHandle>>>,
+    next_task_id: AtomicU64,
+}
+
+/// An event as plugins see it, followed by what the server needs to apply their changes.
+#[repr(C)]
+struct HostEvent<'a> {
+    data: EventData<'a>,
+    message: Option<String>,
+}
+
+impl PluginHost {
+    pub fn new(name: &str, scheduler: Arc<Scheduler<ServerData>>, events: Arc<EventBus>, players: Arc<PlayerList>) -> Self {
+        Self {
+            name: name.to_owned(),
+            scheduler,
+            events,
+            players,
+            loading: Mutex::new(None),
+            handlers: Mutex::new(Vec::new()),
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+            next_task_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn api(&self) -> PluginApi {
+        PluginApi {
+            abi_version: ABI_VERSION,
+            host: self as *const Self as *const c_void,
+            log,
+            register_command,
+            reply,
+            register_event_handler,
+            set_event_message,
+            run_later,
+            run_repeating,
+            cancel_task,
+            broadcast,
+        }
+    }
+
+    /// Lets the plugin register commands until `finish_loading`, except ones named like `taken`.
+    pub fn start_loading(&self, taken: HashSet<String>) {
+        *self.loading.lock().unwrap() = Some((taken, Vec::new()));
+    }
+
+    /// Returns the commands the plugin registered while loading.
+    pub fn finish_loading(&self) -> Vec<PluginCommand> {
+        self.loading.lock().unwrap().take().map(|(_, commands)| commands).unwrap_or_default()
+    }
+
+    /// Cancels the plugin's tasks and removes its event handlers.
+    pub fn clean_up(&self) {
+        for (_, task) in self.tasks.lock().unwrap().drain() {
+            task.cancel();
+        }
+
+        for handler in self.handlers.lock().unwrap().drain(..) {
+            self.events.unregister(handler);
+        }
+    }
+
+    fn add_task(&self, schedule: impl FnOnce(u64) -> TaskHandle) -> u64 {
+        let id = self.next_task_id.fetch_add(1, Ordering::SeqCst);
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.insert(id, schedule(id));
+        id
+    }
+}
+
+/// Hands an event to a plugin's handler and takes over the message and cancellation state it ended up with.
+fn call_handler(callback: EventCallback, user_data: UserData, event: &mut PluginEvent) {
+    let message = event.message.take();
+    let mut host_event = HostEvent {
+        data: EventData {
+            kind: event.kind,
+            player_name: StrRef::new(event.player.map(|profile| profile.name.as_str()).unwrap_or_default()),
+            player_uuid: event.player.map(|profile| *profile.uuid.as_bytes()).unwrap_or_default(),
+            message: StrRef::new(message.as_deref().unwrap_or_default()),
+            has_message: message.is_some(),
+            cancelled: event.cancelled,
+        },
+        message: message.clone(),
+    };
+
+    callback(user_data.as_ptr(), &mut host_event.data);
+    event.message = host_event.message;
+    event.cancelled = host_event.data.cancelled;
+}
+
+fn host<'a>(host: *const c_void) -> &'a PluginHost {
+    // Plugins only ever get pointers to hosts that outlive them
+    unsafe { &*(host as *const PluginHost) }
+}
+
+extern "C" fn log(host_ptr: *const c_void, level: api::LogLevel, message: StrRef) {
+    let level = match level {
+        api::LogLevel::Error => LogLevel::Error,
+        api::LogLevel::Warn => LogLevel::Warn,
+        api::LogLevel::Info => LogLevel::Info,
+        api::LogLevel::Verbose => LogLevel::Verbose,
+        api::LogLevel::Debug => LogLevel::Debug,
+    };
+
+    LOGGER.log(level, &format!("plugins/{}", host(host_ptr).name), unsafe { message.as_str() });
+}
+
+extern "C" fn register_command(host_ptr: *const c_void, name: StrRef, description: StrRef, op_level: u8, callback: CommandCallback, user_data: *mut c_void) -> bool {
+    let name = unsafe { name.as_str() }.to_lowercase();
+    let mut loading = host(host_ptr).loading.lock().unwrap();
+    let Some((taken, commands)) = loading.as_mut() else { return false; };
+
+    if name.is_empty() || name.contains(' ') || !taken.insert(name.clone()) {
+        return false;
+    }
+
+    commands.push(PluginCommand {
+        name,
+        description: unsafe { description.as_str() }.to_owned(),
+        op_level,
+        callback,
+        user_data: UserData(user_data),
+    });
+    true
+}
+
+extern "C" fn reply(source: *const c_void, message: StrRef) {
+    // Only valid while the command callback runs, which is the only time plugins have it
+    let context = unsafe { &*(source as *const CommandContext) };
+    context.reply(unsafe { message.as_str() });
+}
+
+extern "C" fn register_event_handler(host_ptr: *const c_void, kind: EventKind, priority: api::EventPriority, callback: EventCallback, user_data: *mut c_void) {
+    let host = host(host_ptr);
+    let user_data = UserData(user_data);
+
+    let id = event_bridge::register(&host.events, kind, priority, move |event| call_handler(callback, user_data, event));
+    host.handlers.lock().unwrap().push(id);
+}
+
+extern "C" fn set_event_message(event: *mut EventData, message: StrRef) {
+    // Every `EventData` plugins get is the start of a `HostEvent`
+    let event = unsafe { &mut *(event as *mut HostEvent) };
+    let message = unsafe { message.as_str() };
+
+    event.message = (!message.is_empty()).then(|| message.to_owned());
+    event.data.has_message = event.message.is_some();
+    // Points into the string owned by the event, which lives until the handler returns
+    let message: *const str = event.message.as_deref().unwrap_or_default();
+    event.data.message = StrRef::new(unsafe { &*message });
+}
+
+extern "C" fn run_later(host_ptr: *const c_void, delay: u64, callback: TaskCallback, user_data: *mut c_void) -> u64 {
+    let host = host(host_ptr);
+    let user_data = UserData(user_data);
+    let tasks = Arc::clone(&host.tasks);
+
+    host.add_task(|id| host.scheduler.run_later(delay, move |_| {
+        tasks.lock().unwrap().remove(&id);
+        callback(user_data.as_ptr());
+    }))
+}
+
+extern "C" fn run_repeating(host_ptr: *const c_void, delay: u64, period: u64, callback: TaskCallback, user_data: *mut c_void) -> u64 {
+    let host = host(host_ptr);
+    let user_data = UserData(user_data);
+
+    host.add_task(|_| host.scheduler.run_repeating(delay, period, move |_| callback(user_data.as_ptr())))
+}
+
+extern "C" fn cancel_task(host_ptr: *const c_void, task: u64) {
+    if let Some(task) = host(host_ptr).tasks.lock().unwrap().remove(&task) {
+        task.cancel();
+    }
+}
+
+extern "C" fn broadcast(host_ptr: *const c_void, message: StrRef) {
+    host(host_ptr).players.broadcast_message(unsafe { message.as_str() });
+}
\ No newline at end of file
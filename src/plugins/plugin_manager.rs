@@ -0,0 +1,277 @@
+use std::{collections::HashMap, env::consts::DLL_EXTENSION, fs, io, path::{Path, PathBuf}, sync::{Arc, Mutex, RwLock}, time::SystemTime};
+
+use wasmi::Engine;
+
+use crate::{
+    commands::{arguments::ArgumentType, command_manager::{CommandContext, CommandManager}, node::{argument, command}},
+    events::event_bus::EventBus,
+    log,
+    player_list::PlayerList,
+    server::ServerData,
+    tick::scheduler::Scheduler,
+    utils::errors::PluginLoadError,
+    CONFIG,
+    LOGGER,
+};
+
+use super::{native_plugin::NativePlugin, wasm_plugin::{self, WasmCommand, WasmLimits, WasmPlugin, WASM_EXTENSION}};
+
+pub const PLUGINS_DIRECTORY: &str = "plugins";
+/// Ticks between checks for changed WebAssembly plugins.
+pub const HOT_RELOAD_INTERVAL: u64 = 20;
+
+type CommandHandler = Arc<dyn Fn(&CommandContext, &str) -> Result<(), String> + Send + Sync>;
+
+struct RegisteredCommand {
+    plugin: String,
+    handler: CommandHandler,
+}
+
+/// The loaded plugins, and the commands they registered.
+pub struct PluginManager {
+    scheduler: Arc<Scheduler<ServerData>>,
+    events: Arc<EventBus>,
+    players: Arc<PlayerList>,
+    engine: Engine,
+    native_plugins: Mutex<Vec<NativePlugin>>,
+    wasm_plugins: Mutex<Vec<WasmPlugin>>,
+    /// When each WebAssembly plugin's file was modified the last time we tried to load it.
+    wasm_files: Mutex<HashMap<PathBuf, Option<SystemTime>>>,
+    commands: RwLock<HashMap<String, RegisteredCommand>>,
+}
+
+impl PluginManager {
+    pub fn new(scheduler: Arc<Scheduler<ServerData>>, events: Arc<EventBus>, players: Arc<PlayerList>) -> Self {
+        Self {
+            scheduler,
+            events,
+            players,
+            engine: wasm_plugin::new_engine(),
+            native_plugins: Mutex::new(Vec::new()),
+            wasm_plugins: Mutex::new(Vec::new()),
+            wasm_files: Mutex::new(HashMap::new()),
+            commands: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Loads every dynamic library and WebAssembly module in `directory`, creating it if it doesn't exist yet.
+    /// Plugin commands end up in `commands`, which is why this has to happen before the server starts.
+    pub fn load_all(&self, directory: &Path, commands: &mut CommandManager) {
+        if let Err(e) = fs::create_dir_all(directory) {
+            log!(warn, "Failed to create the {} directory: {}", directory.display(), e);
+            return;
+        }
+
+        let paths = match plugin_files(directory) {
+            Ok(paths) => paths,
+            Err(e) => {
+                log!(error, "Failed to read the {} directory: {}", directory.display(), e);
+                return;
+            }
+        };
+
+        for path in paths {
+            let taken = commands.commands().map(|command| command.name().to_owned()).collect();
+
+            if is_wasm(&path) {
+                self.wasm_files.lock().unwrap().insert(path.clone(), modified(&path));
+                match WasmPlugin::load(&self.engine, &path, taken, wasm_limits(&path), &self.events, &self.players) {
+                    Ok((mut plugin, plugin_commands)) => {
+                        log!(info, "Loaded WebAssembly plugin {}", plugin.name);
+                        plugin.register_handlers();
+                        for plugin_command in plugin_commands {
+                            let handler = Arc::new(plugin.command_handler(plugin_command.handler));
+                            self.register_command(commands, &plugin.name, &plugin_command.name, &plugin_command.description, plugin_command.op_level, handler);
+                        }
+                        self.wasm_plugins.lock().unwrap().push(plugin);
+                    },
+                    Err(e) => log!(error, "Failed to load plugin {}: {}", path.display(), e),
+                }
+                continue;
+            }
+
+            match NativePlugin::load(&path, taken, &self.scheduler, &self.events, &self.players) {
+                Ok((plugin, plugin_commands)) => {
+                    log!(info, "Loaded plugin {} v{}", plugin.name, plugin.version);
+                    for plugin_command in plugin_commands {
+                        let (name, description, op_level) = (plugin_command.name.clone(), plugin_command.description.clone(), plugin_command.op_level);
+                        let handler = Arc::new(move |context: &CommandContext, arguments: &str| {
+                            plugin_command.execute(context, arguments);
+                            Ok(())
+                        });
+                        self.register_command(commands, &plugin.name, &name, &description, op_level, handler);
+                    }
+                    self.native_plugins.lock().unwrap().push(plugin);
+                },
+                Err(e) => log!(error, "Failed to load plugin {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn register_command(&self, commands: &mut CommandManager, plugin: &str, name: &str, description: &str, op_level: u8, handler: CommandHandler) {
+        commands.register(command(name, op_level)
+            .describe(description)
+            .executes(run_plugin_command)
+            .then(argument("arguments", ArgumentType::greedy_string()).executes(run_plugin_command)));
+
+        self.commands.write().unwrap().insert(name.to_owned(), RegisteredCommand { plugin: plugin.to_owned(), handler });
+    }
+
+    /// Reloads WebAssembly plugins whose file changed, loads new ones and unloads the ones that were removed.
+    /// Commands can't be added while the server runs, so a reloaded plugin only gets back the commands it had before.
+    pub fn reload_changed(&self, directory: &Path, commands: &CommandManager) {
+        let Ok(paths) = plugin_files(directory) else { return; };
+        let paths: Vec<PathBuf> = paths.into_iter().filter(|path| is_wasm(path)).collect();
+        let mut files = self.wasm_files.lock().unwrap();
+
+        let removed: Vec<PathBuf> = files.keys().filter(|path| !paths.contains(path)).cloned().collect();
+        for path in removed {
+            files.remove(&path);
+            if let Some(plugin) = self.take_wasm_plugin(&path) {
+                log!(info, "Unloading WebAssembly plugin {}, its file was removed", plugin.name);
+                self.unload_wasm_plugin(plugin);
+            }
+        }
+
+        for path in paths {
+            let modified = modified(&path);
+            if files.get(&path) == Some(&modified) { continue; }
+
+            files.insert(path.clone(), modified);
+            self.reload_wasm_plugin(&path, commands);
+        }
+    }
+
+    /// Loads the new version on a worker thread, so a big module doesn't hold up the tick, then swaps it in on the tick thread.
+    fn reload_wasm_plugin(&self, path: &Path, commands: &CommandManager) {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let taken = {
+            let registered = self.commands.read().unwrap();
+            commands.commands()
+                .map(|command| command.name().to_owned())
+                .filter(|command| registered.get(command).is_none_or(|registered| registered.plugin != name))
+                .collect()
+        };
+
+        let (engine, events, players) = (self.engine.clone(), Arc::clone(&self.events), Arc::clone(&self.players));
+        let limits = wasm_limits(path);
+        let path = path.to_owned();
+        let load_path = path.clone();
+        self.scheduler.run_async(
+            move || WasmPlugin::load(&engine, &load_path, taken, limits, &events, &players),
+            move |server, loaded| server.plugins.swap_wasm_plugin(&path, loaded, &server.commands),
+        );
+    }
+
+    fn swap_wasm_plugin(&self, path: &Path, loaded: Result<(WasmPlugin, Vec<WasmCommand>), PluginLoadError>, commands: &CommandManager) {
+        let (mut plugin, plugin_commands) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log!(error, "Failed to load plugin {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        // The file was removed while the new version was loading
+        if !self.wasm_files.lock().unwrap().contains_key(path) {
+            plugin.unload();
+            return;
+        }
+
+        // The old version keeps running until the new one loaded, and its handlers are gone before the new ones are added
+        match self.take_wasm_plugin(path) {
+            Some(old) => {
+                self.unload_wasm_plugin(old);
+                log!(info, "Reloaded WebAssembly plugin {}", plugin.name);
+            },
+            None => log!(info, "Loaded WebAssembly plugin {}", plugin.name),
+        }
+        plugin.register_handlers();
+
+        for plugin_command in plugin_commands {
+            if commands.find(&plugin_command.name).is_none() {
+                log!(warn, "{} registered /{}, which will only be available after a restart", plugin.name, plugin_command.name);
+                continue;
+            }
+
+            let handler = Arc::new(plugin.command_handler(plugin_command.handler));
+            self.commands.write().unwrap().insert(plugin_command.name, RegisteredCommand { plugin: plugin.name.clone(), handler });
+        }
+        self.wasm_plugins.lock().unwrap().push(plugin);
+    }
+
+    fn take_wasm_plugin(&self, path: &Path) -> Option<WasmPlugin> {
+        let mut plugins = self.wasm_plugins.lock().unwrap();
+        let index = plugins.iter().position(|plugin| plugin.path == path)?;
+        Some(plugins.remove(index))
+    }
+
+    fn unload_wasm_plugin(&self, plugin: WasmPlugin) {
+        self.commands.write().unwrap().retain(|_, command| command.plugin != plugin.name);
+        plugin.unload();
+    }
+
+    /// Names and versions of the loaded plugins.
+    pub fn plugins(&self) -> Vec<String> {
+        let native = self.native_plugins.lock().unwrap().iter().map(|plugin| format!("{} v{}", plugin.name, plugin.version)).collect::<Vec<_>>();
+        let wasm = self.wasm_plugins.lock().unwrap().iter().map(|plugin| format!("{} (WebAssembly)", plugin.name)).collect::<Vec<_>>();
+        [native, wasm].concat()
+    }
+
+    /// Unloads the plugins in the reverse order they were loaded. Their commands stay registered, but stop working.
+    pub fn unload_all(&self) {
+        self.commands.write().unwrap().clear();
+
+        let mut wasm_plugins = self.wasm_plugins.lock().unwrap();
+        while let Some(plugin) = wasm_plugins.pop() {
+            log!(info, "Unloading WebAssembly plugin {}", plugin.name);
+            plugin.unload();
+        }
+
+        let mut native_plugins = self.native_plugins.lock().unwrap();
+        while let Some(plugin) = native_plugins.pop() {
+            log!(info, "Unloading plugin {} v{}", plugin.name, plugin.version);
+            plugin.unload();
+        }
+    }
+}
+
+/// Dynamic libraries and WebAssembly modules in `directory`, sorted by name.
+fn plugin_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<_> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == DLL_EXTENSION || extension == WASM_EXTENSION))
+        .collect();
+
+    paths.sort();
+    Ok(paths)
+}
+
+fn is_wasm(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == WASM_EXTENSION)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// The configured limits, with the plugin's overrides applied.
+fn wasm_limits(path: &Path) -> WasmLimits {
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    let overrides = CONFIG.plugins.limits.get(name.as_ref());
+
+    WasmLimits {
+        fuel: overrides.and_then(|limits| limits.wasm_fuel).unwrap_or(CONFIG.plugins.wasm_fuel),
+        max_memory: overrides.and_then(|limits| limits.wasm_memory_mb).unwrap_or(CONFIG.plugins.wasm_memory_mb) * 1024 * 1024,
+    }
+}
+
+fn run_plugin_command(context: &CommandContext) -> Result<(), String> {
+    let name = context.input.split(' ').next().unwrap_or_default().to_lowercase();
+    let handler = context.server.plugins.commands.read().unwrap().get(&name).map(|command| Arc::clone(&command.handler));
+
+    match handler {
+        Some(handler) => handler(context, context.get_string("arguments").unwrap_or_default()),
+        None => Err(format!("The plugin providing /{} isn't loaded", name)),
+    }
+}
\ No newline at end of file
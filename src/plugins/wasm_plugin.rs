@@ -0,0 +1,542 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use rustcraft_plugin_api::{self as api, EventKind};
+use wasmi::{core::TrapCode, Caller, Config, Engine, Error, Extern, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, WasmParams, WasmResults};
+
+use crate::{
+    commands::{command_manager::CommandContext, command_sender::CommandSender},
+    custom_types::game_profile::GameProfile,
+    events::event_bus::{EventBus, HandlerId},
+    player_list::PlayerList,
+    utils::{errors::PluginLoadError, logger::LogLevel},
+    LOGGER,
+};
+
+use super::event_bridge::{self, PluginEvent};
+
+/// The import module holding the host functions.
+pub const HOST_MODULE: &str = "rustcraft";
+pub const WASM_EXTENSION: &str = "wasm";
+
+const LOAD_EXPORT: &str = "rustcraft_load";
+const UNLOAD_EXPORT: &str = "rustcraft_unload";
+const COMMAND_EXPORT: &str = "rustcraft_on_command";
+const EVENT_EXPORT: &str = "rustcraft_on_event";
+
+/// How much a plugin may do. Each plugin gets its own store, so one plugin running out doesn't affect the others.
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Fuel for every call into the plugin, roughly one unit per instruction.
+    pub fuel: u64,
+    /// Bytes of linear memory.
+    pub max_memory: usize,
+}
+
+/// A command a WebAssembly plugin registered while loading. `handler` is passed back to `rustcraft_on_command`.
+pub struct WasmCommand {
+    pub name: String,
+    pub description: String,
+    pub op_level: u8,
+    pub handler: i32,
+}
+
+#[derive(Default)]
+struct Registrations {
+    taken: HashSet<String>,
+    commands: Vec<WasmCommand>,
+    handlers: Vec<(EventKind, api::EventPriority, i32)>,
+}
+
+struct EventState {
+    player: Option<GameProfile>,
+    message: Option<String>,
+    cancelled: bool,
+}
+
+/// What host functions can reach.
+struct WasmState {
+    name: String,
+    players: Arc<PlayerList>,
+    limits: StoreLimits,
+    /// Only there while the plugin is loading.
+    loading: Option<Registrations>,
+    /// The sender and arguments of the command being run.
+    command: Option<(CommandSender, String)>,
+    event: Option<EventState>,
+}
+
+struct WasmRuntime {
+    store: Store<WasmState>,
+    instance: Instance,
+    fuel: u64,
+    /// Set once the plugin trapped, after which its state can't be trusted anymore.
+    disabled: bool,
+}
+
+impl WasmRuntime {
+    fn has_export(&self, name: &str) -> bool {
+        self.instance.get_func(&self.store, name).is_some()
+    }
+
+    /// Calls an export with a fresh allowance of fuel. A trap disables the plugin.
+    fn call<P: WasmParams, R: WasmResults>(&mut self, export: &str, params: P) -> Result<R, String> {
+        if self.disabled {
+            return Err(format!("{} is disabled", self.store.data().name));
+        }
+
+        let function = self.instance.get_typed_func::<P, R>(&self.store, export).map_err(|e| format!("Bad export {}: {}", export, e))?;
+        self.store.set_fuel(self.fuel).map_err(|e| e.to_string())?;
+
+        function.call(&mut self.store, params).map_err(|e| {
+            self.disabled = true;
+            let reason = match e.as_trap_code() {
+                Some(TrapCode::OutOfFuel) => "it ran out of fuel".to_owned(),
+                _ => e.to_string(),
+            };
+            format!("{} failed in {}: {}. It's disabled until it gets reloaded", self.store.data().name, export, reason)
+        })
+    }
+
+    fn on_command(&mut self, handler: i32, sender: &CommandSender, arguments: &str) -> Result<(), String> {
+        self.store.data_mut().command = Some((sender.clone(), arguments.to_owned()));
+        let result = self.call::<i32, ()>(COMMAND_EXPORT, handler);
+        self.store.data_mut().command = None;
+        result
+    }
+
+    fn on_event(&mut self, handler: i32, event: &mut PluginEvent) -> Result<(), String> {
+        self.store.data_mut().event = Some(EventState { player: event.player.cloned(), message: event.message.take(), cancelled: event.cancelled });
+        let result = self.call::<(i32, i32), ()>(EVENT_EXPORT, (handler, event.kind as i32));
+
+        if let Some(state) = self.store.data_mut().event.take() {
+            event.message = state.message;
+            event.cancelled = state.cancelled;
+        }
+        result
+    }
+}
+
+/// A plugin compiled to WebAssembly. It can only reach the server through the host functions in `HOST_MODULE`,
+/// and traps or running out of fuel only disable the plugin.
+pub struct WasmPlugin {
+    pub name: String,
+    pub path: PathBuf,
+    runtime: Arc<Mutex<WasmRuntime>>,
+    events: Arc<EventBus>,
+    /// The event handlers `rustcraft_load` asked for, registered by `register_handlers`.
+    event_handlers: Vec<(EventKind, api::EventPriority, i32)>,
+    handlers: Vec<HandlerId>,
+}
+
+/// Fuel metering has to be switched on for the whole engine.
+pub fn new_engine() -> Engine {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+impl WasmPlugin {
+    /// Loads the module at `path`, named after the file. See `new`.
+    pub fn load(engine: &Engine, path: &Path, taken: HashSet<String>, limits: WasmLimits, events: &Arc<EventBus>, players: &Arc<PlayerList>) -> Result<(Self, Vec<WasmCommand>), PluginLoadError> {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let wasm = fs::read(path).map_err(|e| PluginLoadError::Library(e.to_string()))?;
+
+        let (mut plugin, commands) = Self::new(engine, &name, &wasm, taken, limits, events, players)?;
+        plugin.path = path.to_owned();
+        Ok((plugin, commands))
+    }
+
+    /// Instantiates the module and calls its `rustcraft_load`, which is where it registers commands and event handlers.
+    /// Returns the plugin along with the commands it registered, which can't be named like any of `taken`.
+    /// Its event handlers only get called after `register_handlers`.
+    pub fn new(engine: &Engine, name: &str, wasm: &[u8], taken: HashSet<String>, limits: WasmLimits, events: &Arc<EventBus>, players: &Arc<PlayerList>) -> Result<(Self, Vec<WasmCommand>), PluginLoadError> {
+        let module = Module::new(engine, wasm).map_err(|e| PluginLoadError::Wasm(e.to_string()))?;
+
+        let mut store = Store::new(engine, WasmState {
+            name: name.to_owned(),
+            players: Arc::clone(players),
+            limits: StoreLimitsBuilder::new().memory_size(limits.max_memory).instances(1).build(),
+            loading: Some(Registrations { taken, ..Default::default() }),
+            command: None,
+            event: None,
+        });
+        store.limiter(|state| &mut state.limits);
+        // The start function runs during instantiation
+        store.set_fuel(limits.fuel).map_err(|e| PluginLoadError::Wasm(e.to_string()))?;
+
+        let instance = host_functions(engine)
+            .and_then(|linker| linker.instantiate(&mut store, &module))
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| PluginLoadError::Wasm(e.to_string()))?;
+
+        let mut runtime = WasmRuntime { store, instance, fuel: limits.fuel, disabled: false };
+        if !runtime.has_export(LOAD_EXPORT) {
+            return Err(PluginLoadError::MissingSymbol(LOAD_EXPORT.to_owned()));
+        }
+
+        let loaded = runtime.call::<(), i32>(LOAD_EXPORT, ()).map_err(PluginLoadError::Wasm)?;
+        let registrations = runtime.store.data_mut().loading.take().unwrap_or_default();
+        if loaded == 0 {
+            return Err(PluginLoadError::LoadFailed);
+        }
+
+        if !registrations.commands.is_empty() && !runtime.has_export(COMMAND_EXPORT) {
+            return Err(PluginLoadError::MissingSymbol(COMMAND_EXPORT.to_owned()));
+        }
+        if !registrations.handlers.is_empty() && !runtime.has_export(EVENT_EXPORT) {
+            return Err(PluginLoadError::MissingSymbol(EVENT_EXPORT.to_owned()));
+        }
+
+        let plugin = Self {
+            name: name.to_owned(),
+            path: PathBuf::new(),
+            runtime: Arc::new(Mutex::new(runtime)),
+            events: Arc::clone(events),
+            event_handlers: registrations.handlers,
+            handlers: Vec::new(),
+        };
+        Ok((plugin, registrations.commands))
+    }
+
+    /// Registers the plugin's event handlers. Kept apart from loading, so a reloaded plugin's handlers
+    /// are only added once the old version's are gone.
+    pub fn register_handlers(&mut self) {
+        self.handlers = self.event_handlers.iter().map(|&(kind, priority, handler)| {
+            let runtime = Arc::clone(&self.runtime);
+            let name = self.name.clone();
+            event_bridge::register(&self.events, kind, priority, move |event| {
+                if let Err(e) = runtime.lock().unwrap().on_event(handler, event) {
+                    LOGGER.log(LogLevel::Error, &format!("plugins/{}", name), &e);
+                }
+            })
+        }).collect();
+    }
+
+    /// Runs one of the plugin's commands through `rustcraft_on_command`.
+    pub fn command_handler(&self, handler: i32) -> impl Fn(&CommandContext, &str) -> Result<(), String> + Send + Sync + 'static {
+        let runtime = Arc::clone(&self.runtime);
+        let name = self.name.clone();
+
+        move |context, arguments| runtime.lock().unwrap().on_command(handler, context.sender, arguments).map_err(|e| {
+            LOGGER.log(LogLevel::Error, &format!("plugins/{}", name), &e);
+            format!("{} failed to run the command", name)
+        })
+    }
+
+    /// Removes the plugin's event handlers, then calls its `rustcraft_unload` if it has one.
+    pub fn unload(self) {
+        for handler in self.handlers {
+            self.events.unregister(handler);
+        }
+
+        let mut runtime = self.runtime.lock().unwrap();
+        if runtime.has_export(UNLOAD_EXPORT) {
+            if let Err(e) = runtime.call::<(), ()>(UNLOAD_EXPORT, ()) {
+                LOGGER.log(LogLevel::Error, &format!("plugins/{}", self.name), &e);
+            }
+        }
+    }
+}
+
+fn memory(caller: &Caller<'_, WasmState>) -> Result<Memory, Error> {
+    caller.get_export("memory").and_then(Extern::into_memory).ok_or_else(|| Error::new("the plugin doesn't export its memory"))
+}
+
+fn read_string(caller: &Caller<'_, WasmState>, ptr: i32, len: i32) -> Result<String, Error> {
+    let memory = memory(caller)?;
+    let start = ptr as u32 as usize;
+    let bytes = usize::try_from(len).ok()
+        .and_then(|len| memory.data(caller).get(start..start.checked_add(len)?))
+        .ok_or_else(|| Error::new("string out of bounds"))?;
+
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::new("string isn't valid UTF-8"))
+}
+
+/// Copies `bytes` into the plugin's buffer if they fit. Returns how many bytes there are either way,
+/// so the plugin can retry with a bigger buffer.
+fn write_bytes(caller: &mut Caller<'_, WasmState>, ptr: i32, capacity: i32, bytes: &[u8]) -> Result<i32, Error> {
+    if bytes.len() <= capacity.max(0) as usize {
+        memory(caller)?.write(&mut *caller, ptr as u32 as usize, bytes).map_err(|_| Error::new("buffer out of bounds"))?;
+    }
+
+    Ok(bytes.len() as i32)
+}
+
+fn log_source(caller: &Caller<'_, WasmState>) -> String {
+    format!("plugins/{}", caller.data().name)
+}
+
+fn event_kind(kind: i32) -> Option<EventKind> {
+    match kind {
+        0 => Some(EventKind::StatusPing),
+        1 => Some(EventKind::PlayerLogin),
+        2 => Some(EventKind::PlayerJoin),
+        3 => Some(EventKind::PlayerQuit),
+        4 => Some(EventKind::PlayerChat),
+        _ => None,
+    }
+}
+
+fn event_priority(priority: i32) -> Option<api::EventPriority> {
+    match priority {
+        0 => Some(api::EventPriority::Lowest),
+        1 => Some(api::EventPriority::Low),
+        2 => Some(api::EventPriority::Normal),
+        3 => Some(api::EventPriority::High),
+        4 => Some(api::EventPriority::Highest),
+        5 => Some(api::EventPriority::Monitor),
+        _ => None,
+    }
+}
+
+/// Everything plugins can import. Strings are passed as pointer and length. Functions filling a buffer return the
+/// full length, or -1 if there's nothing to return.
+fn host_functions(engine: &Engine) -> Result<Linker<WasmState>, Error> {
+    let mut linker = Linker::new(engine);
+
+    linker.func_wrap(HOST_MODULE, "log", |caller: Caller<'_, WasmState>, level: i32, ptr: i32, len: i32| -> Result<(), Error> {
+        let level = match level {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warn,
+            3 => LogLevel::Verbose,
+            4 => LogLevel::Debug,
+            _ => LogLevel::Info,
+        };
+
+        LOGGER.log(level, &log_source(&caller), &read_string(&caller, ptr, len)?);
+        Ok(())
+    })?;
+
+    // Only works while loading. Returns 1 if the command was registered
+    linker.func_wrap(HOST_MODULE, "register_command", |mut caller: Caller<'_, WasmState>, name_ptr: i32, name_len: i32, description_ptr: i32, description_len: i32, op_level: i32, handler: i32| -> Result<i32, Error> {
+        let name = read_string(&caller, name_ptr, name_len)?.to_lowercase();
+        let description = read_string(&caller, description_ptr, description_len)?;
+        let Some(loading) = caller.data_mut().loading.as_mut() else { return Ok(0); };
+
+        if name.is_empty() || name.contains(' ') || !loading.taken.insert(name.clone()) {
+            return Ok(0);
+        }
+
+        loading.commands.push(WasmCommand { name, description, op_level: op_level.clamp(0, 4) as u8, handler });
+        Ok(1)
+    })?;
+
+    // Only works while loading. Kinds and priorities are numbered like in the native plugin API
+    linker.func_wrap(HOST_MODULE, "register_event", |mut caller: Caller<'_, WasmState>, kind: i32, priority: i32, handler: i32| -> i32 {
+        match (caller.data_mut().loading.as_mut(), event_kind(kind), event_priority(priority)) {
+            (Some(loading), Some(kind), Some(priority)) => {
+                loading.handlers.push((kind, priority, handler));
+                1
+            },
+            _ => 0,
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "broadcast", |caller: Caller<'_, WasmState>, ptr: i32, len: i32| -> Result<(), Error> {
+        caller.data().players.broadcast_message(&read_string(&caller, ptr, len)?);
+        Ok(())
+    })?;
+
+    // Returns 1 if the player is online
+    linker.func_wrap(HOST_MODULE, "send_message", |caller: Caller<'_, WasmState>, name_ptr: i32, name_len: i32, message_ptr: i32, message_len: i32| -> Result<i32, Error> {
+        let name = read_string(&caller, name_ptr, name_len)?;
+        let message = read_string(&caller, message_ptr, message_len)?;
+
+        match caller.data().players.find_by_name(&name) {
+            Some(player) => {
+                player.handle.send_message(&message);
+                Ok(1)
+            },
+            None => Ok(0),
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "player_count", |caller: Caller<'_, WasmState>| -> i32 {
+        caller.data().players.count() as i32
+    })?;
+
+    // The names of everyone online, sorted and separated by newlines
+    linker.func_wrap(HOST_MODULE, "player_names", |mut caller: Caller<'_, WasmState>, ptr: i32, capacity: i32| -> Result<i32, Error> {
+        let mut names: Vec<String> = caller.data().players.profiles().into_iter().map(|profile| profile.name).collect();
+        names.sort();
+        write_bytes(&mut caller, ptr, capacity, names.join("\n").as_bytes())
+    })?;
+
+    // Writes the player's 16 byte UUID. Returns 1 if the player is online
+    linker.func_wrap(HOST_MODULE, "player_uuid", |mut caller: Caller<'_, WasmState>, name_ptr: i32, name_len: i32, ptr: i32| -> Result<i32, Error> {
+        let name = read_string(&caller, name_ptr, name_len)?;
+        match caller.data().players.find_by_name(&name) {
+            Some(player) => write_bytes(&mut caller, ptr, 16, player.profile.uuid.as_bytes()).map(|_| 1),
+            None => Ok(0),
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "command_sender", |mut caller: Caller<'_, WasmState>, ptr: i32, capacity: i32| -> Result<i32, Error> {
+        match caller.data().command.as_ref().map(|(sender, _)| sender.name()) {
+            Some(sender) => write_bytes(&mut caller, ptr, capacity, sender.as_bytes()),
+            None => Ok(-1),
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "command_arguments", |mut caller: Caller<'_, WasmState>, ptr: i32, capacity: i32| -> Result<i32, Error> {
+        match caller.data().command.as_ref().map(|(_, arguments)| arguments.clone()) {
+            Some(arguments) => write_bytes(&mut caller, ptr, capacity, arguments.as_bytes()),
+            None => Ok(-1),
+        }
+    })?;
+
+    // Sends a message to whoever ran the current command
+    linker.func_wrap(HOST_MODULE, "reply", |caller: Caller<'_, WasmState>, ptr: i32, len: i32| -> Result<(), Error> {
+        let message = read_string(&caller, ptr, len)?;
+        if let Some((sender, _)) = &caller.data().command {
+            sender.send_message(&message);
+        }
+        Ok(())
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "event_player", |mut caller: Caller<'_, WasmState>, ptr: i32, capacity: i32| -> Result<i32, Error> {
+        match caller.data().event.as_ref().and_then(|event| event.player.as_ref()).map(|player| player.name.clone()) {
+            Some(name) => write_bytes(&mut caller, ptr, capacity, name.as_bytes()),
+            None => Ok(-1),
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "event_message", |mut caller: Caller<'_, WasmState>, ptr: i32, capacity: i32| -> Result<i32, Error> {
+        match caller.data().event.as_ref().and_then(|event| event.message.clone()) {
+            Some(message) => write_bytes(&mut caller, ptr, capacity, message.as_bytes()),
+            None => Ok(-1),
+        }
+    })?;
+
+    // An empty message clears it
+    linker.func_wrap(HOST_MODULE, "set_event_message", |mut caller: Caller<'_, WasmState>, ptr: i32, len: i32| -> Result<(), Error> {
+        let message = read_string(&caller, ptr, len)?;
+        if let Some(event) = caller.data_mut().event.as_mut() {
+            event.message = (!message.is_empty()).then_some(message);
+        }
+        Ok(())
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "event_cancelled", |caller: Caller<'_, WasmState>| -> i32 {
+        caller.data().event.as_ref().is_some_and(|event| event.cancelled) as i32
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "set_event_cancelled", |mut caller: Caller<'_, WasmState>, cancelled: i32| {
+        if let Some(event) = caller.data_mut().event.as_mut() {
+            event.cancelled = cancelled != 0;
+        }
+    })?;
+
+    Ok(linker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: WasmLimits = WasmLimits { fuel: 100_000, max_memory: 1 << 20 };
+
+    fn load(source: &str, taken: &[&str]) -> Result<(WasmPlugin, Vec<WasmCommand>), PluginLoadError> {
+        let wasm = wat::parse_str(source).unwrap();
+        let taken = taken.iter().map(|name| name.to_string()).collect();
+        WasmPlugin::new(&new_engine(), "test", &wasm, taken, LIMITS, &Arc::new(EventBus::new()), &Arc::new(PlayerList::new()))
+    }
+
+    fn chat_event(plugin: &WasmPlugin, message: &str) -> (Result<(), String>, PluginEvent<'static>) {
+        let mut event = PluginEvent { kind: EventKind::PlayerChat, player: None, message: Some(message.to_owned()), cancelled: false };
+        let result = plugin.runtime.lock().unwrap().on_event(0, &mut event);
+        (result, event)
+    }
+
+    #[test]
+    fn test_commands_and_events() {
+        let (mut plugin, commands) = load(r#"
+            (module
+                (import "rustcraft" "register_command" (func $register_command (param i32 i32 i32 i32 i32 i32) (result i32)))
+                (import "rustcraft" "register_event" (func $register_event (param i32 i32 i32) (result i32)))
+                (import "rustcraft" "command_arguments" (func $command_arguments (param i32 i32) (result i32)))
+                (import "rustcraft" "event_message" (func $event_message (param i32 i32) (result i32)))
+                (import "rustcraft" "set_event_message" (func $set_event_message (param i32 i32)))
+                (import "rustcraft" "set_event_cancelled" (func $set_event_cancelled (param i32)))
+                (memory (export "memory") 1)
+                (global $arguments_len (export "arguments_len") (mut i32) (i32.const 0))
+                (data (i32.const 0) "HelloTaken")
+                (data (i32.const 96) "[w] ")
+                (func (export "rustcraft_load") (result i32)
+                    (drop (call $register_command (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 0) (i32.const 2) (i32.const 7)))
+                    (drop (call $register_command (i32.const 5) (i32.const 5) (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 8)))
+                    (drop (call $register_event (i32.const 4) (i32.const 2) (i32.const 0)))
+                    (i32.const 1))
+                (func (export "rustcraft_on_command") (param $handler i32)
+                    (global.set $arguments_len (call $command_arguments (i32.const 200) (i32.const 100))))
+                (func (export "rustcraft_on_event") (param $handler i32) (param $kind i32)
+                    (local $len i32)
+                    (local.set $len (call $event_message (i32.const 100) (i32.const 96)))
+                    (call $set_event_message (i32.const 96) (i32.add (local.get $len) (i32.const 4)))
+                    (call $set_event_cancelled (i32.eq (local.get $len) (i32.const 4)))))
+        "#, &["taken"]).unwrap();
+
+        assert_eq!(commands.len(), 1);
+        assert_eq!((commands[0].name.as_str(), commands[0].op_level, commands[0].handler), ("hello", 2, 7));
+        // Nothing is registered on the event bus before `register_handlers`
+        assert_eq!((plugin.event_handlers.len(), plugin.handlers.len()), (1, 0));
+        plugin.register_handlers();
+        assert_eq!(plugin.handlers.len(), 1);
+
+        let (result, event) = chat_event(&plugin, "hi there");
+        assert!(result.is_ok());
+        assert_eq!((event.message.as_deref(), event.cancelled), (Some("[w] hi there"), false));
+        assert!(chat_event(&plugin, "spam").1.cancelled);
+
+        let mut runtime = plugin.runtime.lock().unwrap();
+        runtime.on_command(7, &CommandSender::Console, "some arguments").unwrap();
+        let arguments_len = runtime.instance.get_global(&runtime.store, "arguments_len").unwrap().get(&runtime.store).i32();
+        let memory = runtime.instance.get_memory(&runtime.store, "memory").unwrap();
+        assert_eq!(&memory.data(&runtime.store)[200..200 + arguments_len.unwrap() as usize], b"some arguments");
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let (plugin, _) = load(r#"
+            (module
+                (import "rustcraft" "register_event" (func $register_event (param i32 i32 i32) (result i32)))
+                (func (export "rustcraft_load") (result i32)
+                    (call $register_event (i32.const 4) (i32.const 2) (i32.const 0)))
+                (func (export "rustcraft_on_event") (param i32 i32)
+                    (loop $forever (br $forever))))
+        "#, &[]).unwrap();
+
+        let (result, event) = chat_event(&plugin, "hi");
+        assert!(result.unwrap_err().contains("ran out of fuel"));
+        // Whatever the plugin did before trapping doesn't matter, the event is left as it was
+        assert_eq!(event.message.as_deref(), Some("hi"));
+        assert!(chat_event(&plugin, "hi").0.unwrap_err().contains("disabled"));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let grow = |pages: u32| load(&format!(r#"
+            (module
+                (memory 1)
+                (func (export "rustcraft_load") (result i32)
+                    (i32.ne (memory.grow (i32.const {})) (i32.const -1))))
+        "#, pages), &[]);
+
+        // The limit is 16 pages of 64 KiB
+        assert!(grow(15).is_ok());
+        assert!(matches!(grow(16), Err(PluginLoadError::LoadFailed)));
+        assert!(matches!(load("(module (memory 17) (func (export \"rustcraft_load\") (result i32) (i32.const 1)))", &[]), Err(PluginLoadError::Wasm(_))));
+    }
+
+    #[test]
+    fn test_invalid_plugins() {
+        assert!(matches!(load("(module)", &[]), Err(PluginLoadError::MissingSymbol(_))));
+        assert!(matches!(load(r#"
+            (module
+                (import "rustcraft" "register_event" (func $register_event (param i32 i32 i32) (result i32)))
+                (func (export "rustcraft_load") (result i32)
+                    (call $register_event (i32.const 4) (i32.const 2) (i32.const 0))))
+        "#, &[]), Err(PluginLoadError::MissingSymbol(_))));
+        assert!(matches!(load(r#"(module (import "env" "system" (func)) (func (export "rustcraft_load") (result i32) (i32.const 1)))"#, &[]), Err(PluginLoadError::Wasm(_))));
+    }
+}
\ No newline at end of file
@@ -1,56 +1,326 @@
 use rsa::RsaPublicKey;
 use rsa::RsaPrivateKey;
 use crate::crypto::rsa_util::generate_rsa_keypair;
-use crate::{log, network::connection::Connection, LOGGER, CONFIG};
-use std::{net::TcpListener, thread};
+use crate::access::access_lists::AccessLists;
+use crate::access::permissions::Permissions;
+use crate::commands::{builtin, command_manager::CommandManager};
+use crate::events::{self, event_bus::EventBus};
+use crate::player_list::PlayerList;
+use crate::plugins::plugin_manager::{PluginManager, HOT_RELOAD_INTERVAL, PLUGINS_DIRECTORY};
+use crate::network::{chunk_tracker, connection_registry::ConnectionRegistry};
+use crate::network::rate_limit::IpThrottle;
+use crate::network::packet::ClientboundPacket;
+use crate::network::packets::play::clientbound::player_info_update::ACTION_UPDATE_LATENCY;
+use crate::tick::{scheduler::{Scheduler, ASYNC_WORKER_THREADS}, tick_loop::TickLoop, tick_stats::{TickStats, TICK_INTERVAL}};
+use crate::utils::metrics::Metrics;
+use crate::world::anvil::storage::{AnvilStorage, REGION_DIRECTORY};
+use crate::custom_types::location::Location;
+use crate::world::chunk::{ChunkPos, MIN_Y};
+use crate::world::chunk_manager::{ChunkManager, SaveSummary, TicketKind, UnloadSummary};
+use crate::world::generation::{flat::FlatGenerator, generator};
+use crate::utils::config::FlatConfig;
+use crate::{log, network::connection::Connection, LOGGER, CONFIG, METRICS};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::{io::ErrorKind, net::TcpListener, path::Path, sync::{atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering}, Arc, Mutex}, thread::{self, JoinHandle}, time::Duration};
+
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+pub const SHUTDOWN_MESSAGE: &str = "Server closed";
+/// Ticks between looking for chunks that can be unloaded.
+const UNLOAD_INTERVAL: u64 = 20;
+/// How often the tab list latencies are resent, like vanilla.
+const LATENCY_UPDATE_INTERVAL: u64 = 600;
+/// Ticks to wait before loading a chunk again after it failed to load.
+const CHUNK_LOAD_RETRY_DELAY: u64 = 100;
 
 pub struct MinecraftServer {
     address: String,
     server_data: ServerData,
+    shutdown_requests: Receiver<()>,
+    listening: Arc<AtomicBool>,
+    listener_thread: Option<JoinHandle<()>>,
+    ticking: Arc<AtomicBool>,
+    tick_thread: Option<JoinHandle<()>>,
 }
 
 #[derive(Clone)]
 pub struct ServerData {
     pub private_key: RsaPrivateKey,
     pub public_key: RsaPublicKey,
+    pub unauthenticated_connections: Arc<AtomicUsize>,
+    pub login_throttle: Arc<Mutex<IpThrottle>>,
+    pub status_throttle: Arc<Mutex<IpThrottle>>,
+    pub access_lists: Arc<AccessLists>,
+    pub permissions: Arc<Permissions>,
+    pub players: Arc<PlayerList>,
+    pub connections: Arc<ConnectionRegistry>,
+    pub tick_stats: Arc<Mutex<TickStats>>,
+    /// Runs tasks on the tick thread later or repeatedly, and blocking work off it.
+    pub scheduler: Arc<Scheduler<ServerData>>,
+    pub commands: Arc<CommandManager>,
+    pub events: Arc<EventBus>,
+    pub plugins: Arc<PluginManager>,
+    pub chunks: Arc<ChunkManager>,
+    next_entity_id: Arc<AtomicI32>,
+    /// Asks the main thread to stop the server.
+    pub shutdown: Sender<()>,
+}
+
+impl ServerData {
+    /// Queues `task` to run on the tick thread on the next tick.
+    pub fn run_on_tick(&self, task: impl FnOnce(&ServerData) + Send + 'static) {
+        self.scheduler.run_later(0, task);
+    }
+
+    /// Keeps the chunk loaded until the ticket is removed, loading it in the background if it isn't yet.
+    pub fn add_chunk_ticket(&self, pos: ChunkPos, kind: TicketKind) {
+        if self.chunks.add_ticket(pos, kind) {
+            self.load_chunk_async(pos);
+        }
+    }
+
+    /// Loads the chunk in the background. If that fails, it's tried again later for as long as it has tickets,
+    /// so players waiting for it get it once it loads.
+    fn load_chunk_async(&self, pos: ChunkPos) {
+        self.chunks.load_async(pos, &self.scheduler, move |server, result| {
+            if let Err(e) = result {
+                log!(warn, "Failed to load chunk {}, trying again in {} ticks: {}", pos, CHUNK_LOAD_RETRY_DELAY, e);
+                server.scheduler.run_later(CHUNK_LOAD_RETRY_DELAY, move |server| {
+                    if server.chunks.retry_load(pos) {
+                        server.load_chunk_async(pos);
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn next_entity_id(&self) -> i32 {
+        self.next_entity_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// On top of the highest block at 0, 0, loading its chunk if it has to.
+    pub fn spawn_location(&self) -> Location {
+        let pos = ChunkPos::new(0, 0);
+        if let Err(e) = self.chunks.load(pos) {
+            log!(warn, "Failed to load the spawn chunk: {}", e);
+        }
+
+        let height = self.chunks.with_chunk(pos, |chunk| chunk.heightmaps().motion_blocking.get(0, 0)).unwrap_or(0);
+        Location::new(0.5, (MIN_Y + height as i64) as f64, 0.5, 0.0, 0.0)
+    }
+}
+
+/// Counts a connection as unauthenticated until it's dropped.
+pub struct UnauthenticatedSlot {
+    counter: Arc<AtomicUsize>,
+}
+
+impl UnauthenticatedSlot {
+    pub fn acquire(counter: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        counter.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| if n < max { Some(n + 1) } else { None })
+            .ok()
+            .map(|_| Self { counter: Arc::clone(counter) })
+    }
+}
+
+impl Drop for UnauthenticatedSlot {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl MinecraftServer {
     pub fn new(ip: &str, port: u16) -> Self {
         log!(info, "Generating RSA keypair...");
         let keypair = generate_rsa_keypair();
+        let throttle_window = Duration::from_secs(CONFIG.network.connection_throttle);
+        let permissions = Permissions::load(Path::new("."));
+        let (shutdown, shutdown_requests) = bounded(1);
 
-        MinecraftServer {
+        let scheduler = Arc::new(Scheduler::new(ASYNC_WORKER_THREADS));
+        let players = Arc::new(PlayerList::new());
+
+        let events = EventBus::new();
+        events::builtin::register_all(&events);
+        let events = Arc::new(events);
+
+        let mut commands = CommandManager::new();
+        builtin::register_all(&mut commands);
+
+        let plugins = PluginManager::new(Arc::clone(&scheduler), Arc::clone(&events), Arc::clone(&players));
+        plugins.load_all(Path::new(PLUGINS_DIRECTORY), &mut commands);
+        // After loading plugins, so their commands get permission nodes too
+        commands.register_permissions(&permissions);
+
+        if CONFIG.plugins.hot_reload {
+            scheduler.run_repeating(HOT_RELOAD_INTERVAL, HOT_RELOAD_INTERVAL, |server| server.plugins.reload_changed(Path::new(PLUGINS_DIRECTORY), &server.commands));
+        }
+
+        let storage = AnvilStorage::new(Path::new(&CONFIG.world.directory).join(REGION_DIRECTORY), CONFIG.world.compression);
+        let generator = generator::from_config(&CONFIG.world).unwrap_or_else(|e| {
+            log!(warn, "Invalid world generator settings, generating the default superflat world instead: {}", e);
+            let defaults = FlatConfig::default();
+            Arc::new(FlatGenerator::parse(&defaults.layers, &defaults.biome).unwrap())
+        });
+        let chunks = Arc::new(ChunkManager::new(storage, generator));
+        if CONFIG.world.autosave_interval > 0 {
+            scheduler.run_repeating(CONFIG.world.autosave_interval, CONFIG.world.autosave_interval, |server| {
+                let chunks = Arc::clone(&server.chunks);
+                server.scheduler.run_async(move || chunks.save_all(), |_, summary| log_save(&summary));
+            });
+        }
+        scheduler.run_repeating(UNLOAD_INTERVAL, UNLOAD_INTERVAL, |server| {
+            let chunks = Arc::clone(&server.chunks);
+            let timeout = TICK_INTERVAL * CONFIG.world.unload_delay as u32;
+            server.scheduler.run_async(move || chunks.unload_idle(timeout), |_, summary| log_unload(&summary));
+        });
+
+        scheduler.run_repeating(LATENCY_UPDATE_INTERVAL, LATENCY_UPDATE_INTERVAL, |server| {
+            let players = &server.players;
+            players.broadcast_packet(&players.info_update(&players.players(), ACTION_UPDATE_LATENCY).build());
+        });
+
+        let server = MinecraftServer {
             address: ip.to_owned() + ":" + &port.to_string(),
             server_data: ServerData { 
                 private_key: keypair.0, 
-                public_key: keypair.1
-            }
+                public_key: keypair.1,
+                unauthenticated_connections: Arc::new(AtomicUsize::new(0)),
+                login_throttle: Arc::new(Mutex::new(IpThrottle::new(throttle_window, CONFIG.network.max_logins_per_ip))),
+                status_throttle: Arc::new(Mutex::new(IpThrottle::new(throttle_window, CONFIG.network.max_status_requests_per_ip))),
+                access_lists: Arc::new(AccessLists::load(Path::new("."))),
+                permissions: Arc::new(permissions),
+                players,
+                connections: Arc::new(ConnectionRegistry::new()),
+                tick_stats: Arc::new(Mutex::new(TickStats::new())),
+                scheduler,
+                commands: Arc::new(commands),
+                events,
+                plugins: Arc::new(plugins),
+                chunks,
+                next_entity_id: Arc::new(AtomicI32::new(0)),
+                shutdown,
+            },
+            shutdown_requests,
+            listening: Arc::new(AtomicBool::new(false)),
+            listener_thread: None,
+            ticking: Arc::new(AtomicBool::new(false)),
+            tick_thread: None,
+        };
+
+        for pos in chunk_tracker::spiral(ChunkPos::new(0, 0), CONFIG.world.spawn_chunk_radius as i32) {
+            server.server_data.add_chunk_ticket(pos, TicketKind::Spawn);
         }
+        server
+    }
+
+    pub fn server_data(&self) -> &ServerData {
+        &self.server_data
+    }
+
+    /// Receives a message once something asked the server to stop.
+    pub fn shutdown_requests(&self) -> Receiver<()> {
+        self.shutdown_requests.clone()
     }
 
-    pub fn start_listening(&self) {
+    pub fn start_ticking(&mut self) {
+        self.ticking.store(true, Ordering::SeqCst);
+        let mut tick_loop = TickLoop::new(self.server_data.clone(), Arc::clone(&self.ticking));
+        self.tick_thread = Some(thread::spawn(move || tick_loop.run()));
+    }
+
+    /// Binds the listener and accepts connections on a separate thread until `shutdown` is called.
+    pub fn start_listening(&mut self) {
         if CONFIG.server.online_mode { log!(verbose, "SESSION_HOST = '{}'", crate::SESSION_HOST) }
         else { log!(warn, "> Server is running in OFFLINE mode. ") }
 
         let listener = TcpListener::bind(&self.address).unwrap();
         let server_address = listener.local_addr().unwrap();
+        // Non-blocking, so the accept loop notices when it should stop
+        listener.set_nonblocking(true).unwrap();
 
         log!(info, "Listening on {}:{}", server_address.ip(), server_address.port());
 
-        for stream in listener.incoming() {
-            match stream {
-                Ok(stream) => {
-                    let address = stream.peer_addr().unwrap();
+        self.listening.store(true, Ordering::SeqCst);
+        let listening = Arc::clone(&self.listening);
+        let server_data = self.server_data.clone();
+        self.listener_thread = Some(thread::spawn(move || Self::accept_connections(listener, server_data, listening)));
+    }
+
+    fn accept_connections(listener: TcpListener, server_data: ServerData, listening: Arc<AtomicBool>) {
+        while listening.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, address)) => {
                     log!(verbose, "Received a connection: {}:{}", address.ip(), address.port());
+                    Metrics::increment(&METRICS.connections_accepted);
 
-                    let mut conn = Connection::new(stream, &self.server_data);
+                    let slot = match UnauthenticatedSlot::acquire(&server_data.unauthenticated_connections, CONFIG.network.max_unauthenticated_connections) {
+                        Some(slot) => slot,
+                        None => {
+                            log!(warn, "Rejected connection from {}:{}: too many unauthenticated connections", address.ip(), address.port());
+                            Metrics::increment(&METRICS.connections_rejected_unauthenticated);
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = stream.set_nonblocking(false) {
+                        log!(warn, "Failed to configure the connection from {}:{}: {}", address.ip(), address.port(), e);
+                        continue;
+                    }
+
+                    let mut conn = Connection::new(stream, address, &server_data, slot);
                     thread::spawn(move || { 
                         conn.start_reading();
                     });
                 }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
                 Err(e) => log!(warn, "Failed to read incoming stream: {}", e)
             }
         }
     }
+
+    /// Stops accepting connections, disconnects everyone and waits for their connections to wind down.
+    pub fn shutdown(&mut self) {
+        log!(info, "Stopping the server...");
+        self.listening.store(false, Ordering::SeqCst);
+        if let Some(listener_thread) = self.listener_thread.take() {
+            let _ = listener_thread.join();
+        }
+
+        let connections = &self.server_data.connections;
+        log!(info, "Disconnecting {} connection(s)...", connections.count());
+        connections.disconnect_all(SHUTDOWN_MESSAGE);
+
+        // Connection threads remove their player from the player list before closing
+        if !connections.wait_until_empty(Duration::from_secs(CONFIG.network.shutdown_timeout)) {
+            log!(warn, "{} connection(s) didn't close within {} seconds", connections.count(), CONFIG.network.shutdown_timeout);
+        }
+
+        self.ticking.store(false, Ordering::SeqCst);
+        if let Some(tick_thread) = self.tick_thread.take() {
+            let _ = tick_thread.join();
+        }
+
+        // Nothing can change the world or call into plugins anymore once the connections and the tick loop are gone
+        log!(info, "Saving the world...");
+        log_save(&self.server_data.chunks.save_all());
+        self.server_data.plugins.unload_all();
+    }
 }
+
+fn log_save(summary: &SaveSummary) {
+    for (pos, e) in &summary.failed {
+        log!(warn, "Failed to save chunk {}: {}", pos, e);
+    }
+    if summary.saved > 0 {
+        log!(verbose, "Saved {} chunk(s)", summary.saved);
+    }
+}
+
+fn log_unload(summary: &UnloadSummary) {
+    for (pos, e) in &summary.failed {
+        log!(warn, "Failed to save chunk {} before unloading it: {}", pos, e);
+    }
+    if summary.unloaded > 0 {
+        log!(debug, "Unloaded {} chunk(s)", summary.unloaded);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,3 @@
+pub mod scheduler;
+pub mod tick_loop;
+pub mod tick_stats;
\ No newline at end of file
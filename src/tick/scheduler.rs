@@ -0,0 +1,264 @@
+use std::{cmp::Reverse, collections::BinaryHeap, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc, Mutex}, thread};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::{log, LOGGER};
+
+/// Threads running blocking work handed to `run_async`.
+pub const ASYNC_WORKER_THREADS: usize = 4;
+
+type SyncTask<C> = Box<dyn FnMut(&C) + Send>;
+type Completion<C> = Box<dyn FnOnce(&C) + Send>;
+type AsyncJob = Box<dyn FnOnce() + Send>;
+
+/// Lets whoever scheduled a task cancel it. Dropping the handle doesn't cancel anything.
+#[derive(Clone)]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+struct ScheduledTask<C> {
+    run_at: u64,
+    id: u64,
+    period: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    task: SyncTask<C>,
+}
+
+// Ordered by when the task runs, ties broken by scheduling order
+impl<C> PartialEq for ScheduledTask<C> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.run_at, self.id) == (other.run_at, other.id)
+    }
+}
+
+impl<C> Eq for ScheduledTask<C> {}
+
+impl<C> PartialOrd for ScheduledTask<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C> Ord for ScheduledTask<C> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.run_at, self.id).cmp(&(other.run_at, other.id))
+    }
+}
+
+/// Runs tasks on the tick thread after a number of ticks, or every few ticks, and blocking work on a small thread pool.
+/// Tasks get the context `C` passed in, which is the `ServerData` for the server's scheduler.
+pub struct Scheduler<C> {
+    tasks: Mutex<BinaryHeap<Reverse<ScheduledTask<C>>>>,
+    current_tick: AtomicU64,
+    next_id: AtomicU64,
+    jobs: Sender<AsyncJob>,
+    completions: (Sender<Completion<C>>, Receiver<Completion<C>>),
+}
+
+impl<C: 'static> Scheduler<C> {
+    pub fn new(worker_threads: usize) -> Self {
+        let (jobs, job_receiver) = unbounded::<AsyncJob>();
+        for i in 0..worker_threads {
+            let job_receiver = job_receiver.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("async-worker-{}", i))
+                .spawn(move || {
+                    // Runs until the scheduler is dropped
+                    for job in job_receiver {
+                        job();
+                    }
+                });
+
+            if let Err(e) = spawned {
+                log!(error, "Failed to start an async worker thread: {}", e);
+            }
+        }
+
+        Self {
+            tasks: Mutex::new(BinaryHeap::new()),
+            current_tick: AtomicU64::new(0),
+            next_id: AtomicU64::new(0),
+            jobs,
+            completions: unbounded(),
+        }
+    }
+
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick.load(Ordering::SeqCst)
+    }
+
+    fn schedule(&self, delay: u64, period: Option<u64>, task: SyncTask<C>) -> TaskHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let scheduled = ScheduledTask {
+            // A delay of 0 still means the next tick
+            run_at: self.current_tick() + delay.max(1),
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            period: period.map(|period| period.max(1)),
+            cancelled: Arc::clone(&cancelled),
+            task,
+        };
+
+        self.tasks.lock().unwrap().push(Reverse(scheduled));
+        TaskHandle { cancelled }
+    }
+
+    /// Runs `task` on the tick thread `delay` ticks from now.
+    pub fn run_later(&self, delay: u64, task: impl FnOnce(&C) + Send + 'static) -> TaskHandle {
+        let mut task = Some(task);
+        self.schedule(delay, None, Box::new(move |context| {
+            if let Some(task) = task.take() {
+                task(context);
+            }
+        }))
+    }
+
+    /// Runs `task` on the tick thread `delay` ticks from now, then every `period` ticks until it's cancelled.
+    pub fn run_repeating(&self, delay: u64, period: u64, task: impl FnMut(&C) + Send + 'static) -> TaskHandle {
+        self.schedule(delay, Some(period), Box::new(task))
+    }
+
+    /// Runs `work` on an async worker thread, then hands its result to `then` on the tick thread.
+    pub fn run_async<T: Send + 'static>(&self, work: impl FnOnce() -> T + Send + 'static, then: impl FnOnce(&C, T) + Send + 'static) {
+        let completions = self.completions.0.clone();
+        let _ = self.jobs.send(Box::new(move || {
+            let result = work();
+            let _ = completions.send(Box::new(move |context| then(context, result)));
+        }));
+    }
+
+    /// Advances to the next tick and runs everything that's due. Called by the tick loop.
+    pub fn tick(&self, context: &C) {
+        let tick = self.current_tick.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // Completions that were already there when the tick started
+        for completion in self.completions.1.try_iter().take(self.completions.1.len()) {
+            completion(context);
+        }
+
+        // The lock isn't held while tasks run, so they can schedule more tasks
+        let mut due = Vec::new();
+        {
+            let mut tasks = self.tasks.lock().unwrap();
+            while tasks.peek().is_some_and(|Reverse(task)| task.run_at <= tick) {
+                let Reverse(task) = tasks.pop().unwrap();
+                due.push(task);
+            }
+        }
+
+        for mut task in due {
+            if task.cancelled.load(Ordering::SeqCst) { continue; }
+            (task.task)(context);
+
+            if let Some(period) = task.period {
+                if !task.cancelled.load(Ordering::SeqCst) {
+                    task.run_at = tick + period;
+                    self.tasks.lock().unwrap().push(Reverse(task));
+                }
+            }
+        }
+    }
+
+    /// Tasks waiting to run, including cancelled ones that haven't been cleaned up yet.
+    pub fn pending_tasks(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::AtomicUsize, time::{Duration, Instant}};
+
+    use super::*;
+
+    type Log = Mutex<Vec<(u64, &'static str)>>;
+
+    fn record(log: &Log, scheduler_tick: u64, name: &'static str) {
+        log.lock().unwrap().push((scheduler_tick, name));
+    }
+
+    #[test]
+    fn test_run_later() {
+        let scheduler: Scheduler<Log> = Scheduler::new(0);
+        let log = Log::default();
+
+        scheduler.run_later(2, |log| record(log, 2, "later"));
+        scheduler.run_later(0, |log| record(log, 1, "next tick"));
+        scheduler.run_later(1, |log| record(log, 1, "also next tick"));
+        let cancelled = scheduler.run_later(1, |log| record(log, 1, "cancelled"));
+        cancelled.cancel();
+
+        for _ in 0..3 {
+            scheduler.tick(&log);
+        }
+
+        assert_eq!(*log.lock().unwrap(), vec![(1, "next tick"), (1, "also next tick"), (2, "later")]);
+        assert_eq!(scheduler.pending_tasks(), 0);
+    }
+
+    #[test]
+    fn test_run_repeating() {
+        let scheduler: Scheduler<AtomicUsize> = Scheduler::new(0);
+        let runs = AtomicUsize::new(0);
+
+        let handle = scheduler.run_repeating(1, 3, |runs: &AtomicUsize| { runs.fetch_add(1, Ordering::SeqCst); });
+        // Runs on ticks 1, 4, 7 and 10
+        for _ in 0..10 {
+            scheduler.tick(&runs);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+
+        handle.cancel();
+        for _ in 0..10 {
+            scheduler.tick(&runs);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+        assert_eq!(scheduler.pending_tasks(), 0);
+    }
+
+    #[test]
+    fn test_tasks_scheduling_tasks() {
+        let scheduler: Arc<Scheduler<Log>> = Arc::new(Scheduler::new(0));
+        let log = Log::default();
+
+        let inner = Arc::clone(&scheduler);
+        scheduler.run_later(1, move |log| {
+            record(log, 1, "outer");
+            inner.run_later(1, |log| record(log, 2, "inner"));
+        });
+
+        scheduler.tick(&log);
+        scheduler.tick(&log);
+        assert_eq!(*log.lock().unwrap(), vec![(1, "outer"), (2, "inner")]);
+    }
+
+    #[test]
+    fn test_run_async() {
+        let scheduler: Scheduler<Mutex<Vec<String>>> = Scheduler::new(2);
+        let results = Mutex::new(Vec::new());
+
+        scheduler.run_async(|| thread::current().name().unwrap_or_default().to_owned(), |results, thread_name| {
+            results.lock().unwrap().push(thread_name);
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while results.lock().unwrap().is_empty() && Instant::now() < deadline {
+            scheduler.tick(&results);
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let results = results.lock().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].starts_with("async-worker-"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread, time::{Duration, Instant}};
+
+use crate::{events::tick_events::{EntityTickEvent, WorldTickEvent}, log, server::ServerData, utils::metrics::Metrics, LOGGER, METRICS};
+
+use super::tick_stats::{TickStats, TICK_INTERVAL};
+
+/// When the loop is further behind than this, it skips ahead instead of running the missed ticks.
+const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+
+/// Runs the game at a fixed 20 ticks per second on its own thread.
+pub struct TickLoop {
+    server_data: ServerData,
+    stats: Arc<Mutex<TickStats>>,
+    running: Arc<AtomicBool>,
+    /// Ticks run so far.
+    tick: u64,
+}
+
+impl TickLoop {
+    pub fn new(server_data: ServerData, running: Arc<AtomicBool>) -> Self {
+        Self {
+            stats: Arc::clone(&server_data.tick_stats),
+            server_data,
+            running,
+            tick: 0,
+        }
+    }
+
+    pub fn run(&mut self) {
+        let mut next_tick = Instant::now();
+
+        while self.running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now < next_tick {
+                thread::sleep(next_tick - now);
+                continue;
+            }
+
+            // Missed ticks run back to back, unless there are too many of them
+            let behind = now - next_tick;
+            if behind > MAX_CATCH_UP {
+                let skipped = (behind.as_millis() / TICK_INTERVAL.as_millis()) as u64;
+                log!(warn, "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind", behind.as_millis(), skipped);
+                Metrics::add(&METRICS.ticks_skipped, skipped);
+                next_tick = now;
+            }
+
+            let start = Instant::now();
+            self.tick();
+            let duration = start.elapsed();
+
+            let mut stats = self.stats.lock().unwrap();
+            stats.record_tick(start, duration);
+            Metrics::increment(&METRICS.ticks);
+            Metrics::set_gauge(&METRICS.tps, stats.tps()[0]);
+            Metrics::set_gauge(&METRICS.mspt, stats.mspt().0);
+            drop(stats);
+
+            next_tick += TICK_INTERVAL;
+        }
+    }
+
+    fn tick(&mut self) {
+        self.tick += 1;
+
+        // Packets from players are queued as tasks, so the world sees them before it changes by itself
+        self.server_data.scheduler.tick(&self.server_data);
+        self.server_data.events.post(&mut WorldTickEvent { tick: self.tick });
+        self.server_data.events.post(&mut EntityTickEvent { tick: self.tick });
+
+        // Lets connections stream chunks, including the ones loaded this tick
+        self.server_data.connections.tick_all();
+    }
+}
\ No newline at end of file
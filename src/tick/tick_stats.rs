@@ -0,0 +1,163 @@
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_INTERVAL: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+/// Ticks whose duration is kept for the MSPT numbers, the same amount vanilla keeps.
+const TICK_TIME_HISTORY: usize = 100;
+
+/// Average of the last `size` samples.
+pub struct RollingAverage {
+    samples: VecDeque<f64>,
+    size: usize,
+}
+
+impl RollingAverage {
+    pub fn new(size: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(size),
+            size,
+        }
+    }
+
+    pub fn add(&mut self, sample: f64) {
+        if self.samples.len() == self.size {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() { return None; }
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+}
+
+/// TPS over the last 1, 5 and 15 minutes, and how long the last ticks took.
+pub struct TickStats {
+    tick_count: u64,
+    tick_times: VecDeque<Duration>,
+    /// Start of the tick that began the current one-second window.
+    window_start: Option<Instant>,
+    window_ticks: u32,
+    tps_1m: RollingAverage,
+    tps_5m: RollingAverage,
+    tps_15m: RollingAverage,
+}
+
+impl TickStats {
+    pub fn new() -> Self {
+        Self {
+            tick_count: 0,
+            tick_times: VecDeque::with_capacity(TICK_TIME_HISTORY),
+            window_start: None,
+            window_ticks: 0,
+            tps_1m: RollingAverage::new(60),
+            tps_5m: RollingAverage::new(5 * 60),
+            tps_15m: RollingAverage::new(15 * 60),
+        }
+    }
+
+    /// Records a tick that started at `start` and took `duration`.
+    pub fn record_tick(&mut self, start: Instant, duration: Duration) {
+        self.tick_count += 1;
+        if self.tick_times.len() == TICK_TIME_HISTORY {
+            self.tick_times.pop_front();
+        }
+        self.tick_times.push_back(duration);
+
+        let Some(window_start) = self.window_start else {
+            self.window_start = Some(start);
+            return;
+        };
+
+        // Every 20 ticks, work out how many ticks per second those took
+        self.window_ticks += 1;
+        if self.window_ticks == TICKS_PER_SECOND {
+            let elapsed = start.duration_since(window_start).as_secs_f64();
+            let tps = if elapsed > 0.0 { (TICKS_PER_SECOND as f64 / elapsed).min(TICKS_PER_SECOND as f64) } else { TICKS_PER_SECOND as f64 };
+
+            self.tps_1m.add(tps);
+            self.tps_5m.add(tps);
+            self.tps_15m.add(tps);
+            self.window_start = Some(start);
+            self.window_ticks = 0;
+        }
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// TPS over the last 1, 5 and 15 minutes. Reports a full 20 until a second has been measured.
+    pub fn tps(&self) -> [f64; 3] {
+        [&self.tps_1m, &self.tps_5m, &self.tps_15m].map(|average| average.average().unwrap_or(TICKS_PER_SECOND as f64))
+    }
+
+    /// Average, minimum and maximum milliseconds per tick over the last 100 ticks.
+    pub fn mspt(&self) -> (f64, f64, f64) {
+        if self.tick_times.is_empty() { return (0.0, 0.0, 0.0); }
+
+        let millis = self.tick_times.iter().map(|duration| duration.as_secs_f64() * 1000.0);
+        let average = millis.clone().sum::<f64>() / self.tick_times.len() as f64;
+        let min = millis.clone().fold(f64::MAX, f64::min);
+        let max = millis.fold(0.0, f64::max);
+
+        (average, min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average() {
+        let mut average = RollingAverage::new(2);
+        assert_eq!(average.average(), None);
+
+        average.add(10.0);
+        average.add(20.0);
+        assert_eq!(average.average(), Some(15.0));
+
+        average.add(40.0);
+        assert_eq!(average.average(), Some(30.0));
+    }
+
+    #[test]
+    fn test_tps() {
+        let mut stats = TickStats::new();
+        let start = Instant::now();
+        assert_eq!(stats.tps(), [20.0; 3]);
+
+        // A second at full speed, then a second where every tick took twice as long
+        for tick in 0..=20 {
+            stats.record_tick(start + TICK_INTERVAL * tick, Duration::from_millis(5));
+        }
+        assert_eq!(stats.tps(), [20.0; 3]);
+
+        for tick in 1..=20 {
+            stats.record_tick(start + TICK_INTERVAL * 20 + TICK_INTERVAL * 2 * tick, Duration::from_millis(100));
+        }
+        assert_eq!(stats.tps()[0], 15.0);
+        assert_eq!(stats.tick_count(), 41);
+    }
+
+    #[test]
+    fn test_mspt() {
+        let mut stats = TickStats::new();
+        let start = Instant::now();
+        assert_eq!(stats.mspt(), (0.0, 0.0, 0.0));
+
+        for tick in 0..150 {
+            let millis = if tick < 100 { 100 } else { 10 + tick as u64 % 2 * 20 };
+            stats.record_tick(start, Duration::from_millis(millis));
+        }
+
+        // Only the last 100 ticks count
+        let (average, min, max) = stats.mspt();
+        assert!((average - 60.0).abs() < 1e-9);
+        assert!((min - 10.0).abs() < 1e-9);
+        assert!((max - 100.0).abs() < 1e-9);
+    }
+}
\ No newline at end of file
@@ -1,13 +1,23 @@
-use std::{fs::{self, OpenOptions}, io::Write, path::Path};
+use std::{collections::HashMap, fs::{self, OpenOptions}, io::Write, path::Path};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::world::{anvil::region::Compression, generation::generator::GeneratorKind};
+
 use super::logger::LogLevel;
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub status: StatusConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub world: WorldConfig,
     pub misc: MiscConfig,
 }
 
@@ -17,6 +27,19 @@ pub struct ServerConfig {
     pub port: u16,
     pub max_players: i32,
     pub online_mode: bool,
+    /// Accept any non-whitespace username of 1-16 characters. Only used in offline mode.
+    #[serde(default)]
+    pub relaxed_usernames: bool,
+    /// Only let players listed in whitelist.json join.
+    #[serde(default)]
+    pub whitelist: bool,
+    /// Op level given to players opped through a command.
+    #[serde(default = "default_op_permission_level")]
+    pub op_permission_level: u8,
+}
+
+fn default_op_permission_level() -> u8 {
+    4
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,6 +48,149 @@ pub struct StatusConfig {
     pub motd: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Seconds a client may spend in each state before it gets dropped.
+    pub handshake_timeout: u64,
+    pub status_timeout: u64,
+    pub login_timeout: u64,
+    pub configuration_timeout: u64,
+    /// Connections that haven't finished logging in yet (handshake, status & login).
+    pub max_unauthenticated_connections: usize,
+    /// Bytes of incomplete packets we are willing to buffer before a client finished logging in.
+    pub max_unauthenticated_buffer: usize,
+    /// Window (in seconds) for the per-IP limits below.
+    pub connection_throttle: u64,
+    pub max_logins_per_ip: usize,
+    pub max_status_requests_per_ip: usize,
+    /// Clients sending more packets than this in a second get kicked.
+    pub max_packets_per_second: u32,
+    /// Seconds to wait for connections to close when the server stops.
+    pub shutdown_timeout: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: 5,
+            status_timeout: 10,
+            login_timeout: 30,
+            configuration_timeout: 60,
+            max_unauthenticated_connections: 64,
+            max_unauthenticated_buffer: 4096,
+            connection_throttle: 4,
+            max_logins_per_ip: 1,
+            max_status_requests_per_ip: 10,
+            max_packets_per_second: 500,
+            shutdown_timeout: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub ip: String,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ip: String::from("127.0.0.1"),
+            port: 9940,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// Roughly the number of instructions a WebAssembly plugin may run per call into it.
+    pub wasm_fuel: u64,
+    /// Memory each WebAssembly plugin may use, in MiB.
+    pub wasm_memory_mb: usize,
+    /// Reload WebAssembly plugins whose file changed.
+    pub hot_reload: bool,
+    /// Per-plugin overrides of the limits above, by file name without the extension.
+    pub limits: HashMap<String, PluginLimitsConfig>,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            wasm_fuel: 10_000_000,
+            wasm_memory_mb: 16,
+            hot_reload: true,
+            limits: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PluginLimitsConfig {
+    pub wasm_fuel: Option<u64>,
+    pub wasm_memory_mb: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    /// Where the world is saved, with the region files in its `region` directory.
+    pub directory: String,
+    /// How chunks get compressed when they're saved: gzip, zlib, none or lz4.
+    pub compression: Compression,
+    /// Ticks between saving changed chunks. 0 only saves when the server stops.
+    pub autosave_interval: u64,
+    /// How many chunks around them players get at most, if their own view distance is further.
+    pub view_distance: u8,
+    /// Chunks around the spawn that always stay loaded.
+    pub spawn_chunk_radius: u8,
+    /// Ticks a chunk stays loaded after nothing needs it anymore.
+    pub unload_delay: u64,
+    /// What generates chunks that were never saved: flat or noise.
+    pub generator: GeneratorKind,
+    /// The same seed always generates the same terrain.
+    pub seed: i64,
+    pub flat: FlatConfig,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            directory: String::from("world"),
+            compression: Compression::Zlib,
+            autosave_interval: 6000,
+            view_distance: 10,
+            spawn_chunk_radius: 2,
+            unload_delay: 600,
+            generator: GeneratorKind::Flat,
+            seed: 0,
+            flat: FlatConfig::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlatConfig {
+    /// Layers from the bottom of the world up, each an optional count and a block.
+    pub layers: String,
+    pub biome: String,
+}
+
+impl Default for FlatConfig {
+    fn default() -> Self {
+        Self {
+            layers: String::from("minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block"),
+            biome: String::from("minecraft:plains"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MiscConfig {
     pub log_level: LogLevel
@@ -56,11 +222,18 @@ pub fn write_default_config(filename: &str) -> bool {
             port: 25565,
             max_players: 69, 
             online_mode: true,
+            relaxed_usernames: false,
+            whitelist: false,
+            op_permission_level: default_op_permission_level(),
         },
         status: StatusConfig { 
             version_prefix: String::from("Rusty"),
             motd: String::from("Rusty experimental minecraft server!"), 
         },
+        network: NetworkConfig::default(),
+        metrics: MetricsConfig::default(),
+        plugins: PluginsConfig::default(),
+        world: WorldConfig { seed: rand::random(), ..WorldConfig::default() },
         misc: MiscConfig {
             log_level: LogLevel::Info,
         }
@@ -14,6 +14,46 @@ pub enum PacketReadError {
     Utf8Error,
     UnexpectedValue,
     ConvertationIssue(String),
+    InvalidLength(i32),
+    PacketTooLarge(usize),
+    StringTooLong(usize, usize),
+}
+
+/// A command that couldn't be parsed, `cursor` pointing at the character the problem starts at.
+#[derive(Debug, PartialEq)]
+pub struct CommandSyntaxError {
+    pub message: String,
+    pub input: String,
+    pub cursor: usize,
+}
+
+#[derive(Debug)]
+pub enum PluginLoadError {
+    Library(String),
+    MissingSymbol(String),
+    AbiMismatch(u32, u32),
+    Wasm(String),
+    LoadFailed,
+}
+
+#[derive(Debug)]
+pub enum RegionError {
+    Io(String),
+    InvalidHeader(usize),
+    InvalidLocation(usize, usize),
+    UnknownCompression(u8),
+    Decompression(String),
+    Nbt(String),
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    InvalidLayer(String),
+    UnknownBlock(String),
+    TooManyLayers(usize),
+    UnknownBiome(String),
 }
 
 #[derive(Debug)]
@@ -39,16 +79,88 @@ impl From<PacketReadError> for PacketHandleError {
 
 impl fmt::Display for PacketReadError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = match self {
-            Self::EmptyBuf => "Empty buffer",
-            Self::BufferUnderflow => "Buffer underflow",
-            Self::TooLong => "Too long",
-            Self::Utf8Error => "UTF-8 Error",
-            Self::UnexpectedValue => "Unexpected value",
-            Self::ConvertationIssue(details) => &("Convertation issue: ".to_owned() + details)
-        };
-
-        write!(f, "{}", msg)
+        match self {
+            Self::EmptyBuf => write!(f, "Empty buffer"),
+            Self::BufferUnderflow => write!(f, "Buffer underflow"),
+            Self::TooLong => write!(f, "Too long"),
+            Self::Utf8Error => write!(f, "UTF-8 Error"),
+            Self::UnexpectedValue => write!(f, "Unexpected value"),
+            Self::ConvertationIssue(details) => write!(f, "Convertation issue: {}", details),
+            Self::InvalidLength(length) => write!(f, "Invalid length {}", length),
+            Self::PacketTooLarge(size) => write!(f, "Packet too large ({} bytes)", size),
+            Self::StringTooLong(length, max) => write!(f, "String too long ({} > {})", length, max),
+        }
+    }
+}
+
+impl CommandSyntaxError {
+    pub fn new(message: &str, input: &str, cursor: usize) -> Self {
+        Self {
+            message: message.to_owned(),
+            input: input.to_owned(),
+            cursor,
+        }
+    }
+}
+
+impl fmt::Display for CommandSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Same layout as vanilla: up to 10 characters of context before the error position
+        let cursor = self.cursor.min(self.input.len());
+        let context_start = self.input[..cursor].char_indices().rev().nth(9).map_or(0, |(i, _)| i);
+        let ellipsis = if context_start > 0 { "..." } else { "" };
+
+        write!(f, "{}\n{}{}<--[HERE]", self.message, ellipsis, &self.input[context_start..cursor])
+    }
+}
+
+impl fmt::Display for PluginLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Library(e) => write!(f, "Couldn't open the library: {}", e),
+            Self::MissingSymbol(symbol) => write!(f, "Missing symbol '{}'", symbol),
+            Self::AbiMismatch(found, expected) => write!(f, "Built for plugin ABI version {}, but the server uses version {}", found, expected),
+            Self::Wasm(e) => write!(f, "Invalid WebAssembly module: {}", e),
+            Self::LoadFailed => write!(f, "The plugin failed to load"),
+        }
+    }
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::InvalidHeader(length) => write!(f, "File too short for a region header ({} bytes)", length),
+            Self::InvalidLocation(offset, sectors) => write!(f, "Chunk points outside the file (sector {}, {} sectors)", offset, sectors),
+            Self::UnknownCompression(kind) => write!(f, "Unknown compression type {}", kind),
+            Self::Decompression(e) => write!(f, "Couldn't decompress chunk: {}", e),
+            Self::Nbt(e) => write!(f, "Invalid NBT: {}", e),
+            Self::MissingTag(name) => write!(f, "Missing tag '{}'", name),
+            Self::InvalidTag(name) => write!(f, "Tag '{}' has the wrong type or value", name),
+        }
+    }
+}
+
+impl From<std::io::Error> for RegionError {
+    fn from(err: std::io::Error) -> RegionError {
+        RegionError::Io(err.to_string())
+    }
+}
+
+impl From<nbt::Error> for RegionError {
+    fn from(err: nbt::Error) -> RegionError {
+        RegionError::Nbt(err.to_string())
+    }
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLayer(layer) => write!(f, "Invalid layer '{}', expected something like '2*minecraft:dirt'", layer),
+            Self::UnknownBlock(name) => write!(f, "Unknown block '{}'", name),
+            Self::TooManyLayers(count) => write!(f, "{} layers don't fit into the world", count),
+            Self::UnknownBiome(name) => write!(f, "Unknown biome '{}'", name),
+        }
     }
 }
 
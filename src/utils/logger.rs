@@ -1,13 +1,16 @@
 use core::fmt;
-use std::{fs::{self, OpenOptions}, io::Write, path::Path, sync::Mutex};
+use std::{fs::{self, OpenOptions}, io::Write, path::Path, str::FromStr, sync::{Mutex, RwLock}};
 
 use chrono::Local;
 use colored::Colorize;
+use rustyline::ExternalPrinter;
 use serde_derive::{Deserialize, Serialize};
 
 pub struct Logger {
     file: Mutex<fs::File>,
-    level: LogLevel
+    level: RwLock<LogLevel>,
+    /// Set while the console is reading input, so log lines get printed above the prompt.
+    printer: Mutex<Option<Box<dyn ExternalPrinter + Send>>>,
 }
 
 #[derive(PartialEq, PartialOrd, Serialize, Deserialize, Clone)]
@@ -33,6 +36,21 @@ impl fmt::Display for LogLevel {
     }
 }
 
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "verbose" => Ok(Self::Verbose),
+            "debug" => Ok(Self::Debug),
+            _ => Err(format!("Unknown log level '{}'", s)),
+        }
+    }
+}
+
 impl Logger {
     pub fn new(log_file: &str, level: LogLevel) -> Self {
         let parent = Path::new(log_file).parent().unwrap();
@@ -46,19 +64,29 @@ impl Logger {
 
         Logger {
             file: Mutex::new(file),
-            level
+            level: RwLock::new(level),
+            printer: Mutex::new(None),
         }
     }
 
-    pub fn set_level(&mut self, level: LogLevel) {
-        self.level = level;
+    pub fn level(&self) -> LogLevel {
+        self.level.read().unwrap().clone()
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        *self.level.write().unwrap() = level;
+    }
+
+    pub fn set_printer(&self, printer: Option<Box<dyn ExternalPrinter + Send>>) {
+        *self.printer.lock().unwrap() = printer;
     }
 
     pub fn log(&self, level: LogLevel, source: &str, text: &str) {
-        if level < self.level { return; }
+        let current_level = self.level();
+        if level < current_level { return; }
 
         let date = Local::now();
-        let formatted_text = if self.level <= LogLevel::Verbose {
+        let formatted_text = if current_level <= LogLevel::Verbose {
             format!("[{}] [{}] [{}] {}\n", date.format("%Y-%m-%d %H:%M:%S"), level, source, text)
         } else {
             format!("[{}] [{}] {}\n", date.format("%Y-%m-%d %H:%M:%S"), level, text)
@@ -75,7 +103,11 @@ impl Logger {
             LogLevel::Debug => formatted_text.bright_blue(),
         };
 
-        print!("{}", colored_text);
+        let mut printer = self.printer.lock().unwrap();
+        match printer.as_mut() {
+            Some(printer) => { let _ = printer.print(colored_text.to_string()); },
+            None => print!("{}", colored_text),
+        }
     }
     
     pub fn error(&self, source: &str, text: &str) {
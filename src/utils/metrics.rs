@@ -0,0 +1,113 @@
+use std::{io::{Read, Write}, net::{TcpListener, TcpStream}, sync::atomic::{AtomicU64, Ordering}, thread, time::Duration};
+
+use crate::{log, LOGGER};
+
+#[derive(Default)]
+pub struct Metrics {
+    pub connections_accepted: AtomicU64,
+    pub connections_rejected_unauthenticated: AtomicU64,
+    pub connections_rejected_throttled: AtomicU64,
+    pub status_requests_throttled: AtomicU64,
+    pub packet_rate_kicks: AtomicU64,
+    pub ticks: AtomicU64,
+    pub ticks_skipped: AtomicU64,
+    pub chunks_loaded: AtomicU64,
+    pub chunks_generated: AtomicU64,
+    pub chunks_unloaded: AtomicU64,
+    /// Gauges hold the bits of an `f64`.
+    pub tps: AtomicU64,
+    pub mspt: AtomicU64,
+    pub loaded_chunks: AtomicU64,
+}
+
+impl Metrics {
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn set_gauge(gauge: &AtomicU64, value: f64) {
+        gauge.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    fn gauge_value(gauge: &AtomicU64) -> f64 {
+        f64::from_bits(gauge.load(Ordering::Relaxed))
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let counters = [
+            ("rustcraft_connections_accepted_total", "Connections accepted by the listener", &self.connections_accepted),
+            ("rustcraft_connections_rejected_unauthenticated_total", "Connections rejected because of the unauthenticated connection cap", &self.connections_rejected_unauthenticated),
+            ("rustcraft_connections_rejected_throttled_total", "Logins rejected by the per-IP connection throttle", &self.connections_rejected_throttled),
+            ("rustcraft_status_requests_throttled_total", "Status requests rejected by the per-IP connection throttle", &self.status_requests_throttled),
+            ("rustcraft_packet_rate_kicks_total", "Clients kicked for exceeding the packet rate limit", &self.packet_rate_kicks),
+            ("rustcraft_ticks_total", "Server ticks run", &self.ticks),
+            ("rustcraft_ticks_skipped_total", "Ticks skipped because the server fell too far behind", &self.ticks_skipped),
+            ("rustcraft_chunks_loaded_total", "Chunks loaded from storage", &self.chunks_loaded),
+            ("rustcraft_chunks_generated_total", "Chunks generated because they were never saved", &self.chunks_generated),
+            ("rustcraft_chunks_unloaded_total", "Chunks unloaded after nothing needed them anymore", &self.chunks_unloaded),
+        ];
+        let gauges = [
+            ("rustcraft_tps", "Ticks per second over the last minute", &self.tps),
+            ("rustcraft_mspt", "Average milliseconds per tick over the last 100 ticks", &self.mspt),
+            ("rustcraft_loaded_chunks", "Chunks currently in memory", &self.loaded_chunks),
+        ];
+
+        let mut output = String::new();
+        for (name, help, counter) in counters {
+            output += &format!("# HELP {} {}\n# TYPE {} counter\n{} {}\n", name, help, name, name, counter.load(Ordering::Relaxed));
+        }
+
+        for (name, help, gauge) in gauges {
+            output += &format!("# HELP {} {}\n# TYPE {} gauge\n{} {}\n", name, help, name, name, Self::gauge_value(gauge));
+        }
+
+        output
+    }
+}
+
+pub fn start_metrics_server(address: String) {
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log!(error, "Failed to start metrics endpoint on {}: {}", address, e);
+            return;
+        }
+    };
+
+    log!(info, "Metrics available at http://{}/metrics", address);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = respond(stream) {
+                        log!(debug, "Failed to answer metrics request: {}", e);
+                    }
+                }
+                Err(e) => log!(warn, "Failed to accept metrics request: {}", e)
+            }
+        }
+    });
+}
+
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+    // We serve the same page for every path, so the request itself doesn't matter
+    let mut request = [0u8; 1024];
+    let _ = stream.read(&mut request)?;
+
+    let body = crate::METRICS.render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes())
+}
\ No newline at end of file
@@ -3,5 +3,9 @@ pub mod errors;
 pub mod logger;
 #[macro_use]
 pub mod macros;
+pub mod metrics;
 pub mod mojauth;
-pub mod packet_utils;
\ No newline at end of file
+pub mod packet_utils;
+#[cfg(test)]
+pub mod test_utils;
+pub mod username;
\ No newline at end of file
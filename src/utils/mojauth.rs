@@ -1,12 +1,13 @@
 use serde_derive::Deserialize;
 use sha1::{Sha1, Digest};
-use crate::{SESSION_HOST, crypto::auth_hash::calc_hash};
+use crate::{API_HOST, SESSION_HOST, crypto::auth_hash::calc_hash};
 
 use super::errors::ObjectResponseError;
 
 #[derive(Deserialize)]
 pub struct SessionServerHasJoinedResponse {
     pub id: String,
+    pub name: String,
     pub properties: Vec<SessionServerProperty>,
 }
 
@@ -17,6 +18,22 @@ pub struct SessionServerProperty {
     pub signature: String,
 }
 
+#[derive(Deserialize)]
+pub struct ProfileLookupResponse {
+    pub id: String,
+    pub name: String,
+}
+
+pub fn lookup_profile(username: &str) -> Result<ProfileLookupResponse, ObjectResponseError> {
+    let endpoint = format!("/users/profiles/minecraft/{}", username);
+    let body = reqwest::blocking::get(API_HOST.to_owned() + &endpoint)?.text()?;
+
+    match serde_json::from_str(&body) {
+        Ok(result) => Ok(result),
+        Err(e) => Err(ObjectResponseError::SerdeParseError(format!("{} ({})", body, e)))
+    }
+}
+
 pub fn authenticate_player(username: String, shared_secret: &[u8], encoded_public_key: &[u8]) -> Result<SessionServerHasJoinedResponse, ObjectResponseError> {
     let sha = Sha1::new()
         .chain_update("".as_bytes())
@@ -1,7 +1,12 @@
 use core::str;
 use bytes::{Buf, BufMut};
 
-use crate::utils::errors::PacketReadError;
+use crate::{custom_types::bit_set::BitSet, utils::errors::PacketReadError};
+
+/// Largest packet length a 3 byte VarInt can describe, which is what vanilla allows.
+pub const MAX_PACKET_SIZE: usize = 2097151;
+/// Default maximum string length (in UTF-16 code units, like vanilla counts them).
+pub const MAX_STRING_LENGTH: usize = 32767;
 
 pub fn read_varint(buf: &mut dyn Buf) -> Result<i32, PacketReadError> {
     let mut value = 0;
@@ -72,7 +77,20 @@ pub fn write_varlong(buf: &mut dyn BufMut, value: i64) {
 }
 
 pub fn read_string(buf: &mut dyn Buf) -> Result<String, PacketReadError> {
-    let length = read_varint(buf).unwrap() as usize;
+    read_string_limited(buf, MAX_STRING_LENGTH)
+}
+
+pub fn read_string_limited(buf: &mut dyn Buf, max_length: usize) -> Result<String, PacketReadError> {
+    let length = read_varint(buf)?;
+    if length < 0 {
+        return Err(PacketReadError::InvalidLength(length));
+    }
+
+    // Each UTF-16 code unit takes at most 3 bytes in UTF-8
+    let length = length as usize;
+    if length > max_length * 3 {
+        return Err(PacketReadError::StringTooLong(length, max_length * 3));
+    }
 
     if buf.remaining() < length {
         return Err(PacketReadError::BufferUnderflow);
@@ -80,10 +98,42 @@ pub fn read_string(buf: &mut dyn Buf) -> Result<String, PacketReadError> {
 
     let mut string_bytes = vec![0u8; length];
     buf.copy_to_slice(&mut string_bytes);
-    return match str::from_utf8(&string_bytes) {
-        Ok(result) => Ok(result.to_owned()),
-        Err(_) => Err(PacketReadError::Utf8Error)
+    let result = match str::from_utf8(&string_bytes) {
+        Ok(result) => result.to_owned(),
+        Err(_) => return Err(PacketReadError::Utf8Error)
+    };
+
+    let utf16_length = result.encode_utf16().count();
+    if utf16_length > max_length {
+        return Err(PacketReadError::StringTooLong(utf16_length, max_length));
+    }
+
+    Ok(result)
+}
+
+/// Looks at the length prefix of the next packet frame in `data`.
+/// Returns the size of the prefix and the packet length, or `None` if the prefix isn't complete yet.
+pub fn read_frame_length(data: &[u8]) -> Result<Option<(usize, usize)>, PacketReadError> {
+    let mut value: i32 = 0;
+
+    for (i, byte) in data.iter().take(3).enumerate() {
+        value |= ((byte & 0x7F) as i32) << (7 * i);
+
+        if (byte & 0x80) == 0 {
+            if value == 0 {
+                return Err(PacketReadError::InvalidLength(value));
+            }
+
+            return Ok(Some((i + 1, value as usize)));
+        }
+    }
+
+    if data.len() >= 3 {
+        // A fourth length byte would only be needed for packets above MAX_PACKET_SIZE
+        return Err(PacketReadError::PacketTooLarge(MAX_PACKET_SIZE + 1));
     }
+
+    Ok(None)
 }
 
 pub fn write_string(buf: &mut dyn BufMut, data: &str) {
@@ -94,6 +144,14 @@ pub fn write_string(buf: &mut dyn BufMut, data: &str) {
     buf.put_slice(bytes);
 }
 
+/// Writes the set as a VarInt length followed by its longs.
+pub fn write_bit_set(buf: &mut dyn BufMut, bits: &BitSet) {
+    write_varint(buf, bits.words().len() as i32);
+    for word in bits.words() {
+        buf.put_u64(*word);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,6 +346,64 @@ mod tests {
         read_string(&mut buf).unwrap();
     }
 
+    #[test]
+    fn test_read_string_missing_length() {
+        let mut buf = BytesMut::new();
+        assert!(matches!(read_string(&mut buf), Err(PacketReadError::EmptyBuf)));
+    }
+
+    #[test]
+    fn test_read_string_negative_length() {
+        let mut buf = BytesMut::new();
+        write_varint(&mut buf, -1);
+        assert!(matches!(read_string(&mut buf), Err(PacketReadError::InvalidLength(-1))));
+    }
+
+    #[test]
+    fn test_read_string_limited() {
+        let mut buf = BytesMut::new();
+        write_string(&mut buf, "Notch");
+        assert_eq!(read_string_limited(&mut buf, 5).unwrap(), "Notch");
+
+        let mut buf = BytesMut::new();
+        write_string(&mut buf, "ThisNameIsTooLong");
+        assert!(matches!(read_string_limited(&mut buf, 16), Err(PacketReadError::StringTooLong(17, 16))));
+
+        // Way more bytes than 16 characters could ever take
+        let mut buf = BytesMut::new();
+        write_varint(&mut buf, 49);
+        assert!(matches!(read_string_limited(&mut buf, 16), Err(PacketReadError::StringTooLong(49, 48))));
+    }
+
+    #[test]
+    fn test_read_string_limited_counts_characters() {
+        // 3 bytes per character in UTF-8, but still only 16 characters
+        let name = "\u{4e16}".repeat(16);
+        let mut buf = BytesMut::new();
+        write_string(&mut buf, &name);
+        assert_eq!(read_string_limited(&mut buf, 16).unwrap(), name);
+    }
+
+    #[test]
+    fn test_read_frame_length() {
+        assert_eq!(read_frame_length(&[]).unwrap(), None);
+        assert_eq!(read_frame_length(&[0x05, 0x00]).unwrap(), Some((1, 5)));
+        assert_eq!(read_frame_length(&[0xAC]).unwrap(), None);
+        assert_eq!(read_frame_length(&[0xAC, 0x02]).unwrap(), Some((2, 300)));
+        assert_eq!(read_frame_length(&[0xFF, 0xFF, 0x7F]).unwrap(), Some((3, MAX_PACKET_SIZE)));
+    }
+
+    #[test]
+    fn test_read_frame_length_too_large() {
+        assert!(matches!(read_frame_length(&[0x80, 0x80, 0x80, 0x01]), Err(PacketReadError::PacketTooLarge(_))));
+        assert!(matches!(read_frame_length(&[0xFF, 0xFF, 0xFF]), Err(PacketReadError::PacketTooLarge(_))));
+    }
+
+    #[test]
+    fn test_read_frame_length_zero() {
+        assert!(matches!(read_frame_length(&[0x00]), Err(PacketReadError::InvalidLength(0))));
+    }
+
     #[test]
     #[should_panic(expected = "Utf8Error")]
     fn test_read_string_invalid_utf8() {
@@ -0,0 +1,9 @@
+use std::{fs, path::PathBuf};
+
+/// An empty directory for a test, unique to `name` and the test process.
+pub fn temp_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("rustcraft_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+use core::fmt;
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum UsernameError {
+    TooShort(usize),
+    TooLong(usize),
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooShort(min) => write!(f, "Invalid username: must be at least {} characters long", min),
+            Self::TooLong(max) => write!(f, "Invalid username: must be at most {} characters long", max),
+            Self::InvalidCharacter(c) => write!(f, "Invalid username: character '{}' is not allowed", c.escape_default()),
+        }
+    }
+}
+
+/// Checks a username the way vanilla does (3-16 characters of `[A-Za-z0-9_]`).
+/// In `relaxed` mode any 1-16 characters are accepted, as long as they aren't whitespace or control characters.
+pub fn validate_username(name: &str, relaxed: bool) -> Result<(), UsernameError> {
+    let length = name.chars().count();
+    let min_length = if relaxed { 1 } else { MIN_USERNAME_LENGTH };
+
+    if length < min_length {
+        return Err(UsernameError::TooShort(min_length));
+    }
+
+    if length > MAX_USERNAME_LENGTH {
+        return Err(UsernameError::TooLong(MAX_USERNAME_LENGTH));
+    }
+
+    let invalid_character = if relaxed {
+        name.chars().find(|c| c.is_whitespace() || c.is_control())
+    } else {
+        name.chars().find(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
+    };
+
+    match invalid_character {
+        Some(c) => Err(UsernameError::InvalidCharacter(c)),
+        None => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_usernames() {
+        for name in ["Notch", "jeb_", "abc", "Player_123456789", "____"] {
+            assert_eq!(validate_username(name, false), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(validate_username("ab", false), Err(UsernameError::TooShort(3)));
+        assert_eq!(validate_username("", false), Err(UsernameError::TooShort(3)));
+        assert_eq!(validate_username("abcdefghijklmnopq", false), Err(UsernameError::TooLong(16)));
+        assert_eq!(validate_username("abcdefghijklmnop", false), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_characters() {
+        assert_eq!(validate_username("Not ch", false), Err(UsernameError::InvalidCharacter(' ')));
+        assert_eq!(validate_username("Notch!", false), Err(UsernameError::InvalidCharacter('!')));
+        assert_eq!(validate_username("Nötch", false), Err(UsernameError::InvalidCharacter('ö')));
+    }
+
+    #[test]
+    fn test_relaxed() {
+        assert_eq!(validate_username("a", true), Ok(()));
+        assert_eq!(validate_username("Nötch-☃", true), Ok(()));
+        assert_eq!(validate_username("", true), Err(UsernameError::TooShort(1)));
+        assert_eq!(validate_username("Not ch", true), Err(UsernameError::InvalidCharacter(' ')));
+        assert_eq!(validate_username("Notch\n", true), Err(UsernameError::InvalidCharacter('\n')));
+        assert_eq!(validate_username("ööööööööööööööööö", true), Err(UsernameError::TooLong(16)));
+    }
+}
\ No newline at end of file
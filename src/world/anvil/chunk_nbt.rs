@@ -0,0 +1,369 @@
+use std::{collections::{HashMap, HashSet}, sync::Mutex};
+
+use nbt::{Blob, Value};
+use once_cell::sync::Lazy;
+
+use crate::{
+    custom_types::position::Position,
+    log,
+    utils::errors::RegionError,
+    world::{
+        bit_storage::BitStorage,
+        block_entity::BlockEntity,
+        chunk::{Chunk, ChunkPos, MIN_Y, SECTION_COUNT},
+        chunk_section::{ChunkSection, UnknownEntries, AIR, SECTION_SIZE},
+        heightmap::{Heightmap, Heightmaps},
+        paletted_container::{ContainerKind, PalettedContainer, BIOMES, BLOCK_STATES},
+        registry::{self, FALLBACK_BLOCK_STATE, PLAINS},
+    },
+    LOGGER,
+};
+
+/// The data version of 1.21, which tells vanilla which format chunks are saved in.
+pub const DATA_VERSION: i32 = 3953;
+pub const FULL_STATUS: &str = "minecraft:full";
+
+/// Root tags the server writes itself, the others are kept from the loaded chunk.
+/// `isLightOn` is dropped since light isn't saved, which makes vanilla light the chunk again.
+const OWNED_TAGS: [&str; 9] = ["DataVersion", "xPos", "zPos", "yPos", "Status", "sections", "block_entities", "Heightmaps", "isLightOn"];
+/// Block entity tags that are part of `BlockEntity` itself rather than its data.
+const BLOCK_ENTITY_KEYS: [&str; 5] = ["id", "x", "y", "z", "keepPacked"];
+
+type Compound = HashMap<String, Value>;
+
+/// Names of unknown blocks and biomes that were already warned about.
+static WARNED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// `handling` says what happens to it instead, it's always saved unchanged.
+fn warn_unknown(kind: &str, name: &str, handling: &str) {
+    if WARNED.lock().unwrap().insert(name.to_owned()) {
+        log!(warn, "Unknown {} '{}' in a saved chunk, {} but it's saved unchanged", kind, name, handling);
+    }
+}
+
+fn get<'a>(compound: &'a Compound, name: &'static str) -> Result<&'a Value, RegionError> {
+    compound.get(name).ok_or(RegionError::MissingTag(name))
+}
+
+fn int(compound: &Compound, name: &'static str) -> Result<i32, RegionError> {
+    match get(compound, name)? {
+        Value::Int(value) => Ok(*value),
+        _ => Err(RegionError::InvalidTag(name)),
+    }
+}
+
+fn string<'a>(compound: &'a Compound, name: &'static str) -> Result<&'a str, RegionError> {
+    match get(compound, name)? {
+        Value::String(value) => Ok(value),
+        _ => Err(RegionError::InvalidTag(name)),
+    }
+}
+
+/// A list tag, where a missing list counts as an empty one.
+fn list<'a>(value: Option<&'a Value>, name: &'static str) -> Result<&'a [Value], RegionError> {
+    match value {
+        Some(Value::List(values)) => Ok(values),
+        None => Ok(&[]),
+        _ => Err(RegionError::InvalidTag(name)),
+    }
+}
+
+/// Every root tag of `blob`. `Blob` can't list its tags, so this goes through its binary form.
+fn root_tags(blob: &Blob) -> Result<Compound, RegionError> {
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes)?;
+    // Tag type and name
+    let name_length = u16::from_be_bytes([bytes[1], bytes[2]]) as usize;
+    match Value::from_reader(0x0A, &mut &bytes[3 + name_length..])? {
+        Value::Compound(tags) => Ok(tags),
+        _ => Err(RegionError::InvalidTag("root")),
+    }
+}
+
+fn compound<'a>(value: &'a Value, name: &'static str) -> Result<&'a Compound, RegionError> {
+    match value {
+        Value::Compound(compound) => Ok(compound),
+        _ => Err(RegionError::InvalidTag(name)),
+    }
+}
+
+/// Whether the chunk finished generating. Vanilla generates the others again, so they're best treated as missing.
+pub fn is_complete(blob: &Blob) -> bool {
+    matches!(blob.get("Status"), Some(Value::String(status)) if status == FULL_STATUS || status == "full")
+}
+
+/// Builds a chunk from its saved NBT. Blocks and biomes the server doesn't know are shown as stone and plains,
+/// and kept in the sections so `encode` writes them back.
+pub fn decode(blob: &Blob) -> Result<Chunk, RegionError> {
+    let root = |name: &'static str| blob.get(name).ok_or(RegionError::MissingTag(name));
+    let coordinate = |name: &'static str| match root(name)? {
+        Value::Int(value) => Ok(*value),
+        _ => Err(RegionError::InvalidTag(name)),
+    };
+    let pos = ChunkPos::new(coordinate("xPos")?, coordinate("zPos")?);
+
+    let mut sections = vec![ChunkSection::new(PLAINS); SECTION_COUNT];
+    for section in list(blob.get("sections"), "sections")? {
+        let section = compound(section, "sections")?;
+        let index = match get(section, "Y")? {
+            Value::Byte(y) => *y as i64 - MIN_Y / SECTION_SIZE as i64,
+            _ => return Err(RegionError::InvalidTag("Y")),
+        };
+        // Sections right below and above the world only hold light
+        if !(0..SECTION_COUNT as i64).contains(&index) { continue; }
+
+        let (block_states, unknown_block_states) = match section.get("block_states") {
+            Some(value) => decode_container(compound(value, "block_states")?, BLOCK_STATES, block_state)?,
+            None => (PalettedContainer::new(BLOCK_STATES, AIR), HashMap::new()),
+        };
+        let (biomes, unknown_biomes) = match section.get("biomes") {
+            Some(value) => decode_container(compound(value, "biomes")?, BIOMES, biome)?,
+            None => (PalettedContainer::new(BIOMES, PLAINS), HashMap::new()),
+        };
+        let section = &mut sections[index as usize];
+        *section = ChunkSection::from_containers(block_states, biomes);
+        section.set_unknown(UnknownEntries { block_states: unknown_block_states, biomes: unknown_biomes });
+    }
+
+    let mut chunk = Chunk::from_sections(pos, sections);
+
+    for block_entity in list(blob.get("block_entities"), "block_entities")? {
+        let block_entity = compound(block_entity, "block_entities")?;
+        match decode_block_entity(block_entity)? {
+            Some(decoded) => chunk.set_block_entity(decoded),
+            None => chunk.add_unknown_block_entity(block_entity_position(block_entity)?, Value::Compound(block_entity.clone())),
+        }
+    }
+
+    // Heightmaps vanilla saved know more about blocks than the server, so they're kept when they're there
+    if let Some(Value::Compound(heightmaps)) = blob.get("Heightmaps") {
+        if let (Some(motion_blocking), Some(world_surface)) = (heightmap(heightmaps, "MOTION_BLOCKING"), heightmap(heightmaps, "WORLD_SURFACE")) {
+            chunk.set_heightmaps(Heightmaps { motion_blocking, world_surface });
+        }
+    }
+
+    let mut tags = root_tags(blob)?;
+    tags.retain(|name, _| !OWNED_TAGS.contains(&name.as_str()));
+    chunk.set_extra_tags(tags);
+
+    Ok(chunk)
+}
+
+/// Anvil palettes always hold every value of the container, and the data always indexes into them.
+/// `entry` gives the ID and whether it's a fallback for an unknown entry, which is returned by index.
+fn decode_container(container: &Compound, kind: ContainerKind, entry: fn(&Value) -> Result<(u32, bool), RegionError>) -> Result<(PalettedContainer, HashMap<usize, Value>), RegionError> {
+    let entries = list(Some(get(container, "palette")?), "palette")?;
+    let palette: Vec<(u32, bool)> = entries.iter().map(entry).collect::<Result<_, _>>()?;
+    let Some(&(first, _)) = palette.first() else { return Err(RegionError::InvalidTag("palette")); };
+
+    let data = match container.get("data") {
+        Some(Value::LongArray(data)) if palette.len() > 1 => data,
+        Some(Value::LongArray(_)) | None => {
+            let unknown = match palette[0] {
+                (_, true) => (0..kind.size).map(|index| (index, entries[0].clone())).collect(),
+                (_, false) => HashMap::new(),
+            };
+            return Ok((PalettedContainer::new(kind, first), unknown));
+        },
+        Some(_) => return Err(RegionError::InvalidTag("data")),
+    };
+
+    let bits = ((usize::BITS - (palette.len() - 1).leading_zeros()) as u8).max(kind.min_indirect_bits);
+    let storage = BitStorage::from_data(bits, kind.size, data.iter().map(|&long| long as u64).collect())
+        .map_err(|_| RegionError::InvalidTag("data"))?;
+
+    let mut container = PalettedContainer::new(kind, first);
+    let mut unknown = HashMap::new();
+    for index in 0..kind.size {
+        let palette_index = storage.get(index) as usize;
+        let Some(&(value, is_unknown)) = palette.get(palette_index) else { return Err(RegionError::InvalidTag("data")); };
+        container.set(index, value);
+        if is_unknown {
+            unknown.insert(index, entries[palette_index].clone());
+        }
+    }
+
+    Ok((container, unknown))
+}
+
+fn block_state(value: &Value) -> Result<(u32, bool), RegionError> {
+    let state = compound(value, "palette")?;
+    let properties = match state.get("Properties") {
+        Some(Value::Compound(properties)) => properties.iter()
+            .filter_map(|(key, value)| match value {
+                Value::String(value) => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect(),
+        _ => HashMap::new(),
+    };
+
+    let name = string(state, "Name")?;
+    match registry::block_state_id(name, &properties) {
+        Some(id) => Ok((id, false)),
+        None => {
+            warn_unknown("block", name, "it's shown as stone");
+            Ok((FALLBACK_BLOCK_STATE, true))
+        }
+    }
+}
+
+fn biome(value: &Value) -> Result<(u32, bool), RegionError> {
+    match value {
+        Value::String(name) => match registry::biome_id(name) {
+            Some(id) => Ok((id, false)),
+            None => {
+                warn_unknown("biome", name, "it's shown as plains");
+                Ok((PLAINS, true))
+            }
+        },
+        _ => Err(RegionError::InvalidTag("palette")),
+    }
+}
+
+fn block_entity_position(block_entity: &Compound) -> Result<Position, RegionError> {
+    Ok(Position::new(int(block_entity, "x")? as i64, int(block_entity, "y")? as i64, int(block_entity, "z")? as i64))
+}
+
+/// `None` for block entity types the server doesn't know, which can't be sent.
+fn decode_block_entity(block_entity: &Compound) -> Result<Option<BlockEntity>, RegionError> {
+    let id = string(block_entity, "id")?;
+    let Some(kind) = registry::block_entity_type_id(id) else {
+        warn_unknown("block entity", id, "it isn't sent to players");
+        return Ok(None);
+    };
+    let position = block_entity_position(block_entity)?;
+    let data = block_entity.iter()
+        .filter(|(key, _)| !BLOCK_ENTITY_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    Ok(Some(BlockEntity { position, kind, data: Value::Compound(data) }))
+}
+
+fn heightmap(heightmaps: &Compound, name: &str) -> Option<Heightmap> {
+    match heightmaps.get(name) {
+        Some(Value::LongArray(data)) => Heightmap::from_data(data.iter().map(|&long| long as u64).collect()).ok(),
+        _ => None,
+    }
+}
+
+/// Turns a chunk into the NBT vanilla saves, on top of the tags it was loaded with.
+/// Light is left out, which makes vanilla light the chunk again.
+pub fn encode(chunk: &Chunk) -> Blob {
+    let sections = chunk.sections().iter().enumerate()
+        .map(|(index, section)| Value::Compound(HashMap::from([
+            ("Y".to_owned(), Value::Byte((index as i64 + MIN_Y / SECTION_SIZE as i64) as i8)),
+            ("block_states".to_owned(), encode_container(section.block_states(), section.unknown().map(|unknown| &unknown.block_states), encode_block_state)),
+            ("biomes".to_owned(), encode_container(section.biomes(), section.unknown().map(|unknown| &unknown.biomes), encode_biome)),
+        ])))
+        .collect();
+    let block_entities = chunk.block_entities().iter().filter_map(encode_block_entity)
+        .chain(chunk.unknown_block_entities().iter().map(|(_, data)| data.clone()))
+        .collect();
+
+    let heightmaps = chunk.heightmaps();
+    let heightmap = |heightmap: &Heightmap| Value::LongArray(heightmap.data().iter().map(|&long| long as i64).collect());
+    let heightmaps = HashMap::from([
+        ("MOTION_BLOCKING".to_owned(), heightmap(&heightmaps.motion_blocking)),
+        ("WORLD_SURFACE".to_owned(), heightmap(&heightmaps.world_surface)),
+    ]);
+
+    let pos = chunk.pos();
+    let mut blob = Blob::new();
+    // Vanilla reads a missing `LastUpdate` and `InhabitedTime` as 0
+    for (name, value) in chunk.extra_tags() {
+        let _ = blob.insert(name.as_str(), value.clone());
+    }
+
+    for (name, value) in [
+        ("DataVersion", Value::Int(DATA_VERSION)),
+        ("xPos", Value::Int(pos.x)),
+        ("zPos", Value::Int(pos.z)),
+        ("yPos", Value::Int((MIN_Y / SECTION_SIZE as i64) as i32)),
+        ("Status", Value::String(FULL_STATUS.to_owned())),
+        ("sections", Value::List(sections)),
+        ("block_entities", Value::List(block_entities)),
+        ("Heightmaps", Value::Compound(heightmaps)),
+    ] {
+        // Only fails for lists mixing tag types
+        let _ = blob.insert(name, value);
+    }
+
+    blob
+}
+
+/// Entries in `unknown` are written as they were loaded instead of what the container holds there.
+fn encode_container(container: &PalettedContainer, unknown: Option<&HashMap<usize, Value>>, entry: fn(u32) -> Value) -> Value {
+    let kind = container.kind();
+    let mut palette = Vec::new();
+    let mut ids = HashMap::new();
+    // Rare enough to look up one by one
+    let mut unknown_ids: Vec<(&Value, u32)> = Vec::new();
+    let indices: Vec<u32> = (0..kind.size)
+        .map(|index| match unknown.and_then(|unknown| unknown.get(&index)) {
+            Some(original) => match unknown_ids.iter().find(|(value, _)| *value == original) {
+                Some(&(_, id)) => id,
+                None => {
+                    palette.push(original.clone());
+                    unknown_ids.push((original, palette.len() as u32 - 1));
+                    palette.len() as u32 - 1
+                }
+            },
+            None => {
+                let value = container.get(index);
+                *ids.entry(value).or_insert_with(|| {
+                    palette.push(entry(value));
+                    palette.len() as u32 - 1
+                })
+            }
+        })
+        .collect();
+
+    let palette_length = palette.len();
+    let mut compound = HashMap::from([("palette".to_owned(), Value::List(palette))]);
+    if palette_length > 1 {
+        let bits = ((usize::BITS - (palette_length - 1).leading_zeros()) as u8).max(kind.min_indirect_bits);
+        let mut storage = BitStorage::new(bits, kind.size);
+        for (index, id) in indices.into_iter().enumerate() {
+            storage.set(index, id);
+        }
+        compound.insert("data".to_owned(), Value::LongArray(storage.data().iter().map(|&long| long as i64).collect()));
+    }
+
+    Value::Compound(compound)
+}
+
+fn encode_block_state(state: u32) -> Value {
+    let (name, properties) = registry::block_state(state)
+        .or_else(|| registry::block_state(FALLBACK_BLOCK_STATE))
+        .unwrap_or(("minecraft:stone", &[]));
+
+    let mut compound = HashMap::from([("Name".to_owned(), Value::String(name.to_owned()))]);
+    if !properties.is_empty() {
+        let properties = properties.iter().map(|(key, value)| (key.to_string(), Value::String(value.to_string()))).collect();
+        compound.insert("Properties".to_owned(), Value::Compound(properties));
+    }
+
+    Value::Compound(compound)
+}
+
+fn encode_biome(biome: u32) -> Value {
+    Value::String(registry::biome_name(biome).or_else(|| registry::biome_name(PLAINS)).unwrap_or_default().to_owned())
+}
+
+fn encode_block_entity(block_entity: &BlockEntity) -> Option<Value> {
+    let kind = registry::block_entity_type_name(block_entity.kind)?;
+    let mut compound = match &block_entity.data {
+        Value::Compound(data) => data.clone(),
+        _ => HashMap::new(),
+    };
+
+    let position = &block_entity.position;
+    compound.insert("id".to_owned(), Value::String(kind.to_owned()));
+    compound.insert("x".to_owned(), Value::Int(position.x() as i32));
+    compound.insert("y".to_owned(), Value::Int(position.y() as i32));
+    compound.insert("z".to_owned(), Value::Int(position.z() as i32));
+    compound.insert("keepPacked".to_owned(), Value::Byte(0));
+    Some(Value::Compound(compound))
+}
\ No newline at end of file
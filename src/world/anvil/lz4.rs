@@ -0,0 +1,136 @@
+use twox_hash::XxHash32;
+
+use crate::utils::errors::RegionError;
+
+const MAGIC: &[u8; 8] = b"LZ4Block";
+/// Magic, token, compressed length, decompressed length and checksum.
+const HEADER_LENGTH: usize = 21;
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+/// lz4-java's default block size, 64 KiB, and the compression level it writes for it.
+const BLOCK_SIZE: usize = 1 << 16;
+const COMPRESSION_LEVEL: u8 = 6;
+/// lz4-java checksums blocks with XXH32 and this seed, keeping the lower 28 bits.
+const CHECKSUM_SEED: u32 = 0x9747B28C;
+const CHECKSUM_MASK: u32 = 0x0FFFFFFF;
+
+fn corrupt(reason: &str) -> RegionError {
+    RegionError::Decompression(format!("LZ4: {}", reason))
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    XxHash32::oneshot(CHECKSUM_SEED, data) & CHECKSUM_MASK
+}
+
+/// Decompresses the blocks lz4-java's `LZ4BlockOutputStream` writes, which is what Anvil stores for LZ4 chunks.
+pub fn decompress_blocks(mut input: &[u8]) -> Result<Vec<u8>, RegionError> {
+    let mut output = Vec::new();
+
+    loop {
+        if input.len() < HEADER_LENGTH || &input[..MAGIC.len()] != MAGIC { return Err(corrupt("missing block header")); }
+        let token = input[8];
+        let compressed = u32::from_le_bytes([input[9], input[10], input[11], input[12]]) as usize;
+        let decompressed = u32::from_le_bytes([input[13], input[14], input[15], input[16]]) as usize;
+        let expected = u32::from_le_bytes([input[17], input[18], input[19], input[20]]);
+        input = &input[HEADER_LENGTH..];
+
+        // An empty block ends the stream
+        if decompressed == 0 { return Ok(output); }
+        if input.len() < compressed { return Err(corrupt("block longer than the data")); }
+
+        let block = &input[..compressed];
+        let start = output.len();
+        match token & 0xF0 {
+            METHOD_RAW if compressed == decompressed => output.extend_from_slice(block),
+            METHOD_LZ4 => {
+                let data = lz4_flex::block::decompress(block, decompressed).map_err(|e| corrupt(&e.to_string()))?;
+                if data.len() != decompressed { return Err(corrupt("wrong decompressed length")); }
+                output.extend(data);
+            },
+            _ => return Err(corrupt("unknown block method")),
+        }
+
+        if checksum(&output[start..]) != expected { return Err(corrupt("checksum mismatch")); }
+        input = &input[compressed..];
+    }
+}
+
+/// Compresses `data` into the blocks lz4-java's `LZ4BlockInputStream` reads.
+pub fn compress_blocks(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    for block in data.chunks(BLOCK_SIZE) {
+        let compressed = lz4_flex::block::compress(block);
+
+        // Blocks that don't get smaller are stored as they are
+        let (method, body) = if compressed.len() < block.len() { (METHOD_LZ4, &compressed[..]) } else { (METHOD_RAW, block) };
+        write_header(&mut output, method, body.len(), block.len(), checksum(block));
+        output.extend_from_slice(body);
+    }
+
+    write_header(&mut output, METHOD_RAW, 0, 0, 0);
+    output
+}
+
+fn write_header(output: &mut Vec<u8>, method: u8, compressed: usize, decompressed: usize, checksum: u32) {
+    output.extend_from_slice(MAGIC);
+    output.push(method | COMPRESSION_LEVEL);
+    output.extend_from_slice(&(compressed as u32).to_le_bytes());
+    output.extend_from_slice(&(decompressed as u32).to_le_bytes());
+    output.extend_from_slice(&checksum.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(token: u8, compressed: u32, decompressed: u32, checksum: u32) -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        header.push(token);
+        header.extend_from_slice(&compressed.to_le_bytes());
+        header.extend_from_slice(&decompressed.to_le_bytes());
+        header.extend_from_slice(&checksum.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn test_decompress_blocks() {
+        let mut input = header(METHOD_RAW, 3, 3, checksum(b"raw"));
+        input.extend_from_slice(b"raw");
+        // "ab", then 8 bytes copied from 2 back, then "c" on its own
+        input.extend(header(METHOD_LZ4 | 1, 7, 11, checksum(b"abababababc")));
+        input.extend_from_slice(&[0x24, b'a', b'b', 2, 0, 0x10, b'c']);
+        input.extend(header(METHOD_RAW, 0, 0, 0));
+        assert_eq!(decompress_blocks(&input).unwrap(), b"rawabababababc");
+
+        // No end block
+        input.truncate(input.len() - HEADER_LENGTH);
+        assert!(decompress_blocks(&input).is_err());
+    }
+
+    #[test]
+    fn test_checksum() {
+        // XXH32 with lz4-java's seed, masked to 28 bits
+        assert_eq!(checksum(b"abc"), 0x0D4CB222);
+        assert_eq!(checksum(b"Nobody inspects the spammish repetition"), 0x00B91719);
+
+        let mut input = header(METHOD_RAW, 3, 3, checksum(b"raw") ^ 1);
+        input.extend_from_slice(b"raw");
+        input.extend(header(METHOD_RAW, 0, 0, 0));
+        assert!(decompress_blocks(&input).is_err());
+    }
+
+    #[test]
+    fn test_compress() {
+        let repetitive: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8 ^ (i / 5000) as u8).collect();
+        let compressed = compress_blocks(&repetitive);
+        assert!(compressed.len() < repetitive.len() / 10);
+        assert_eq!(decompress_blocks(&compressed).unwrap(), repetitive);
+
+        // Too short to compress, so it's stored raw
+        let compressed = compress_blocks(b"short");
+        assert_eq!(compressed[8], METHOD_RAW | COMPRESSION_LEVEL);
+        assert_eq!(decompress_blocks(&compressed).unwrap(), b"short");
+        assert_eq!(decompress_blocks(&compress_blocks(&[])).unwrap(), b"");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,4 @@
+pub mod chunk_nbt;
+pub mod lz4;
+pub mod region;
+pub mod storage;
\ No newline at end of file
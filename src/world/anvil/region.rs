@@ -0,0 +1,326 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use nbt::Blob;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{utils::errors::RegionError, world::chunk::ChunkPos};
+
+use super::lz4;
+
+pub const SECTOR_SIZE: usize = 4096;
+/// Chunks per region along each axis.
+pub const REGION_SIZE: i32 = 32;
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE) as usize;
+/// The chunk locations, then the times they were saved.
+const HEADER_SECTORS: usize = 2;
+/// Set on the compression type of chunks too big for the region, which sit in their own `.mcc` file.
+const EXTERNAL_FLAG: u8 = 0x80;
+/// The most sectors a location entry can point at.
+const MAX_CHUNK_SECTORS: usize = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip = 1,
+    Zlib = 2,
+    None = 3,
+    Lz4 = 4,
+}
+
+impl Compression {
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Gzip),
+            2 => Some(Self::Zlib),
+            3 => Some(Self::None),
+            4 => Some(Self::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn decompress(self, mut data: &[u8]) -> Result<Blob, RegionError> {
+        let blob = match self {
+            Self::Gzip => Blob::from_gzip_reader(&mut data)?,
+            Self::Zlib => Blob::from_zlib_reader(&mut data)?,
+            Self::None => Blob::from_reader(&mut data)?,
+            Self::Lz4 => Blob::from_reader(&mut &lz4::decompress_blocks(data)?[..])?,
+        };
+
+        Ok(blob)
+    }
+
+    pub fn compress(self, blob: &Blob) -> Result<Vec<u8>, RegionError> {
+        let mut data = Vec::new();
+        match self {
+            Self::Gzip => blob.to_gzip_writer(&mut data)?,
+            Self::Zlib => blob.to_zlib_writer(&mut data)?,
+            Self::None => blob.to_writer(&mut data)?,
+            Self::Lz4 => {
+                let mut raw = Vec::new();
+                blob.to_writer(&mut raw)?;
+                data = lz4::compress_blocks(&raw);
+            },
+        }
+
+        Ok(data)
+    }
+}
+
+/// An Anvil `.mca` file holding the chunks of a 32x32 chunk region.
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    /// Sector offset in the upper 24 bits and sector count in the lower 8 of each chunk, 0 if it isn't saved.
+    locations: [u32; CHUNKS_PER_REGION],
+    /// Unix time each chunk was last saved at.
+    timestamps: [u32; CHUNKS_PER_REGION],
+}
+
+impl RegionFile {
+    /// The file in `dir` holding chunk `pos`.
+    pub fn path_for(dir: &Path, pos: ChunkPos) -> PathBuf {
+        dir.join(format!("r.{}.{}.mca", pos.x.div_euclid(REGION_SIZE), pos.z.div_euclid(REGION_SIZE)))
+    }
+
+    /// Opens the file, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> Result<Self, RegionError> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let length = file.metadata()?.len() as usize;
+        let mut region = Self { path: path.to_owned(), file, locations: [0; CHUNKS_PER_REGION], timestamps: [0; CHUNKS_PER_REGION] };
+
+        // A new file gets an empty header
+        if length == 0 {
+            region.file.set_len((HEADER_SECTORS * SECTOR_SIZE) as u64)?;
+            return Ok(region);
+        }
+        if length < HEADER_SECTORS * SECTOR_SIZE { return Err(RegionError::InvalidHeader(length)); }
+
+        let mut header = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+        region.file.read_exact(&mut header)?;
+        for (index, entry) in header.chunks_exact(4).enumerate() {
+            let value = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            match index {
+                index if index < CHUNKS_PER_REGION => region.locations[index] = value,
+                index => region.timestamps[index - CHUNKS_PER_REGION] = value,
+            }
+        }
+
+        Ok(region)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn index(pos: ChunkPos) -> usize {
+        (pos.x.rem_euclid(REGION_SIZE) + pos.z.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+    }
+
+    pub fn has_chunk(&self, pos: ChunkPos) -> bool {
+        self.locations[Self::index(pos)] != 0
+    }
+
+    pub fn timestamp(&self, pos: ChunkPos) -> u32 {
+        self.timestamps[Self::index(pos)]
+    }
+
+    /// The chunk's NBT, or `None` if it was never saved.
+    pub fn read_chunk(&mut self, pos: ChunkPos) -> Result<Option<Blob>, RegionError> {
+        let location = self.locations[Self::index(pos)];
+        if location == 0 { return Ok(None); }
+
+        let (offset, sectors) = ((location >> 8) as usize, (location & 0xFF) as usize);
+        let file_length = self.file.metadata()?.len() as usize;
+        if offset < HEADER_SECTORS || sectors == 0 || offset * SECTOR_SIZE >= file_length {
+            return Err(RegionError::InvalidLocation(offset, sectors));
+        }
+
+        // Length of the compression type and data, then the compression type
+        let mut header = [0; 5];
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if length == 0 || length + 4 > sectors * SECTOR_SIZE { return Err(RegionError::InvalidLocation(offset, sectors)); }
+
+        let data = if header[4] & EXTERNAL_FLAG != 0 {
+            fs::read(self.external_path(pos))?
+        } else {
+            let mut data = vec![0; length - 1];
+            self.file.read_exact(&mut data)?;
+            data
+        };
+
+        let kind = header[4] & !EXTERNAL_FLAG;
+        let compression = Compression::from_id(kind).ok_or(RegionError::UnknownCompression(kind))?;
+        compression.decompress(&data).map(Some)
+    }
+
+    /// Saves the chunk into sectors nothing else uses, then points the header at them once they're on disk,
+    /// so the old copy stays intact until the header is updated with a single write.
+    pub fn write_chunk(&mut self, pos: ChunkPos, blob: &Blob, compression: Compression) -> Result<(), RegionError> {
+        let index = Self::index(pos);
+        let data = compression.compress(blob)?;
+        let external = data.len() + 5 > MAX_CHUNK_SECTORS * SECTOR_SIZE;
+
+        // Length of the compression type and data, then the compression type and data
+        let mut entry = Vec::with_capacity(data.len() + 5);
+        if external {
+            write_atomically(&self.external_path(pos), &data)?;
+            entry.extend_from_slice(&1u32.to_be_bytes());
+            entry.push(compression.id() | EXTERNAL_FLAG);
+        } else {
+            entry.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+            entry.push(compression.id());
+            entry.extend_from_slice(&data);
+        }
+
+        let sectors = entry.len().div_ceil(SECTOR_SIZE);
+        entry.resize(sectors * SECTOR_SIZE, 0);
+        let offset = self.allocate(sectors);
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&entry)?;
+        self.file.sync_data()?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as u32);
+        self.locations[index] = ((offset as u32) << 8) | sectors as u32;
+        self.timestamps[index] = timestamp;
+        self.file.seek(SeekFrom::Start((index * 4) as u64))?;
+        self.file.write_all(&self.locations[index].to_be_bytes())?;
+        self.file.seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+        self.file.sync_data()?;
+
+        // An older copy that was too big for the region isn't needed anymore
+        if !external {
+            match fs::remove_file(self.external_path(pos)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The first run of `sectors` sectors no chunk uses, which may go past the end of the file.
+    fn allocate(&self, sectors: usize) -> usize {
+        let mut used: Vec<(usize, usize)> = self.locations.iter()
+            .filter(|&&location| location != 0)
+            .map(|&location| ((location >> 8) as usize, (location & 0xFF) as usize))
+            .collect();
+        used.sort_unstable();
+
+        let mut start = HEADER_SECTORS;
+        for (offset, count) in used {
+            if offset >= start + sectors { break; }
+            start = start.max(offset + count);
+        }
+
+        start
+    }
+
+    /// Where a chunk too big for the region is stored.
+    fn external_path(&self, pos: ChunkPos) -> PathBuf {
+        self.path.with_file_name(format!("c.{}.{}.mcc", pos.x, pos.z))
+    }
+}
+
+/// Writes to a temporary file first, so `path` holds either the old or the new data.
+fn write_atomically(path: &Path, data: &[u8]) -> Result<(), RegionError> {
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nbt::Value;
+
+    use crate::utils::test_utils::temp_directory;
+
+    use super::*;
+
+    fn temp_region(name: &str) -> PathBuf {
+        let directory = temp_directory(&format!("region_{}", name));
+        directory.join("r.0.0.mca")
+    }
+
+    fn blob(size: usize) -> Blob {
+        let mut blob = Blob::new();
+        // Doesn't compress, so the size on disk is known
+        let data = (0..size).map(|i| (i.wrapping_mul(2654435761) >> 13) as i8).collect();
+        blob.insert("data", Value::ByteArray(data)).unwrap();
+        blob
+    }
+
+    fn location(region: &RegionFile, pos: ChunkPos) -> (u32, u32) {
+        let location = region.locations[RegionFile::index(pos)];
+        (location >> 8, location & 0xFF)
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let path = temp_region("write");
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 8192);
+
+        for (x, compression) in [(0, Compression::Gzip), (1, Compression::Zlib), (2, Compression::None), (3, Compression::Lz4)] {
+            region.write_chunk(ChunkPos::new(x, 5), &blob(100 * (x as usize + 1)), compression).unwrap();
+        }
+
+        let mut region = RegionFile::open(&path).unwrap();
+        for x in 0..4 {
+            let pos = ChunkPos::new(x, 5);
+            assert_eq!(region.read_chunk(pos).unwrap(), Some(blob(100 * (x as usize + 1))));
+            assert_eq!(location(&region, pos), (2 + x as u32, 1));
+            assert!(region.timestamp(pos) > 0);
+        }
+        assert_eq!(region.read_chunk(ChunkPos::new(4, 5)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sector_allocation() {
+        let path = temp_region("allocation");
+        let mut region = RegionFile::open(&path).unwrap();
+        let (first, second) = (ChunkPos::new(0, 0), ChunkPos::new(31, 31));
+
+        region.write_chunk(first, &blob(100), Compression::None).unwrap();
+        region.write_chunk(second, &blob(100), Compression::None).unwrap();
+        assert_eq!(location(&region, second), (3, 1));
+
+        // Growing chunks move past the end, and the sectors they leave get reused
+        region.write_chunk(first, &blob(10_000), Compression::None).unwrap();
+        assert_eq!(location(&region, first), (4, 3));
+        region.write_chunk(second, &blob(4000), Compression::None).unwrap();
+        assert_eq!(location(&region, second), (2, 1));
+        region.write_chunk(second, &blob(4000), Compression::None).unwrap();
+        assert_eq!(location(&region, second), (3, 1));
+
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(region.read_chunk(first).unwrap(), Some(blob(10_000)));
+        assert_eq!(region.read_chunk(second).unwrap(), Some(blob(4000)));
+    }
+
+    #[test]
+    fn test_external_chunk() {
+        let path = temp_region("external");
+        let pos = ChunkPos::new(-31, 2);
+        let external = path.with_file_name("c.-31.2.mcc");
+        let mut region = RegionFile::open(&path).unwrap();
+
+        region.write_chunk(pos, &blob(MAX_CHUNK_SECTORS * SECTOR_SIZE), Compression::None).unwrap();
+        assert!(external.exists());
+        assert_eq!(location(&region, pos), (2, 1));
+        assert_eq!(region.read_chunk(pos).unwrap(), Some(blob(MAX_CHUNK_SECTORS * SECTOR_SIZE)));
+
+        region.write_chunk(pos, &blob(10), Compression::None).unwrap();
+        assert!(!external.exists());
+        assert_eq!(RegionFile::open(&path).unwrap().read_chunk(pos).unwrap(), Some(blob(10)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,234 @@
+use std::{collections::{hash_map::Entry, HashMap}, fs, path::PathBuf};
+
+use crate::{utils::errors::RegionError, world::chunk::{Chunk, ChunkPos}};
+
+use super::{chunk_nbt, region::{Compression, RegionFile, REGION_SIZE}};
+
+/// Where region files are kept within a world's directory.
+pub const REGION_DIRECTORY: &str = "region";
+
+/// The chunks saved in the region files of a world's `region` directory.
+pub struct AnvilStorage {
+    dir: PathBuf,
+    /// Used for chunks that get saved. Chunks of any compression can be loaded.
+    compression: Compression,
+    /// Region files that were opened, by region coordinates.
+    regions: HashMap<(i32, i32), RegionFile>,
+}
+
+impl AnvilStorage {
+    pub fn new(dir: impl Into<PathBuf>, compression: Compression) -> Self {
+        Self { dir: dir.into(), compression, regions: HashMap::new() }
+    }
+
+    /// `None` if the chunk was never saved or didn't finish generating.
+    pub fn load_chunk(&mut self, pos: ChunkPos) -> Result<Option<Chunk>, RegionError> {
+        // Nothing in a region was saved if it has no file
+        if !self.regions.contains_key(&Self::region_key(pos)) && !RegionFile::path_for(&self.dir, pos).exists() { return Ok(None); }
+
+        let Some(blob) = self.region(pos)?.read_chunk(pos)? else { return Ok(None); };
+        if !chunk_nbt::is_complete(&blob) { return Ok(None); }
+
+        chunk_nbt::decode(&blob).map(Some)
+    }
+
+    pub fn save_chunk(&mut self, chunk: &Chunk) -> Result<(), RegionError> {
+        let compression = self.compression;
+        self.region(chunk.pos())?.write_chunk(chunk.pos(), &chunk_nbt::encode(chunk), compression)
+    }
+
+    fn region_key(pos: ChunkPos) -> (i32, i32) {
+        (pos.x.div_euclid(REGION_SIZE), pos.z.div_euclid(REGION_SIZE))
+    }
+
+    /// The region file holding `pos`, which is created if it doesn't exist.
+    fn region(&mut self, pos: ChunkPos) -> Result<&mut RegionFile, RegionError> {
+        match self.regions.entry(Self::region_key(pos)) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                fs::create_dir_all(&self.dir)?;
+                Ok(entry.insert(RegionFile::open(&RegionFile::path_for(&self.dir, pos))?))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        custom_types::position::Position,
+        utils::test_utils::temp_directory,
+        world::{chunk_section::AIR, heightmap::Heightmaps, registry::{self, FALLBACK_BLOCK_STATE, PLAINS}},
+    };
+
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/region");
+
+    fn load(x: i32, z: i32) -> Result<Option<Chunk>, RegionError> {
+        AnvilStorage::new(FIXTURES, Compression::Zlib).load_chunk(ChunkPos::new(x, z))
+    }
+
+    #[test]
+    fn test_decode_chunk() {
+        let chunk = load(0, 0).unwrap().unwrap();
+        let desert = registry::biome_id("minecraft:desert").unwrap();
+
+        assert_eq!(chunk.pos(), ChunkPos::new(0, 0));
+        assert_eq!(chunk.get_block(&Position::new(5, -64, 5)), Some(79));
+        assert_eq!(chunk.get_block(&Position::new(5, -61, 5)), Some(1));
+        assert_eq!(chunk.get_block(&Position::new(0, -60, 0)), Some(9));
+        assert_eq!(chunk.get_block(&Position::new(1, -60, 0)), Some(AIR));
+        assert_eq!(chunk.sections()[0].block_count(), 16 * 16 * 4 + 1);
+
+        // Cave air is air, unknown blocks are stone, and the palette of 22 entries needed 5 bits
+        assert_eq!(chunk.get_block(&Position::new(0, 16, 0)), Some(AIR));
+        assert_eq!(chunk.get_block(&Position::new(1, 16, 0)), Some(FALLBACK_BLOCK_STATE));
+        assert_eq!(chunk.get_block(&Position::new(5, 16, 1)), Some(132));
+        assert_eq!(chunk.get_biome(&Position::new(0, 16, 0)), Some(PLAINS));
+
+        assert_eq!(chunk.get_biome(&Position::new(0, -64, 0)), Some(PLAINS));
+        assert_eq!(chunk.get_biome(&Position::new(15, 15, 15)), Some(desert));
+        assert_eq!(chunk.get_biome(&Position::new(11, 11, 11)), Some(PLAINS));
+        // Sections that weren't saved are empty
+        assert!(chunk.sections()[10].is_empty());
+
+        assert_eq!(chunk.block_entities().len(), 1);
+        let chest = &chunk.block_entities()[0];
+        assert_eq!(chest.position, Position::new(1, -60, 2));
+        assert_eq!(chest.kind, registry::block_entity_type_id("minecraft:chest").unwrap());
+        let nbt::Value::Compound(data) = &chest.data else { panic!("block entity data isn't a compound") };
+        let mut keys: Vec<&str> = data.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["CustomName", "Items"]);
+
+        assert_eq!(*chunk.heightmaps(), Heightmaps::compute(chunk.sections()));
+        assert_eq!(chunk.heightmaps().world_surface.get(0, 0), 96);
+    }
+
+    #[test]
+    fn test_compression() {
+        let desert = registry::biome_id("minecraft:desert").unwrap();
+
+        // Gzip, uncompressed without heightmaps, LZ4, and zlib in an external file
+        for (x, z) in [(1, 0), (0, 1), (1, 1), (3, 0)] {
+            let chunk = load(x, z).unwrap().unwrap();
+            assert_eq!(chunk.pos(), ChunkPos::new(x, z));
+            assert_eq!(chunk.sections()[0].block_count(), 4096);
+            assert_eq!(chunk.get_biome(&Position::new(0, -64, 0)), Some(desert));
+            assert_eq!(chunk.heightmaps().motion_blocking.get(7, 7), 16);
+        }
+
+        assert!(matches!(load(4, 0), Err(RegionError::UnknownCompression(9))));
+    }
+
+    #[test]
+    fn test_missing_chunks() {
+        // Never saved, still generating, and in a region without a file
+        assert!(load(5, 5).unwrap().is_none());
+        assert!(load(2, 0).unwrap().is_none());
+        assert!(load(100, 0).unwrap().is_none());
+
+        let chunk = load(-1, -1).unwrap().unwrap();
+        assert_eq!(chunk.pos(), ChunkPos::new(-1, -1));
+
+        let region = RegionFile::open(&RegionFile::path_for(FIXTURES.as_ref(), ChunkPos::new(-1, -1))).unwrap();
+        assert!(region.has_chunk(ChunkPos::new(-1, -1)));
+        assert!(!region.has_chunk(ChunkPos::new(-2, -1)));
+        assert_eq!(region.timestamp(ChunkPos::new(-1, -1)), 1_700_001_023);
+    }
+
+    #[test]
+    fn test_save_chunks() {
+        let directory = temp_directory("storage_save");
+
+        let loaded = load(0, 0).unwrap().unwrap();
+        let mut generated = Chunk::new(ChunkPos::new(-33, 40), registry::biome_id("minecraft:desert").unwrap());
+        generated.set_block(&Position::new(-520, 100, 650), 131);
+        generated.set_biome(&Position::new(-520, 100, 650), PLAINS);
+
+        for compression in [Compression::Gzip, Compression::Zlib, Compression::None, Compression::Lz4] {
+            let mut storage = AnvilStorage::new(&directory, compression);
+            storage.save_chunk(&loaded).unwrap();
+            storage.save_chunk(&generated).unwrap();
+
+            let mut storage = AnvilStorage::new(&directory, compression);
+            assert_eq!(storage.load_chunk(loaded.pos()).unwrap().as_ref(), Some(&loaded));
+
+            let reloaded = storage.load_chunk(generated.pos()).unwrap().unwrap();
+            assert_eq!(reloaded.get_block(&Position::new(-520, 100, 650)), Some(131));
+            assert_eq!(reloaded.get_biome(&Position::new(-520, 100, 650)), Some(PLAINS));
+            assert_eq!(reloaded.get_biome(&Position::new(-520, 104, 650)), generated.get_biome(&Position::new(-520, 104, 650)));
+            assert_eq!(reloaded.heightmaps(), generated.heightmaps());
+        }
+
+        assert!(directory.join("r.0.0.mca").exists());
+        assert!(directory.join("r.-2.1.mca").exists());
+    }
+
+    #[test]
+    fn test_save_unknown_entries() {
+        let directory = temp_directory("storage_unknown");
+        let mut storage = AnvilStorage::new(&directory, Compression::Zlib);
+        let mut chunk = load(0, 0).unwrap().unwrap();
+
+        // The unknown block at 1, 16, 0 is index 1 of section 5, and comes back from saving as it was
+        let unknown = |chunk: &Chunk| chunk.sections()[5].unknown().and_then(|unknown| unknown.block_states.get(&1).cloned());
+        let original = unknown(&chunk).unwrap();
+        storage.save_chunk(&chunk).unwrap();
+        let reloaded = storage.load_chunk(chunk.pos()).unwrap().unwrap();
+        assert_eq!(unknown(&reloaded), Some(original));
+        assert_eq!(reloaded.get_block(&Position::new(1, 16, 0)), Some(FALLBACK_BLOCK_STATE));
+
+        // Until it's replaced
+        chunk.set_block(&Position::new(1, 16, 0), 131);
+        storage.save_chunk(&chunk).unwrap();
+        let reloaded = storage.load_chunk(chunk.pos()).unwrap().unwrap();
+        assert_eq!(unknown(&reloaded), None);
+        assert_eq!(reloaded.get_block(&Position::new(1, 16, 0)), Some(131));
+    }
+
+    #[test]
+    fn test_save_extra_tags() {
+        let mut blob = chunk_nbt::encode(&load(0, 0).unwrap().unwrap());
+        let structures = nbt::Value::Compound(HashMap::from([("References".to_owned(), nbt::Value::Compound(HashMap::new()))]));
+        blob.insert("InhabitedTime", nbt::Value::Long(1234)).unwrap();
+        blob.insert("structures", structures.clone()).unwrap();
+        blob.insert("isLightOn", nbt::Value::Byte(1)).unwrap();
+
+        let mut chunk = chunk_nbt::decode(&blob).unwrap();
+        chunk.set_block(&Position::new(1, -60, 0), 131);
+        let saved = chunk_nbt::encode(&chunk);
+
+        // What the server doesn't use survives changes, light is left for vanilla to redo
+        assert_eq!(saved.get("InhabitedTime"), Some(&nbt::Value::Long(1234)));
+        assert_eq!(saved.get("structures"), Some(&structures));
+        assert_eq!(saved.get("isLightOn"), None);
+        assert_eq!(chunk_nbt::decode(&saved).unwrap().get_block(&Position::new(1, -60, 0)), Some(131));
+    }
+
+    #[test]
+    fn test_save_unknown_block_entities() {
+        let directory = temp_directory("storage_unknown_block_entities");
+        let mut storage = AnvilStorage::new(&directory, Compression::Zlib);
+        let mut chunk = load(0, 0).unwrap().unwrap();
+
+        // The fixture's block at 0, -60, 0 has a block entity of a type the server doesn't know
+        let position = Position::new(0, -60, 0);
+        let [(unknown_position, data)] = chunk.unknown_block_entities() else { panic!("expected one unknown block entity") };
+        assert_eq!(*unknown_position, position);
+        let nbt::Value::Compound(compound) = data else { panic!("block entity data isn't a compound") };
+        assert_eq!(compound.get("id"), Some(&nbt::Value::String("minecraft:not_a_block_entity".to_owned())));
+
+        let original = chunk.unknown_block_entities().to_vec();
+        storage.save_chunk(&chunk).unwrap();
+        let reloaded = storage.load_chunk(chunk.pos()).unwrap().unwrap();
+        assert_eq!(reloaded.unknown_block_entities(), original);
+        assert_eq!(reloaded.block_entities().len(), 1);
+
+        // Until its block changes
+        chunk.set_block(&position, AIR);
+        storage.save_chunk(&chunk).unwrap();
+        assert!(storage.load_chunk(chunk.pos()).unwrap().unwrap().unknown_block_entities().is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,96 @@
+use crate::utils::errors::PacketReadError;
+
+/// Fixed-width values packed into longs, lowest bits first. Values never span two longs, so the top bits of each
+/// long can go unused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitStorage {
+    bits: u8,
+    size: usize,
+    data: Vec<u64>,
+}
+
+impl BitStorage {
+    /// `bits` has to be between 1 and 32.
+    pub fn new(bits: u8, size: usize) -> Self {
+        Self { bits, size, data: vec![0; Self::longs_needed(bits, size)] }
+    }
+
+    /// Wraps longs read from the wire or from disk, which have to be exactly as many as needed.
+    pub fn from_data(bits: u8, size: usize, data: Vec<u64>) -> Result<Self, PacketReadError> {
+        if !(1..=32).contains(&bits) || data.len() != Self::longs_needed(bits, size) {
+            return Err(PacketReadError::InvalidLength(data.len() as i32));
+        }
+
+        Ok(Self { bits, size, data })
+    }
+
+    pub fn longs_needed(bits: u8, size: usize) -> usize {
+        size.div_ceil(Self::values_per_long(bits))
+    }
+
+    fn values_per_long(bits: u8) -> usize {
+        64 / bits as usize
+    }
+
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let per_long = Self::values_per_long(self.bits);
+        (index / per_long, (index % per_long) * self.bits as usize)
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        let (long, shift) = self.locate(index);
+        ((self.data[long] >> shift) & self.mask()) as u32
+    }
+
+    pub fn set(&mut self, index: usize, value: u32) {
+        let (long, shift) = self.locate(index);
+        let mask = self.mask();
+        self.data[long] = (self.data[long] & !(mask << shift)) | ((value as u64 & mask) << shift);
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing() {
+        // 5 bits fit 12 times into a long, leaving the top 4 bits unused
+        let mut storage = BitStorage::new(5, 24);
+        assert_eq!(storage.data().len(), 2);
+
+        for i in 0..24 {
+            storage.set(i, i as u32);
+        }
+        storage.set(3, 31);
+
+        assert_eq!(storage.get(3), 31);
+        assert_eq!(storage.get(11), 11);
+        assert_eq!(storage.get(12), 12);
+        assert_eq!(storage.data()[1] & 0x1F, 12);
+        assert_eq!(storage.data()[0] >> 60, 0);
+    }
+
+    #[test]
+    fn test_from_data() {
+        assert!(BitStorage::from_data(4, 4096, vec![0; 256]).is_ok());
+        assert!(BitStorage::from_data(4, 4096, vec![0; 255]).is_err());
+        assert!(BitStorage::from_data(0, 4096, Vec::new()).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,11 @@
+use crate::custom_types::position::Position;
+
+/// Extra data of a block like a chest or sign.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity {
+    pub position: Position,
+    /// ID in the block entity type registry.
+    pub kind: i32,
+    /// The entity's NBT compound, without the position and ID.
+    pub data: nbt::Value,
+}
\ No newline at end of file
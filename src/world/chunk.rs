@@ -0,0 +1,202 @@
+use std::{collections::HashMap, fmt};
+
+use bytes::BufMut;
+
+use crate::custom_types::position::Position;
+
+use super::{block_entity::BlockEntity, chunk_section::{ChunkSection, SECTION_SIZE}, heightmap::Heightmaps};
+
+/// The lowest block Y coordinate of the overworld.
+pub const MIN_Y: i64 = -64;
+/// The overworld's height, 24 sections.
+pub const WORLD_HEIGHT: i64 = 384;
+pub const SECTION_COUNT: usize = WORLD_HEIGHT as usize / SECTION_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk the block at `position` is in.
+    pub fn of(position: &Position) -> Self {
+        Self { x: (position.x() >> 4) as i32, z: (position.z() >> 4) as i32 }
+    }
+}
+
+impl fmt::Display for ChunkPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.x, self.z)
+    }
+}
+
+/// A 16 block wide column of sections from `MIN_Y` up to the build limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pos: ChunkPos,
+    sections: Vec<ChunkSection>,
+    block_entities: Vec<BlockEntity>,
+    /// Block entities of types the server doesn't know, as they were loaded. They aren't sent, but get saved
+    /// unchanged until the block they belong to changes.
+    unknown_block_entities: Vec<(Position, nbt::Value)>,
+    heightmaps: Heightmaps,
+    /// Tags of the saved chunk the server doesn't use, like ticks and structures, which are saved back unchanged.
+    extra_tags: HashMap<String, nbt::Value>,
+}
+
+impl Chunk {
+    /// A chunk full of air in a single biome.
+    pub fn new(pos: ChunkPos, biome: u32) -> Self {
+        Self { pos, sections: vec![ChunkSection::new(biome); SECTION_COUNT], block_entities: Vec::new(), unknown_block_entities: Vec::new(), heightmaps: Heightmaps::new(), extra_tags: HashMap::new() }
+    }
+
+    /// A chunk made of `SECTION_COUNT` sections from the bottom up.
+    pub fn from_sections(pos: ChunkPos, sections: Vec<ChunkSection>) -> Self {
+        let heightmaps = Heightmaps::compute(&sections);
+        Self { pos, sections, block_entities: Vec::new(), unknown_block_entities: Vec::new(), heightmaps, extra_tags: HashMap::new() }
+    }
+
+    pub fn pos(&self) -> ChunkPos {
+        self.pos
+    }
+
+    pub fn sections(&self) -> &[ChunkSection] {
+        &self.sections
+    }
+
+    /// Sections from the bottom up. Call `recompute_heightmaps` after changing blocks through them.
+    pub fn sections_mut(&mut self) -> &mut [ChunkSection] {
+        &mut self.sections
+    }
+
+    /// The section and coordinates within it, or `None` above or below the world.
+    /// Only the block's offset within its chunk matters, not which chunk it's in.
+    fn locate(position: &Position) -> Option<(usize, usize, usize, usize)> {
+        let y = position.y() - MIN_Y;
+        if !(0..WORLD_HEIGHT).contains(&y) { return None; }
+
+        let (x, z) = (position.x().rem_euclid(16) as usize, position.z().rem_euclid(16) as usize);
+        Some((y as usize / SECTION_SIZE, x, y as usize % SECTION_SIZE, z))
+    }
+
+    /// `None` above or below the world.
+    pub fn get_block(&self, position: &Position) -> Option<u32> {
+        Self::locate(position).map(|(section, x, y, z)| self.sections[section].get_block(x, y, z))
+    }
+
+    /// Returns the block state that was there before, or `None` if `position` is above or below the world.
+    pub fn set_block(&mut self, position: &Position, state: u32) -> Option<u32> {
+        let (section, x, y, z) = Self::locate(position)?;
+        let old = self.sections[section].set_block(x, y, z, state);
+        self.heightmaps.update(&self.sections, x, section * SECTION_SIZE + y, z);
+        if old != state {
+            self.unknown_block_entities.retain(|(unknown, _)| unknown != position);
+        }
+        Some(old)
+    }
+
+    pub fn get_biome(&self, position: &Position) -> Option<u32> {
+        Self::locate(position).map(|(section, x, y, z)| self.sections[section].get_biome(x, y, z))
+    }
+
+    pub fn set_biome(&mut self, position: &Position, biome: u32) -> Option<u32> {
+        Self::locate(position).map(|(section, x, y, z)| self.sections[section].set_biome(x, y, z, biome))
+    }
+
+    pub fn heightmaps(&self) -> &Heightmaps {
+        &self.heightmaps
+    }
+
+    pub fn set_heightmaps(&mut self, heightmaps: Heightmaps) {
+        self.heightmaps = heightmaps;
+    }
+
+    pub fn recompute_heightmaps(&mut self) {
+        self.heightmaps = Heightmaps::compute(&self.sections);
+    }
+
+    pub fn block_entities(&self) -> &[BlockEntity] {
+        &self.block_entities
+    }
+
+    /// Replaces the block entity at the same position, if there is one.
+    pub fn set_block_entity(&mut self, block_entity: BlockEntity) {
+        self.remove_block_entity(&block_entity.position);
+        self.unknown_block_entities.retain(|(unknown, _)| *unknown != block_entity.position);
+        self.block_entities.push(block_entity);
+    }
+
+    pub fn unknown_block_entities(&self) -> &[(Position, nbt::Value)] {
+        &self.unknown_block_entities
+    }
+
+    /// Keeps the saved compound of a block entity the server doesn't know, replacing the one at the same position.
+    pub fn add_unknown_block_entity(&mut self, position: Position, data: nbt::Value) {
+        self.unknown_block_entities.retain(|(unknown, _)| *unknown != position);
+        self.unknown_block_entities.push((position, data));
+    }
+
+    pub fn remove_block_entity(&mut self, position: &Position) -> Option<BlockEntity> {
+        let index = self.block_entities.iter().position(|block_entity| block_entity.position == *position)?;
+        Some(self.block_entities.remove(index))
+    }
+
+    pub fn extra_tags(&self) -> &HashMap<String, nbt::Value> {
+        &self.extra_tags
+    }
+
+    pub fn set_extra_tags(&mut self, tags: HashMap<String, nbt::Value>) {
+        self.extra_tags = tags;
+    }
+
+    /// Writes every section, which is the data field of the Chunk Data packet.
+    pub fn write_sections(&self, buf: &mut dyn BufMut) {
+        for section in &self.sections {
+            section.write(buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_pos() {
+        assert_eq!(ChunkPos::of(&Position::new(15, 0, 16)), ChunkPos::new(0, 1));
+        assert_eq!(ChunkPos::of(&Position::new(-1, 0, -16)), ChunkPos::new(-1, -1));
+        assert_eq!(ChunkPos::of(&Position::new(-17, 0, 0)), ChunkPos::new(-2, 0));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let mut chunk = Chunk::new(ChunkPos::new(-1, 0), 0);
+
+        assert_eq!(chunk.set_block(&Position::new(-1, -64, 5), 1), Some(0));
+        assert_eq!(chunk.set_block(&Position::new(-16, 319, 0), 2), Some(0));
+        assert_eq!(chunk.set_block(&Position::new(-1, 320, 0), 2), None);
+        assert_eq!(chunk.set_block(&Position::new(-1, -65, 0), 2), None);
+
+        assert_eq!(chunk.get_block(&Position::new(-1, -64, 5)), Some(1));
+        assert_eq!(chunk.get_block(&Position::new(-16, 319, 0)), Some(2));
+        assert_eq!(chunk.sections()[0].get_block(15, 0, 5), 1);
+        assert_eq!(chunk.sections()[23].get_block(0, 15, 0), 2);
+        assert_eq!(chunk.sections()[23].block_count(), 1);
+    }
+
+    #[test]
+    fn test_write_sections() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), 0);
+        chunk.set_biome(&Position::new(0, 0, 0), 3);
+
+        let mut buf = Vec::new();
+        chunk.write_sections(&mut buf);
+        // 23 empty sections of 8 bytes, and one with a 2 entry biome palette and a long of biome data
+        assert_eq!(buf.len(), 23 * 8 + 2 + 3 + 5 + 8);
+    }
+}
\ No newline at end of file
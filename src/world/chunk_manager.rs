@@ -0,0 +1,552 @@
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use crate::{custom_types::position::Position, tick::scheduler::Scheduler, utils::{errors::RegionError, metrics::Metrics}, METRICS};
+
+use super::{
+    anvil::storage::AnvilStorage,
+    chunk::{Chunk, ChunkPos},
+    generation::generator::ChunkGenerator,
+    light::{LightArray, LightData, LightKind, LIGHT_SECTION_COUNT},
+    lighting::{self, LightWorld},
+};
+
+/// Why a chunk is kept loaded. Chunks without tickets are unloaded after they went unused for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TicketKind {
+    /// In a player's view.
+    Player,
+    /// Around the world spawn.
+    Spawn,
+    /// Kept loaded with `/forceload`.
+    Forced,
+}
+
+struct LoadedChunk {
+    chunk: Chunk,
+    light: LightData,
+    /// The light version each section last changed at.
+    light_versions: [u64; LIGHT_SECTION_COUNT],
+    /// Changed since it was last saved.
+    dirty: bool,
+    /// When it lost its last ticket, `None` while it has any.
+    unused_since: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Chunks {
+    loaded: HashMap<ChunkPos, LoadedChunk>,
+    /// Ticket counts by `TicketKind`, for chunks with at least one ticket, loaded or not.
+    tickets: HashMap<ChunkPos, [u32; 3]>,
+    /// Loads that were asked for through `add_ticket` and haven't finished.
+    loading: HashSet<ChunkPos>,
+    /// Goes up every time light changes anywhere.
+    light_version: u64,
+}
+
+impl Chunks {
+    /// Adds a chunk lit by itself, and lets light through to the chunks around it.
+    fn insert(&mut self, pos: ChunkPos, chunk: Chunk, light: LightData, dirty: bool) {
+        let unused_since = (!self.tickets.contains_key(&pos)).then(Instant::now);
+        self.light_version += 1;
+        let light_versions = [self.light_version; LIGHT_SECTION_COUNT];
+        self.loaded.insert(pos, LoadedChunk { chunk, light, light_versions, dirty, unused_since });
+        self.update_light(|world| lighting::spread_across_borders(world, pos));
+        Metrics::set_gauge(&METRICS.loaded_chunks, self.loaded.len() as f64);
+    }
+
+    /// Runs the light engine on the loaded chunks, then moves the sections whose light changed to a new version.
+    fn update_light(&mut self, update: impl FnOnce(&mut LoadedLight)) {
+        let mut world = LoadedLight { loaded: &mut self.loaded, changed: HashMap::new() };
+        update(&mut world);
+        let changed = world.changed;
+        if changed.is_empty() { return; }
+
+        self.light_version += 1;
+        for (pos, sections) in changed {
+            let Some(loaded) = self.loaded.get_mut(&pos) else { continue; };
+            for (section, version) in loaded.light_versions.iter_mut().enumerate() {
+                if sections & (1 << section) != 0 {
+                    *version = self.light_version;
+                }
+            }
+        }
+    }
+}
+
+/// The loaded chunks as the light engine sees them, noting the light sections it changes.
+struct LoadedLight<'a> {
+    loaded: &'a mut HashMap<ChunkPos, LoadedChunk>,
+    /// Bit masks of changed sections, counted like in `LightData`.
+    changed: HashMap<ChunkPos, u32>,
+}
+
+impl LightWorld for LoadedLight<'_> {
+    fn chunk(&self, pos: ChunkPos) -> Option<(&Chunk, &LightData)> {
+        self.loaded.get(&pos).map(|loaded| (&loaded.chunk, &loaded.light))
+    }
+
+    fn section_light_mut(&mut self, pos: ChunkPos, kind: LightKind, section: usize) -> Option<&mut LightArray> {
+        let loaded = self.loaded.get_mut(&pos)?;
+        *self.changed.entry(pos).or_default() |= 1 << section;
+        Some(loaded.light.sections_mut(kind)[section].get_or_insert_with(|| LightArray::filled(0)))
+    }
+}
+
+/// What a save did. Chunks that failed stay marked as changed, so the next save tries them again.
+pub struct SaveSummary {
+    pub saved: usize,
+    pub failed: Vec<(ChunkPos, RegionError)>,
+}
+
+/// What unloading unused chunks did. Chunks that failed to save stay loaded.
+pub struct UnloadSummary {
+    pub unloaded: usize,
+    pub failed: Vec<(ChunkPos, RegionError)>,
+}
+
+/// The chunks in memory, shared by everyone who needs them, the storage they're loaded from and saved to,
+/// and what generates the missing ones. Chunks are kept loaded by tickets.
+pub struct ChunkManager {
+    chunks: Mutex<Chunks>,
+    storage: Mutex<AnvilStorage>,
+    generator: Arc<dyn ChunkGenerator>,
+    /// Held for a whole save, so an older copy of a chunk never gets written after a newer one.
+    saving: Mutex<()>,
+}
+
+impl ChunkManager {
+    pub fn new(storage: AnvilStorage, generator: Arc<dyn ChunkGenerator>) -> Self {
+        Self { chunks: Mutex::new(Chunks::default()), storage: Mutex::new(storage), generator, saving: Mutex::new(()) }
+    }
+
+    /// Loads the chunk from storage, or generates it if it was never saved, unless it's loaded already.
+    pub fn load(&self, pos: ChunkPos) -> Result<(), RegionError> {
+        if self.is_loaded(pos) {
+            self.chunks.lock().unwrap().loading.remove(&pos);
+            return Ok(());
+        }
+
+        let stored = self.storage.lock().unwrap().load_chunk(pos);
+        // Lighting takes a while, so it's done without holding either lock
+        let stored = stored.map(|chunk| chunk.map(|chunk| {
+            let light = lighting::light_chunk(&chunk);
+            (chunk, light)
+        }));
+        let mut chunks = self.chunks.lock().unwrap();
+        chunks.loading.remove(&pos);
+
+        // Someone else may have loaded or changed it in the meantime, which wins
+        if chunks.loaded.contains_key(&pos) { return Ok(()); }
+
+        match stored? {
+            Some((chunk, light)) => {
+                chunks.insert(pos, chunk, light, false);
+                Metrics::increment(&METRICS.chunks_loaded);
+            },
+            None => {
+                drop(chunks);
+                let chunk = self.generator.generate(pos);
+                let light = lighting::light_chunk(&chunk);
+
+                let mut chunks = self.chunks.lock().unwrap();
+                if chunks.loaded.contains_key(&pos) { return Ok(()); }
+                // Generated chunks get saved, so they don't change along with the generator settings
+                chunks.insert(pos, chunk, light, true);
+                Metrics::increment(&METRICS.chunks_generated);
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Loads or generates the chunk on a worker thread, then runs `then` on the tick thread.
+    pub fn load_async<C: 'static>(self: &Arc<Self>, pos: ChunkPos, scheduler: &Scheduler<C>, then: impl FnOnce(&C, Result<(), RegionError>) + Send + 'static) {
+        let chunks = Arc::clone(self);
+        scheduler.run_async(move || chunks.load(pos), then);
+    }
+
+    /// Keeps the chunk loaded until the ticket is removed. Returns `true` if the caller should load it,
+    /// which is only the case for the first ticket to reach a chunk that isn't loaded or being loaded.
+    pub fn add_ticket(&self, pos: ChunkPos, kind: TicketKind) -> bool {
+        let mut chunks = self.chunks.lock().unwrap();
+        chunks.tickets.entry(pos).or_default()[kind as usize] += 1;
+
+        if let Some(loaded) = chunks.loaded.get_mut(&pos) {
+            loaded.unused_since = None;
+            return false;
+        }
+
+        chunks.loading.insert(pos)
+    }
+
+    /// Whether the caller should load the chunk again after it failed to load, which is only the case
+    /// if it still has tickets and isn't loaded or being loaded.
+    pub fn retry_load(&self, pos: ChunkPos) -> bool {
+        let mut chunks = self.chunks.lock().unwrap();
+        if !chunks.tickets.contains_key(&pos) || chunks.loaded.contains_key(&pos) { return false; }
+
+        chunks.loading.insert(pos)
+    }
+
+    /// Removes one ticket of `kind`. The chunk gets unloaded later if that was its last one.
+    pub fn remove_ticket(&self, pos: ChunkPos, kind: TicketKind) {
+        let mut chunks = self.chunks.lock().unwrap();
+        let Some(tickets) = chunks.tickets.get_mut(&pos) else { return; };
+        tickets[kind as usize] = tickets[kind as usize].saturating_sub(1);
+        if tickets.iter().any(|count| *count > 0) { return; }
+
+        chunks.tickets.remove(&pos);
+        if let Some(loaded) = chunks.loaded.get_mut(&pos) {
+            loaded.unused_since = Some(Instant::now());
+        }
+    }
+
+    pub fn ticket_count(&self, pos: ChunkPos, kind: TicketKind) -> u32 {
+        self.chunks.lock().unwrap().tickets.get(&pos).map_or(0, |tickets| tickets[kind as usize])
+    }
+
+    /// Chunks with at least one ticket of `kind`.
+    pub fn ticketed(&self, kind: TicketKind) -> Vec<ChunkPos> {
+        let chunks = self.chunks.lock().unwrap();
+        let mut ticketed: Vec<ChunkPos> = chunks.tickets.iter().filter(|(_, tickets)| tickets[kind as usize] > 0).map(|(pos, _)| *pos).collect();
+        ticketed.sort_by_key(|pos| (pos.x, pos.z));
+        ticketed
+    }
+
+    /// Adds a chunk that isn't saved yet, like a newly generated one.
+    pub fn insert(&self, chunk: Chunk) {
+        let light = lighting::light_chunk(&chunk);
+        self.chunks.lock().unwrap().insert(chunk.pos(), chunk, light, true);
+    }
+
+    pub fn is_loaded(&self, pos: ChunkPos) -> bool {
+        self.chunks.lock().unwrap().loaded.contains_key(&pos)
+    }
+
+    pub fn loaded_count(&self) -> usize {
+        self.chunks.lock().unwrap().loaded.len()
+    }
+
+    /// Runs `f` on the chunk if it's loaded.
+    pub fn with_chunk<T>(&self, pos: ChunkPos, f: impl FnOnce(&Chunk) -> T) -> Option<T> {
+        self.chunks.lock().unwrap().loaded.get(&pos).map(|loaded| f(&loaded.chunk))
+    }
+
+    /// Runs `f` on the chunk if it's loaded, and marks it to be saved. Light isn't updated, `set_block` does that.
+    pub fn with_chunk_mut<T>(&self, pos: ChunkPos, f: impl FnOnce(&mut Chunk) -> T) -> Option<T> {
+        self.chunks.lock().unwrap().loaded.get_mut(&pos).map(|loaded| {
+            loaded.dirty = true;
+            f(&mut loaded.chunk)
+        })
+    }
+
+    /// Runs `f` on the chunk and its light if it's loaded. Also returns the light version, for `light_changes`.
+    pub fn with_light<T>(&self, pos: ChunkPos, f: impl FnOnce(&Chunk, &LightData) -> T) -> Option<(T, u64)> {
+        let chunks = self.chunks.lock().unwrap();
+        chunks.loaded.get(&pos).map(|loaded| (f(&loaded.chunk, &loaded.light), chunks.light_version))
+    }
+
+    /// Goes up every time light changes anywhere.
+    pub fn light_version(&self) -> u64 {
+        self.chunks.lock().unwrap().light_version
+    }
+
+    /// The light of the chunk's sections that changed after `version`, and the version it's at now.
+    /// `None` if nothing changed or the chunk isn't loaded.
+    pub fn light_changes(&self, pos: ChunkPos, version: u64) -> Option<(LightData, u64)> {
+        let chunks = self.chunks.lock().unwrap();
+        let loaded = chunks.loaded.get(&pos)?;
+
+        let mut light = LightData::new();
+        for section in (0..LIGHT_SECTION_COUNT).filter(|section| loaded.light_versions[*section] > version) {
+            light.sky_light[section] = loaded.light.sky_light[section].clone();
+            light.block_light[section] = loaded.light.block_light[section].clone();
+        }
+
+        (light != LightData::new()).then_some((light, chunks.light_version))
+    }
+
+    /// Sets a block and updates the light around it, also in the chunks next to it. Returns the block that was there
+    /// before, or `None` if its chunk isn't loaded or it's above or below the world.
+    pub fn set_block(&self, position: &Position, state: u32) -> Option<u32> {
+        let mut chunks = self.chunks.lock().unwrap();
+        let loaded = chunks.loaded.get_mut(&ChunkPos::of(position))?;
+        let old = loaded.chunk.set_block(position, state)?;
+        loaded.dirty = true;
+
+        if old != state {
+            chunks.update_light(|world| lighting::update_block(world, position.x(), position.y(), position.z()));
+        }
+        Some(old)
+    }
+
+    /// Saves every chunk that changed since it was last saved. Chunks are copied first, so they can keep changing
+    /// while the save runs.
+    pub fn save_all(&self) -> SaveSummary {
+        let _saving = self.saving.lock().unwrap();
+        let changed: Vec<Chunk> = self.chunks.lock().unwrap().loaded.values_mut()
+            .filter(|loaded| loaded.dirty)
+            .map(|loaded| {
+                loaded.dirty = false;
+                loaded.chunk.clone()
+            })
+            .collect();
+
+        let (saved, failed) = self.save(changed);
+        SaveSummary { saved, failed }
+    }
+
+    /// Saves and drops the chunks that have been without tickets for `timeout`. Chunks that get a ticket or change
+    /// while they're being saved stay loaded.
+    pub fn unload_idle(&self, timeout: Duration) -> UnloadSummary {
+        let _saving = self.saving.lock().unwrap();
+        let now = Instant::now();
+
+        let mut chunks = self.chunks.lock().unwrap();
+        let idle: Vec<ChunkPos> = chunks.loaded.iter()
+            .filter(|(_, loaded)| loaded.unused_since.is_some_and(|since| now.duration_since(since) >= timeout))
+            .map(|(pos, _)| *pos)
+            .collect();
+        let mut changed = Vec::new();
+        for pos in &idle {
+            if let Some(loaded) = chunks.loaded.get_mut(pos).filter(|loaded| loaded.dirty) {
+                loaded.dirty = false;
+                changed.push(loaded.chunk.clone());
+            }
+        }
+        drop(chunks);
+
+        let (_, failed) = self.save(changed);
+
+        let mut chunks = self.chunks.lock().unwrap();
+        let mut unloaded = 0;
+        for pos in idle {
+            let still_idle = chunks.loaded.get(&pos).is_some_and(|loaded| loaded.unused_since.is_some() && !loaded.dirty);
+            if still_idle {
+                chunks.loaded.remove(&pos);
+                unloaded += 1;
+            }
+        }
+
+        Metrics::add(&METRICS.chunks_unloaded, unloaded as u64);
+        Metrics::set_gauge(&METRICS.loaded_chunks, chunks.loaded.len() as f64);
+        UnloadSummary { unloaded, failed }
+    }
+
+    /// Writes the chunks to storage. Chunks that fail are marked as changed again.
+    fn save(&self, changed: Vec<Chunk>) -> (usize, Vec<(ChunkPos, RegionError)>) {
+        let mut saved = 0;
+        let mut failed = Vec::new();
+        for chunk in changed {
+            match self.storage.lock().unwrap().save_chunk(&chunk) {
+                Ok(()) => saved += 1,
+                Err(e) => failed.push((chunk.pos(), e)),
+            }
+        }
+
+        let mut chunks = self.chunks.lock().unwrap();
+        for (pos, _) in &failed {
+            if let Some(loaded) = chunks.loaded.get_mut(pos) {
+                loaded.dirty = true;
+            }
+        }
+
+        (saved, failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, thread, time::{Duration, Instant}};
+
+    use crate::{custom_types::position::Position, world::{anvil::region::Compression, chunk_section::AIR, generation::flat::FlatGenerator}};
+
+    use crate::utils::test_utils::temp_directory;
+
+    use super::*;
+
+    fn manager(directory: &PathBuf) -> ChunkManager {
+        let generator = FlatGenerator::parse("minecraft:bedrock,minecraft:dirt", "minecraft:plains").unwrap();
+        ChunkManager::new(AnvilStorage::new(directory, Compression::Zlib), Arc::new(generator))
+    }
+
+    #[test]
+    fn test_save_changed_chunks() {
+        let directory = temp_directory("chunks_save");
+        let chunks = manager(&directory);
+        let pos = ChunkPos::new(3, -7);
+
+        chunks.insert(Chunk::new(pos, 0));
+        chunks.insert(Chunk::new(ChunkPos::new(4, -7), 0));
+        assert_eq!(chunks.save_all().saved, 2);
+        assert_eq!(chunks.save_all().saved, 0);
+
+        chunks.with_chunk_mut(pos, |chunk| chunk.set_block(&Position::new(48, 64, -112), 1));
+        assert_eq!(chunks.save_all().saved, 1);
+
+        let reloaded = manager(&directory);
+        reloaded.load(pos).unwrap();
+        assert_eq!(reloaded.loaded_count(), 1);
+        assert_eq!(reloaded.with_chunk(pos, |chunk| chunk.get_block(&Position::new(48, 64, -112))), Some(Some(1)));
+        // Saved chunks aren't generated again
+        assert_eq!(reloaded.with_chunk(pos, |chunk| chunk.get_block(&Position::new(48, -64, -112))), Some(Some(AIR)));
+        assert_eq!(reloaded.save_all().saved, 0);
+    }
+
+    #[test]
+    fn test_generate_missing_chunks() {
+        let directory = temp_directory("chunks_generate");
+        let chunks = manager(&directory);
+        let pos = ChunkPos::new(-1, 1);
+
+        assert_eq!(chunks.with_chunk(pos, |_| ()), None);
+        chunks.load(pos).unwrap();
+        assert_eq!(chunks.with_chunk(pos, |chunk| chunk.get_block(&Position::new(-1, -63, 16))), Some(Some(10)));
+        assert_eq!(chunks.save_all().saved, 1);
+    }
+
+    #[test]
+    fn test_load_async() {
+        let directory = temp_directory("chunks_async");
+        let chunks = Arc::new(manager(&directory));
+        let scheduler: Scheduler<Mutex<Vec<ChunkPos>>> = Scheduler::new(2);
+        let loaded = Mutex::new(Vec::new());
+
+        for pos in [ChunkPos::new(0, 0), ChunkPos::new(5, -5)] {
+            chunks.load_async(pos, &scheduler, move |loaded, result| {
+                result.unwrap();
+                loaded.lock().unwrap().push(pos);
+            });
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while loaded.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            scheduler.tick(&loaded);
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(loaded.lock().unwrap().len(), 2);
+        assert!(chunks.is_loaded(ChunkPos::new(5, -5)));
+    }
+
+    #[test]
+    fn test_tickets() {
+        let directory = temp_directory("chunks_tickets");
+        let chunks = manager(&directory);
+        let pos = ChunkPos::new(2, 2);
+
+        // Only the first ticket starts a load
+        assert!(chunks.add_ticket(pos, TicketKind::Player));
+        assert!(!chunks.add_ticket(pos, TicketKind::Player));
+        assert!(!chunks.add_ticket(pos, TicketKind::Forced));
+        assert_eq!(chunks.ticket_count(pos, TicketKind::Player), 2);
+        assert_eq!(chunks.ticketed(TicketKind::Forced), vec![pos]);
+        assert!(chunks.ticketed(TicketKind::Spawn).is_empty());
+
+        chunks.load(pos).unwrap();
+        chunks.remove_ticket(pos, TicketKind::Player);
+        chunks.remove_ticket(pos, TicketKind::Forced);
+        assert_eq!(chunks.unload_idle(Duration::ZERO).unloaded, 0);
+
+        chunks.remove_ticket(pos, TicketKind::Player);
+        chunks.remove_ticket(pos, TicketKind::Player);
+        assert_eq!(chunks.ticket_count(pos, TicketKind::Player), 0);
+        assert_eq!(chunks.unload_idle(Duration::from_secs(60)).unloaded, 0);
+
+        // A new ticket keeps the chunk, and reloading it doesn't start another load
+        assert!(!chunks.add_ticket(pos, TicketKind::Spawn));
+        assert_eq!(chunks.unload_idle(Duration::ZERO).unloaded, 0);
+        assert!(chunks.is_loaded(pos));
+    }
+
+    #[test]
+    fn test_retry_failed_loads() {
+        let directory = temp_directory("chunks_retry");
+        fs::write(directory.join("r.0.0.mca"), b"not a region file").unwrap();
+        let chunks = manager(&directory);
+        let pos = ChunkPos::new(1, 1);
+
+        assert!(chunks.add_ticket(pos, TicketKind::Player));
+        assert!(!chunks.retry_load(pos));
+        assert!(chunks.load(pos).is_err());
+
+        // Only one retry at a time, and none once the ticket is gone
+        assert!(chunks.retry_load(pos));
+        assert!(!chunks.retry_load(pos));
+        assert!(chunks.load(pos).is_err());
+        chunks.remove_ticket(pos, TicketKind::Player);
+        assert!(!chunks.retry_load(pos));
+    }
+
+    #[test]
+    fn test_unload_saves_first() {
+        let directory = temp_directory("chunks_unload");
+        let chunks = manager(&directory);
+        let pos = ChunkPos::new(-4, 9);
+        let block = Position::new(-60, 10, 150);
+
+        chunks.add_ticket(pos, TicketKind::Player);
+        chunks.load(pos).unwrap();
+        chunks.with_chunk_mut(pos, |chunk| chunk.set_block(&block, 15));
+        chunks.remove_ticket(pos, TicketKind::Player);
+
+        let summary = chunks.unload_idle(Duration::ZERO);
+        assert_eq!((summary.unloaded, summary.failed.len()), (1, 0));
+        assert!(!chunks.is_loaded(pos));
+        // Nothing left to save
+        assert_eq!(chunks.save_all().saved, 0);
+
+        chunks.load(pos).unwrap();
+        assert_eq!(chunks.with_chunk(pos, |chunk| chunk.get_block(&block)), Some(Some(15)));
+    }
+
+    #[test]
+    fn test_light() {
+        let directory = temp_directory("chunks_light");
+        let chunks = manager(&directory);
+        let sky = |pos: ChunkPos, section: usize, x: usize, y: usize, z: usize| {
+            chunks.with_light(pos, |_, light| light.sky_light[section].as_ref().unwrap().get(x, y, z)).unwrap().0
+        };
+
+        // The dirt is on top at y -63, in section 1 counting the one below the world
+        chunks.load(ChunkPos::new(0, 0)).unwrap();
+        assert_eq!(sky(ChunkPos::new(0, 0), 1, 15, 2, 0), 15);
+        assert_eq!(sky(ChunkPos::new(0, 0), 1, 15, 1, 0), 0);
+        let version = chunks.light_version();
+
+        // Digging next to the border lets sky light into the chunk next to it once that's loaded
+        chunks.set_block(&Position::new(15, -63, 0), AIR);
+        assert_eq!(sky(ChunkPos::new(0, 0), 1, 15, 1, 0), 15);
+        chunks.load(ChunkPos::new(1, 0)).unwrap();
+        chunks.set_block(&Position::new(16, -63, 0), AIR);
+        chunks.set_block(&Position::new(16, -62, 0), 1);
+        assert_eq!(sky(ChunkPos::new(1, 0), 1, 0, 1, 0), 14);
+
+        let (changes, now) = chunks.light_changes(ChunkPos::new(0, 0), version).unwrap();
+        assert_eq!(now, chunks.light_version());
+        assert!(changes.sky_light[1].is_some());
+        assert!(changes.sky_light[2].is_none());
+        assert!(chunks.light_changes(ChunkPos::new(0, 0), now).is_none());
+        assert!(chunks.light_changes(ChunkPos::new(5, 5), version).is_none());
+    }
+
+    #[test]
+    fn test_failed_saves_are_retried() {
+        // A file where the world directory should be makes every save fail
+        let directory = temp_directory("chunks_failed").join("world");
+        fs::write(&directory, b"").unwrap();
+        let chunks = manager(&directory);
+
+        chunks.insert(Chunk::new(ChunkPos::new(0, 0), 0));
+        let summary = chunks.save_all();
+        assert_eq!((summary.saved, summary.failed.len()), (0, 1));
+
+        // Chunks that can't be saved aren't thrown away
+        let summary = chunks.unload_idle(Duration::ZERO);
+        assert_eq!((summary.unloaded, summary.failed.len()), (0, 1));
+        assert!(chunks.is_loaded(ChunkPos::new(0, 0)));
+
+        fs::remove_file(&directory).unwrap();
+        assert_eq!(chunks.save_all().saved, 1);
+    }
+}
\ No newline at end of file
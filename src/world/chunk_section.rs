@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use bytes::{Buf, BufMut};
+use nbt::Value;
+
+use crate::utils::errors::PacketReadError;
+
+use super::{paletted_container::{PalettedContainer, BIOMES, BLOCK_STATES}, registry::blocks};
+
+pub const SECTION_SIZE: usize = 16;
+/// Biomes are stored per 4x4x4 blocks.
+pub const BIOME_SIZE: usize = 4;
+/// The block state ID of air.
+pub const AIR: u32 = blocks::AIR;
+
+/// A 16x16x16 part of a chunk. Coordinates are relative to the section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSection {
+    /// Blocks that aren't air, which the client wants to know up front.
+    block_count: u16,
+    block_states: PalettedContainer,
+    biomes: PalettedContainer,
+    unknown: Option<Box<UnknownEntries>>,
+}
+
+/// Saved palette entries the server doesn't know, by index in their container. They're played as a fallback but
+/// written back on save, until the block or biome there changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnknownEntries {
+    pub block_states: HashMap<usize, Value>,
+    pub biomes: HashMap<usize, Value>,
+}
+
+fn block_index(x: usize, y: usize, z: usize) -> usize {
+    (y * SECTION_SIZE + z) * SECTION_SIZE + x
+}
+
+fn biome_index(x: usize, y: usize, z: usize) -> usize {
+    (y * BIOME_SIZE + z) * BIOME_SIZE + x
+}
+
+impl ChunkSection {
+    /// A section full of air in a single biome.
+    pub fn new(biome: u32) -> Self {
+        Self {
+            block_count: 0,
+            block_states: PalettedContainer::new(BLOCK_STATES, AIR),
+            biomes: PalettedContainer::new(BIOMES, biome),
+            unknown: None,
+        }
+    }
+
+    pub fn from_containers(block_states: PalettedContainer, biomes: PalettedContainer) -> Self {
+        let block_count = block_states.count(|state| state != AIR) as u16;
+        Self { block_count, block_states, biomes, unknown: None }
+    }
+
+    pub fn unknown(&self) -> Option<&UnknownEntries> {
+        self.unknown.as_deref()
+    }
+
+    pub fn set_unknown(&mut self, unknown: UnknownEntries) {
+        let empty = unknown.block_states.is_empty() && unknown.biomes.is_empty();
+        self.unknown = if empty { None } else { Some(Box::new(unknown)) };
+    }
+
+    pub fn block_count(&self) -> u16 {
+        self.block_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.block_count == 0
+    }
+
+    pub fn block_states(&self) -> &PalettedContainer {
+        &self.block_states
+    }
+
+    pub fn biomes(&self) -> &PalettedContainer {
+        &self.biomes
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> u32 {
+        self.block_states.get(block_index(x, y, z))
+    }
+
+    /// Returns the block state that was there before.
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: u32) -> u32 {
+        let index = block_index(x, y, z);
+        let old = self.block_states.set(index, state);
+        if let Some(unknown) = &mut self.unknown {
+            unknown.block_states.remove(&index);
+        }
+        match (old == AIR, state == AIR) {
+            (true, false) => self.block_count += 1,
+            (false, true) => self.block_count -= 1,
+            _ => {},
+        }
+
+        old
+    }
+
+    /// Sets every block to `state`.
+    pub fn fill(&mut self, state: u32) {
+        self.block_states.fill(state);
+        if let Some(unknown) = &mut self.unknown {
+            unknown.block_states.clear();
+        }
+        self.block_count = if state == AIR { 0 } else { BLOCK_STATES.size as u16 };
+    }
+
+    /// Takes block coordinates within the section, not biome coordinates.
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> u32 {
+        self.biomes.get(biome_index(x / BIOME_SIZE, y / BIOME_SIZE, z / BIOME_SIZE))
+    }
+
+    /// Takes block coordinates within the section, and sets the biome of the 4x4x4 cell they're in.
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: u32) -> u32 {
+        let index = biome_index(x / BIOME_SIZE, y / BIOME_SIZE, z / BIOME_SIZE);
+        if let Some(unknown) = &mut self.unknown {
+            unknown.biomes.remove(&index);
+        }
+        self.biomes.set(index, biome)
+    }
+
+    /// Writes the section like it's sent in the Chunk Data packet.
+    pub fn write(&self, buf: &mut dyn BufMut) {
+        buf.put_u16(self.block_count);
+        self.block_states.write(buf);
+        self.biomes.write(buf);
+    }
+
+    pub fn read(buf: &mut dyn Buf) -> Result<Self, PacketReadError> {
+        if buf.remaining() < 2 { return Err(PacketReadError::BufferUnderflow); }
+        let block_count = buf.get_u16();
+        let block_states = PalettedContainer::read(BLOCK_STATES, buf)?;
+        let biomes = PalettedContainer::read(BIOMES, buf)?;
+
+        Ok(Self { block_count, block_states, biomes, unknown: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: u32 = 1;
+    const DIRT: u32 = 10;
+
+    fn bytes(section: &ChunkSection) -> Vec<u8> {
+        let mut buf = Vec::new();
+        section.write(&mut buf);
+        buf
+    }
+
+    fn read(bytes: &[u8]) -> ChunkSection {
+        let mut data = bytes;
+        let section = ChunkSection::read(&mut data).unwrap();
+        assert!(data.is_empty());
+        section
+    }
+
+    #[test]
+    fn test_empty_section() {
+        let section = ChunkSection::new(0);
+        // No blocks, then a single value of air and a single biome, both without data
+        let expected = [0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(bytes(&section), expected);
+        assert_eq!(read(&expected), section);
+    }
+
+    #[test]
+    fn test_indirect_section() {
+        let mut section = ChunkSection::new(1);
+        section.set_block(0, 0, 0, STONE);
+        section.set_block(1, 0, 0, DIRT);
+        section.set_block(15, 15, 15, STONE);
+        assert_eq!(section.block_count(), 3);
+
+        // 4 bits per block with palette [air, stone, dirt], 16 blocks per long
+        let mut expected = vec![0, 3, 4, 3, 0, 1, 10, 0x80, 0x02];
+        let mut longs = vec![0u64; 256];
+        longs[0] = 0x21;
+        longs[255] = 1 << 60;
+        for long in longs {
+            expected.extend_from_slice(&long.to_be_bytes());
+        }
+        expected.extend_from_slice(&[0, 1, 0]);
+
+        assert_eq!(bytes(&section), expected);
+        let read = read(&expected);
+        assert_eq!(read, section);
+        assert_eq!(read.get_block(1, 0, 0), DIRT);
+        assert_eq!(read.get_block(15, 15, 15), STONE);
+        assert_eq!(read.get_block(15, 15, 14), AIR);
+    }
+
+    #[test]
+    fn test_block_count() {
+        let mut section = ChunkSection::new(0);
+        section.fill(STONE);
+        assert_eq!(section.block_count(), 4096);
+
+        assert_eq!(section.set_block(3, 4, 5, AIR), STONE);
+        assert_eq!(section.set_block(3, 4, 5, AIR), AIR);
+        assert_eq!(section.block_count(), 4095);
+
+        let rebuilt = ChunkSection::from_containers(section.block_states().clone(), section.biomes().clone());
+        assert_eq!(rebuilt, section);
+    }
+
+    #[test]
+    fn test_biomes() {
+        let mut section = ChunkSection::new(0);
+        section.set_biome(5, 9, 2, 7);
+
+        assert_eq!(section.get_biome(4, 8, 0), 7);
+        assert_eq!(section.get_biome(7, 11, 3), 7);
+        assert_eq!(section.get_biome(8, 8, 0), 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{
+    custom_types::identifier::Identifier,
+    utils::errors::GeneratorError,
+    world::{
+        chunk::{Chunk, ChunkPos, SECTION_COUNT, WORLD_HEIGHT},
+        chunk_section::{ChunkSection, AIR, SECTION_SIZE},
+        registry,
+    },
+};
+
+use super::generator::ChunkGenerator;
+
+/// The same layers of blocks everywhere, starting at the bottom of the world, in a single biome.
+/// There are no structures or features.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatGenerator {
+    /// Block states from the bottom up.
+    layers: Vec<u32>,
+    biome: u32,
+}
+
+/// Names without a namespace are in `minecraft`.
+fn full_name(name: &str) -> Result<String, String> {
+    let identifier = match name.contains(':') {
+        true => Identifier::from_string(name)?,
+        false => Identifier::new(None, name)?,
+    };
+
+    Ok(identifier.to_string())
+}
+
+impl FlatGenerator {
+    pub fn new(layers: Vec<u32>, biome: u32) -> Self {
+        Self { layers, biome }
+    }
+
+    /// Reads layers written like vanilla's superflat presets, bottom up with an optional count:
+    /// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block`.
+    pub fn parse(layers: &str, biome: &str) -> Result<Self, GeneratorError> {
+        let mut states = Vec::new();
+
+        for layer in layers.split(',').map(str::trim).filter(|layer| !layer.is_empty()) {
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => (count.trim().parse::<usize>().map_err(|_| GeneratorError::InvalidLayer(layer.to_owned()))?, name.trim()),
+                None => (1, layer),
+            };
+
+            let name = full_name(name).map_err(|_| GeneratorError::InvalidLayer(layer.to_owned()))?;
+            let state = registry::block_state_id(&name, &HashMap::new()).ok_or(GeneratorError::UnknownBlock(name))?;
+            // Checked first, so a huge count doesn't get allocated
+            let height = states.len().saturating_add(count);
+            if height > WORLD_HEIGHT as usize { return Err(GeneratorError::TooManyLayers(height)); }
+
+            states.extend(std::iter::repeat_n(state, count));
+        }
+
+        let biome_name = full_name(biome).map_err(|_| GeneratorError::UnknownBiome(biome.to_owned()))?;
+        let biome = registry::biome_id(&biome_name).ok_or(GeneratorError::UnknownBiome(biome_name))?;
+
+        Ok(Self::new(states, biome))
+    }
+
+    fn layer(&self, y: usize) -> u32 {
+        self.layers.get(y).copied().unwrap_or(AIR)
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, pos: ChunkPos) -> Chunk {
+        let sections = (0..SECTION_COUNT)
+            .map(|index| {
+                let mut section = ChunkSection::new(self.biome);
+                let bottom = index * SECTION_SIZE;
+
+                // Most sections are a single layer or air
+                if (bottom..bottom + SECTION_SIZE).all(|y| self.layer(y) == self.layer(bottom)) {
+                    section.fill(self.layer(bottom));
+                    return section;
+                }
+
+                for y in 0..SECTION_SIZE {
+                    for z in 0..SECTION_SIZE {
+                        for x in 0..SECTION_SIZE {
+                            section.set_block(x, y, z, self.layer(bottom + y));
+                        }
+                    }
+                }
+                section
+            })
+            .collect();
+
+        Chunk::from_sections(pos, sections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{custom_types::position::Position, world::registry::PLAINS};
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let generator = FlatGenerator::parse("minecraft:bedrock, 2*dirt,minecraft:grass_block", "plains").unwrap();
+        assert_eq!(generator, FlatGenerator::new(vec![79, 10, 10, 9], PLAINS));
+
+        assert!(matches!(FlatGenerator::parse("minecraft:bedrock,x*minecraft:dirt", "plains"), Err(GeneratorError::InvalidLayer(_))));
+        assert!(matches!(FlatGenerator::parse("minecraft:bedrock,Dirt", "plains"), Err(GeneratorError::InvalidLayer(_))));
+        assert!(matches!(FlatGenerator::parse("minecraft:no_such_block", "plains"), Err(GeneratorError::UnknownBlock(_))));
+        assert!(matches!(FlatGenerator::parse("385*minecraft:stone", "plains"), Err(GeneratorError::TooManyLayers(385))));
+        assert!(matches!(FlatGenerator::parse("stone,99999999999999*minecraft:stone", "plains"), Err(GeneratorError::TooManyLayers(100000000000000))));
+        assert!(matches!(FlatGenerator::parse("minecraft:stone", "minecraft:nowhere"), Err(GeneratorError::UnknownBiome(_))));
+        assert_eq!(FlatGenerator::parse("", "plains").unwrap(), FlatGenerator::new(vec![], PLAINS));
+    }
+
+    #[test]
+    fn test_generate() {
+        let desert = registry::biome_id("minecraft:desert").unwrap();
+        let generator = FlatGenerator::parse("minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block,20*minecraft:stone", "minecraft:desert").unwrap();
+        let chunk = generator.generate(ChunkPos::new(-3, 7));
+
+        assert_eq!(chunk.pos(), ChunkPos::new(-3, 7));
+        assert_eq!(chunk.get_block(&Position::new(-48, -64, 112)), Some(79));
+        assert_eq!(chunk.get_block(&Position::new(-40, -62, 115)), Some(10));
+        assert_eq!(chunk.get_block(&Position::new(-33, -61, 127)), Some(9));
+        assert_eq!(chunk.get_block(&Position::new(-33, -41, 127)), Some(1));
+        assert_eq!(chunk.get_block(&Position::new(-33, -40, 127)), Some(AIR));
+        assert_eq!(chunk.get_biome(&Position::new(-33, 300, 127)), Some(desert));
+
+        assert_eq!(chunk.sections()[0].block_count(), 4096);
+        assert_eq!(chunk.sections()[1].block_count(), 16 * 16 * 8);
+        assert!(chunk.sections()[2].is_empty());
+        assert_eq!(chunk.heightmaps().world_surface.get(4, 9), 24);
+    }
+}
\ No newline at end of file
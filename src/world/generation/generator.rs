@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{utils::{config::WorldConfig, errors::GeneratorError}, world::chunk::{Chunk, ChunkPos}};
+
+use super::{flat::FlatGenerator, noise::NoiseGenerator};
+
+/// What the client gets instead of the seed, like vanilla: the first 8 bytes of the SHA-256 hash of the seed,
+/// both little endian.
+pub fn hashed_seed(seed: i64) -> i64 {
+    let hash = Sha256::digest(seed.to_le_bytes());
+    i64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+/// Fills chunks that were never saved. Generators run on worker threads.
+pub trait ChunkGenerator: Send + Sync {
+    fn generate(&self, pos: ChunkPos) -> Chunk;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorKind {
+    Flat,
+    Noise,
+}
+
+/// The generator `[world]` asks for.
+pub fn from_config(config: &WorldConfig) -> Result<Arc<dyn ChunkGenerator>, GeneratorError> {
+    match config.generator {
+        GeneratorKind::Flat => Ok(Arc::new(FlatGenerator::parse(&config.flat.layers, &config.flat.biome)?)),
+        GeneratorKind::Noise => Ok(Arc::new(NoiseGenerator::new(config.seed))),
+    }
+}
\ No newline at end of file
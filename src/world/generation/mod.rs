@@ -0,0 +1,5 @@
+pub mod flat;
+pub mod generator;
+pub mod noise;
+pub mod perlin;
+pub mod random;
\ No newline at end of file
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::world::{
+    chunk::{Chunk, ChunkPos, MIN_Y, SECTION_COUNT, WORLD_HEIGHT},
+    chunk_section::{ChunkSection, AIR, BIOME_SIZE, SECTION_SIZE},
+    registry::{self, blocks::{BEDROCK, COAL_ORE, DIRT, GOLD_ORE, GRASS_BLOCK, GRAVEL, IRON_ORE, LAVA, OAK_LOG, SAND, STONE, WATER}, FALLBACK_BLOCK_STATE, PLAINS},
+};
+
+use super::{generator::ChunkGenerator, perlin::OctaveNoise, random::{self, WorldRandom}};
+
+pub const SEA_LEVEL: i64 = 62;
+
+/// Caves below this are flooded with lava, like in vanilla.
+const LAVA_LEVEL: i64 = MIN_Y + 10;
+/// Caves stay this far under the surface, so they never open up under water or trees.
+const CAVE_ROOF: i64 = 5;
+
+const ORE_SALT: u64 = 1;
+const TREE_SALT: u64 = 2;
+
+/// Ore block, veins per chunk, lowest and highest y, and blocks per vein.
+const ORES: [(u32, usize, i64, i64, usize); 3] = [
+    (COAL_ORE, 20, 0, 128, 12),
+    (IRON_ORE, 12, -24, 64, 8),
+    (GOLD_ORE, 4, -64, 32, 6),
+];
+
+/// Places a tree may grow per chunk, each taken with the biome's tree chance.
+const TREE_ATTEMPTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Biome {
+    Ocean,
+    Beach,
+    Desert,
+    Plains,
+    Forest,
+}
+
+impl Biome {
+    fn name(self) -> &'static str {
+        match self {
+            Biome::Ocean => "minecraft:ocean",
+            Biome::Beach => "minecraft:beach",
+            Biome::Desert => "minecraft:desert",
+            Biome::Plains => "minecraft:plains",
+            Biome::Forest => "minecraft:forest",
+        }
+    }
+
+    /// The top block and the few blocks under it.
+    fn surface(self) -> (u32, u32) {
+        match self {
+            Biome::Ocean => (GRAVEL, DIRT),
+            Biome::Beach | Biome::Desert => (SAND, SAND),
+            Biome::Plains | Biome::Forest => (GRASS_BLOCK, DIRT),
+        }
+    }
+
+    fn tree_chance(self) -> f64 {
+        match self {
+            Biome::Forest => 0.6,
+            Biome::Plains => 0.03,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    /// The y of the top solid block.
+    height: i64,
+    biome: Biome,
+}
+
+/// Hills, oceans and a few biomes from layered Perlin noise, with caves, ores and oak trees.
+/// Everything follows from the seed and the chunk position, so a chunk comes out the same whenever and
+/// on whichever thread it's generated.
+pub struct NoiseGenerator {
+    seed: i64,
+    continents: OctaveNoise,
+    detail: OctaveNoise,
+    temperature: OctaveNoise,
+    humidity: OctaveNoise,
+    /// Tunnels are where both cave noises are close to 0.
+    caves: [OctaveNoise; 2],
+    /// Persistent oak leaves, because nothing updates leaf distances yet.
+    leaves: u32,
+}
+
+/// Sets a block by its world y within the chunk's sections, ignoring blocks outside the world.
+fn set_block(sections: &mut [ChunkSection], x: usize, y: i64, z: usize, state: u32) {
+    if !(MIN_Y..MIN_Y + WORLD_HEIGHT).contains(&y) { return; }
+
+    let y = (y - MIN_Y) as usize;
+    sections[y / SECTION_SIZE].set_block(x, y % SECTION_SIZE, z, state);
+}
+
+fn get_block(sections: &[ChunkSection], x: usize, y: i64, z: usize) -> u32 {
+    if !(MIN_Y..MIN_Y + WORLD_HEIGHT).contains(&y) { return AIR; }
+
+    let y = (y - MIN_Y) as usize;
+    sections[y / SECTION_SIZE].get_block(x, y % SECTION_SIZE, z)
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: i64) -> Self {
+        let mut random = WorldRandom::new(seed);
+        Self {
+            seed,
+            continents: OctaveNoise::new(&mut random, 4),
+            detail: OctaveNoise::new(&mut random, 4),
+            temperature: OctaveNoise::new(&mut random, 2),
+            humidity: OctaveNoise::new(&mut random, 2),
+            caves: [OctaveNoise::new(&mut random, 2), OctaveNoise::new(&mut random, 2)],
+            leaves: registry::block_state_id("minecraft:oak_leaves", &HashMap::from([
+                ("distance".to_owned(), "1".to_owned()),
+                ("persistent".to_owned(), "true".to_owned()),
+            ])).unwrap_or(FALLBACK_BLOCK_STATE),
+        }
+    }
+
+    fn column(&self, x: i64, z: i64) -> Column {
+        let (x, z) = (x as f64, z as f64);
+        let continent = self.continents.sample_2d(x / 512.0, z / 512.0);
+        let detail = self.detail.sample_2d(x / 64.0, z / 64.0);
+        let height = SEA_LEVEL + (continent * 48.0 + detail * 16.0 + 10.0).floor() as i64;
+
+        let biome = if height < SEA_LEVEL - 1 {
+            Biome::Ocean
+        } else if height <= SEA_LEVEL + 1 {
+            Biome::Beach
+        } else {
+            let temperature = self.temperature.sample_2d(x / 256.0, z / 256.0);
+            let humidity = self.humidity.sample_2d(x / 256.0, z / 256.0);
+            match (temperature, humidity) {
+                (t, h) if t > 0.1 && h < 0.0 => Biome::Desert,
+                (_, h) if h > 0.1 => Biome::Forest,
+                _ => Biome::Plains,
+            }
+        };
+
+        Column { height, biome }
+    }
+
+    fn is_cave(&self, x: i64, y: i64, z: i64) -> bool {
+        let (x, y, z) = (x as f64 / 48.0, y as f64 / 24.0, z as f64 / 48.0);
+        self.caves.iter().all(|noise| noise.sample(x, y, z).abs() < 0.06)
+    }
+
+    fn block(&self, x: i64, y: i64, z: i64, column: Column) -> u32 {
+        if y == MIN_Y || (y < MIN_Y + 5 && random::hash(self.seed, x, y, z) % 5 >= (y - MIN_Y) as u64) {
+            return BEDROCK;
+        }
+        if y > column.height {
+            return if y <= SEA_LEVEL { WATER } else { AIR };
+        }
+        if y < column.height - CAVE_ROOF && self.is_cave(x, y, z) {
+            return if y < LAVA_LEVEL { LAVA } else { AIR };
+        }
+
+        let (top, filler) = column.biome.surface();
+        match column.height - y {
+            // Grass doesn't grow under water
+            0 if top == GRASS_BLOCK && y < SEA_LEVEL => DIRT,
+            0 => top,
+            1..=3 => filler,
+            _ => STONE,
+        }
+    }
+
+    fn place_ores(&self, sections: &mut [ChunkSection], pos: ChunkPos) {
+        let mut random = WorldRandom::for_chunk(self.seed, pos, ORE_SALT);
+
+        for (ore, veins, min_y, max_y, size) in ORES {
+            for _ in 0..veins {
+                let (mut x, mut y, mut z) = (random.next_range(0..16), random.next_range(min_y..max_y), random.next_range(0..16));
+                for _ in 0..size {
+                    if get_block(sections, x as usize, y, z as usize) == STONE {
+                        set_block(sections, x as usize, y, z as usize, ore);
+                    }
+                    // Veins wander, but stay in the chunk so they don't depend on its neighbours
+                    x = (x + random.next_range(-1..2)).clamp(0, 15);
+                    y += random.next_range(-1..2);
+                    z = (z + random.next_range(-1..2)).clamp(0, 15);
+                }
+            }
+        }
+    }
+
+    /// Trees are placed from the neighbouring chunks too, so the ones growing across a border get all their leaves.
+    fn place_trees(&self, sections: &mut [ChunkSection], pos: ChunkPos) {
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let origin = ChunkPos::new(pos.x + dx, pos.z + dz);
+                let mut random = WorldRandom::for_chunk(self.seed, origin, TREE_SALT);
+
+                for _ in 0..TREE_ATTEMPTS {
+                    let x = origin.x as i64 * 16 + random.next_range(0..16);
+                    let z = origin.z as i64 * 16 + random.next_range(0..16);
+                    let (roll, trunk) = (random.next_f64(), random.next_range(4..7));
+
+                    let column = self.column(x, z);
+                    if roll < column.biome.tree_chance() && column.height > SEA_LEVEL {
+                        self.place_tree(sections, pos, x, column.height, z, trunk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The parts of a tree growing on the block at `ground` that are inside the chunk at `pos`.
+    /// Leaves only go into air and trunks only replace air and leaves, so overlapping trees come out the same in any order.
+    fn place_tree(&self, sections: &mut [ChunkSection], pos: ChunkPos, x: i64, ground: i64, z: i64, trunk: i64) {
+        let in_chunk = |x: i64, z: i64| {
+            let (local_x, local_z) = (x - pos.x as i64 * 16, z - pos.z as i64 * 16);
+            ((0..16).contains(&local_x) && (0..16).contains(&local_z)).then_some((local_x as usize, local_z as usize))
+        };
+        let top = ground + trunk;
+
+        for y in top - 2..=top + 1 {
+            let radius = if y < top { 2 } else { 1 };
+            for leaf_z in z - radius..=z + radius {
+                for leaf_x in x - radius..=x + radius {
+                    let corner = (leaf_x - x).abs() == radius && (leaf_z - z).abs() == radius;
+                    if corner && (y == top + 1 || random::hash(self.seed, leaf_x, y, leaf_z).is_multiple_of(2)) { continue; }
+
+                    if let Some((local_x, local_z)) = in_chunk(leaf_x, leaf_z) {
+                        if get_block(sections, local_x, y, local_z) == AIR {
+                            set_block(sections, local_x, y, local_z, self.leaves);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some((local_x, local_z)) = in_chunk(x, z) {
+            for y in ground + 1..=top {
+                let block = get_block(sections, local_x, y, local_z);
+                if block == AIR || block == self.leaves {
+                    set_block(sections, local_x, y, local_z, OAK_LOG);
+                }
+            }
+            if get_block(sections, local_x, ground, local_z) == GRASS_BLOCK {
+                set_block(sections, local_x, ground, local_z, DIRT);
+            }
+        }
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, pos: ChunkPos) -> Chunk {
+        let (base_x, base_z) = (pos.x as i64 * 16, pos.z as i64 * 16);
+        let mut sections = vec![ChunkSection::new(PLAINS); SECTION_COUNT];
+        let mut columns = Vec::with_capacity(16 * 16);
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let column = self.column(base_x + x as i64, base_z + z as i64);
+                for y in MIN_Y..=column.height.max(SEA_LEVEL) {
+                    let block = self.block(base_x + x as i64, y, base_z + z as i64, column);
+                    if block != AIR {
+                        set_block(&mut sections, x, y, z, block);
+                    }
+                }
+                columns.push(column);
+            }
+        }
+
+        self.place_ores(&mut sections, pos);
+        self.place_trees(&mut sections, pos);
+
+        // Each 4x4 biome cell takes the biome of its corner column, from the bottom of the world to the top
+        for z in (0..16).step_by(BIOME_SIZE) {
+            for x in (0..16).step_by(BIOME_SIZE) {
+                let biome = registry::biome_id(columns[z * 16 + x].biome.name()).unwrap_or(PLAINS);
+                for section in sections.iter_mut() {
+                    for y in (0..SECTION_SIZE).step_by(BIOME_SIZE) {
+                        section.set_biome(x, y, z, biome);
+                    }
+                }
+            }
+        }
+
+        Chunk::from_sections(pos, sections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::custom_types::position::Position;
+
+    use super::*;
+
+    /// FNV-1a of the chunk as it's sent to clients.
+    fn fingerprint(chunk: &Chunk) -> u64 {
+        let mut data = Vec::new();
+        chunk.write_sections(&mut data);
+        data.iter().fold(0xCBF29CE484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001B3))
+    }
+
+    #[test]
+    fn test_same_seed_same_chunks() {
+        let positions = [ChunkPos::new(0, 0), ChunkPos::new(-3, 5), ChunkPos::new(40, -12)];
+        let first = NoiseGenerator::new(12345);
+        let second = NoiseGenerator::new(12345);
+        let other = NoiseGenerator::new(54321);
+
+        for pos in positions {
+            let chunk = first.generate(pos);
+            assert_eq!(fingerprint(&chunk), fingerprint(&second.generate(pos)));
+            assert_ne!(fingerprint(&chunk), fingerprint(&other.generate(pos)));
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        // Changing these means every existing seed now makes different worlds
+        let generator = NoiseGenerator::new(12345);
+        assert_eq!(fingerprint(&generator.generate(ChunkPos::new(0, 0))), 7451921119695041908);
+        assert_eq!(fingerprint(&generator.generate(ChunkPos::new(-3, 5))), 267409086358238318);
+    }
+
+    #[test]
+    fn test_generate_in_parallel() {
+        let generator = Arc::new(NoiseGenerator::new(99));
+        let positions: Vec<ChunkPos> = (-2..2).flat_map(|x| (-2..2).map(move |z| ChunkPos::new(x, z))).collect();
+        let expected: Vec<u64> = positions.iter().map(|pos| fingerprint(&generator.generate(*pos))).collect();
+
+        let handles: Vec<_> = positions.iter().rev()
+            .map(|pos| {
+                let (generator, pos) = (Arc::clone(&generator), *pos);
+                thread::spawn(move || fingerprint(&generator.generate(pos)))
+            })
+            .collect();
+        let mut fingerprints: Vec<u64> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        fingerprints.reverse();
+        assert_eq!(fingerprints, expected);
+    }
+
+    #[test]
+    fn test_terrain() {
+        let generator = NoiseGenerator::new(12345);
+        let chunk = generator.generate(ChunkPos::new(2, 2));
+
+        for (x, z) in [(32, 32), (40, 45), (47, 47)] {
+            assert_eq!(chunk.get_block(&Position::new(x, MIN_Y, z)), Some(BEDROCK));
+            // Water fills everything up to sea level
+            assert!((MIN_Y..=SEA_LEVEL).all(|y| chunk.get_block(&Position::new(x, y, z)) != Some(AIR) || generator.is_cave(x, y, z)));
+
+            let column = generator.column(x, z);
+            let surface = chunk.get_block(&Position::new(x, column.height, z)).unwrap();
+            assert!(matches!(surface, GRASS_BLOCK | DIRT | SAND | GRAVEL | OAK_LOG), "surface {}", surface);
+        }
+
+        assert!(chunk.block_entities().is_empty());
+        assert!(chunk.heightmaps().world_surface.get(0, 0) > 0);
+    }
+}
\ No newline at end of file
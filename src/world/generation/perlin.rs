@@ -0,0 +1,128 @@
+use super::random::WorldRandom;
+
+/// Ken Perlin's improved noise, with the permutation shuffled and the origin moved by the seed.
+pub struct PerlinNoise {
+    permutation: [u8; 512],
+    offset: [f64; 3],
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The dot product of the offset with one of 12 gradient directions picked by `hash`.
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl PerlinNoise {
+    pub fn new(random: &mut WorldRandom) -> Self {
+        let offset = [random.next_f64() * 256.0, random.next_f64() * 256.0, random.next_f64() * 256.0];
+
+        let mut permutation = [0u8; 512];
+        for (index, value) in permutation.iter_mut().take(256).enumerate() {
+            *value = index as u8;
+        }
+        for index in (1..256).rev() {
+            permutation.swap(index, random.next_range(0..index as i64 + 1) as usize);
+        }
+        permutation.copy_within(0..256, 256);
+
+        Self { permutation, offset }
+    }
+
+    /// Between about -1 and 1, and 0 on every lattice point.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset[0], y + self.offset[1], z + self.offset[2]);
+        let (floor_x, floor_y, floor_z) = (x.floor(), y.floor(), z.floor());
+        let (cell_x, cell_y, cell_z) = ((floor_x as i64 & 255) as usize, (floor_y as i64 & 255) as usize, (floor_z as i64 & 255) as usize);
+        let (x, y, z) = (x - floor_x, y - floor_y, z - floor_z);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[cell_x] as usize + cell_y;
+        let (aa, ab) = (p[a] as usize + cell_z, p[a + 1] as usize + cell_z);
+        let b = p[cell_x + 1] as usize + cell_y;
+        let (ba, bb) = (p[b] as usize + cell_z, p[b + 1] as usize + cell_z);
+
+        lerp(w,
+            lerp(v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z))),
+            lerp(v,
+                lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))))
+    }
+}
+
+/// Several layers of Perlin noise, each at twice the frequency and half the amplitude of the one before.
+pub struct OctaveNoise {
+    octaves: Vec<PerlinNoise>,
+}
+
+impl OctaveNoise {
+    pub fn new(random: &mut WorldRandom, octaves: usize) -> Self {
+        Self { octaves: (0..octaves).map(|_| PerlinNoise::new(random)).collect() }
+    }
+
+    /// Scaled back to between about -1 and 1.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (mut total, mut max, mut frequency, mut amplitude) = (0.0, 0.0, 1.0, 1.0);
+        for octave in &self.octaves {
+            total += octave.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+            max += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+
+        total / max
+    }
+
+    pub fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, 0.0, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perlin() {
+        let noise = PerlinNoise::new(&mut WorldRandom::new(42));
+        let other = PerlinNoise::new(&mut WorldRandom::new(42));
+
+        let mut min_max = (f64::MAX, f64::MIN);
+        for i in 0..2000 {
+            let (x, y, z) = (i as f64 * 0.37, i as f64 * 0.11, i as f64 * -0.23);
+            let value = noise.sample(x, y, z);
+            assert_eq!(value, other.sample(x, y, z));
+            min_max = (min_max.0.min(value), min_max.1.max(value));
+        }
+        assert!(min_max.0 > -1.1 && min_max.0 < -0.3);
+        assert!(min_max.1 < 1.1 && min_max.1 > 0.3);
+
+        // Smooth: close points have close values
+        assert!((noise.sample(10.0, 5.0, 3.0) - noise.sample(10.01, 5.0, 3.0)).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_octaves() {
+        let noise = OctaveNoise::new(&mut WorldRandom::new(7), 4);
+        let different = OctaveNoise::new(&mut WorldRandom::new(8), 4);
+        assert_ne!(noise.sample_2d(0.5, 0.5), different.sample_2d(0.5, 0.5));
+        assert!((0..1000).all(|i| noise.sample(i as f64 * 0.13, 0.7, i as f64 * 0.29).abs() <= 1.0));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,72 @@
+use std::ops::Range;
+
+use crate::world::chunk::ChunkPos;
+
+/// Mixes a value into a well distributed one, the output function of SplitMix64.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+    value ^ (value >> 31)
+}
+
+/// SplitMix64, which gives the same numbers on every platform and version, unlike the generators of `rand`.
+pub struct WorldRandom {
+    state: u64,
+}
+
+impl WorldRandom {
+    pub fn new(seed: i64) -> Self {
+        Self { state: seed as u64 }
+    }
+
+    /// Numbers for one purpose in one chunk, the same whatever order chunks get generated in.
+    pub fn for_chunk(seed: i64, pos: ChunkPos, salt: u64) -> Self {
+        Self::new(hash(seed, pos.x as i64, salt as i64, pos.z as i64) as i64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        mix(self.state)
+    }
+
+    /// Between 0 and 1, excluding 1.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn next_range(&mut self, range: Range<i64>) -> i64 {
+        range.start + (self.next_u64() % (range.end - range.start) as u64) as i64
+    }
+}
+
+/// A number for a single block, for decisions that don't need a whole generator.
+pub fn hash(seed: i64, x: i64, y: i64, z: i64) -> u64 {
+    let mut hash = mix(seed as u64);
+    for coordinate in [x, y, z] {
+        hash = mix(hash ^ coordinate as u64);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let mut first = WorldRandom::new(1234);
+        let mut second = WorldRandom::new(1234);
+        let numbers: Vec<u64> = (0..4).map(|_| first.next_u64()).collect();
+        assert_eq!(numbers, (0..4).map(|_| second.next_u64()).collect::<Vec<_>>());
+        // Pinned so that a change to the algorithm, which would change every world, gets noticed
+        assert_eq!(WorldRandom::new(1234).next_u64(), 13478418381427711195);
+
+        let mut random = WorldRandom::for_chunk(1, ChunkPos::new(-5, 7), 3);
+        for _ in 0..1000 {
+            assert!((10..20).contains(&random.next_range(10..20)));
+            assert!((0.0..1.0).contains(&random.next_f64()));
+        }
+        assert_ne!(WorldRandom::for_chunk(1, ChunkPos::new(7, -5), 3).next_u64(), WorldRandom::for_chunk(1, ChunkPos::new(-5, 7), 3).next_u64());
+        assert_ne!(hash(0, 1, 2, 3), hash(0, 3, 2, 1));
+    }
+}
\ No newline at end of file
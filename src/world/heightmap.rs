@@ -0,0 +1,135 @@
+use crate::utils::errors::PacketReadError;
+
+use super::{bit_storage::BitStorage, chunk::WORLD_HEIGHT, chunk_section::{ChunkSection, AIR, SECTION_SIZE}};
+
+/// Enough bits for every height from 0 to `WORLD_HEIGHT`.
+pub const HEIGHTMAP_BITS: u8 = 9;
+
+/// For each column of a chunk, one above the highest block that counts, relative to the bottom of the world.
+/// 0 means the column has no such block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    storage: BitStorage,
+}
+
+impl Heightmap {
+    pub fn new() -> Self {
+        Self { storage: BitStorage::new(HEIGHTMAP_BITS, SECTION_SIZE * SECTION_SIZE) }
+    }
+
+    /// Reads the packed heights as they're stored and sent.
+    pub fn from_data(data: Vec<u64>) -> Result<Self, PacketReadError> {
+        Ok(Self { storage: BitStorage::from_data(HEIGHTMAP_BITS, SECTION_SIZE * SECTION_SIZE, data)? })
+    }
+
+    /// Finds the highest block in each column that `counts` holds for.
+    pub fn compute(sections: &[ChunkSection], counts: impl Fn(u32) -> bool) -> Self {
+        let mut heightmap = Self::new();
+
+        for x in 0..SECTION_SIZE {
+            for z in 0..SECTION_SIZE {
+                heightmap.set(x, z, column_height(sections, x, z, sections.len() * SECTION_SIZE, &counts));
+            }
+        }
+
+        heightmap
+    }
+
+    /// Adjusts the height of a column after the block at `y`, counted from the bottom of the world, changed.
+    pub fn update(&mut self, sections: &[ChunkSection], x: usize, y: usize, z: usize, counts: impl Fn(u32) -> bool) {
+        let height = self.get(x, z) as usize;
+        let state = sections[y / SECTION_SIZE].get_block(x, y % SECTION_SIZE, z);
+
+        if counts(state) && y >= height {
+            self.set(x, z, y as u32 + 1);
+        } else if !counts(state) && y + 1 == height {
+            self.set(x, z, column_height(sections, x, z, y, &counts));
+        }
+    }
+
+    pub fn get(&self, x: usize, z: usize) -> u32 {
+        self.storage.get(z * SECTION_SIZE + x)
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, height: u32) {
+        self.storage.set(z * SECTION_SIZE + x, height.min(WORLD_HEIGHT as u32));
+    }
+
+    pub fn data(&self) -> &[u64] {
+        self.storage.data()
+    }
+}
+
+/// The height of the highest block in the column below `below` that `counts` holds for.
+fn column_height(sections: &[ChunkSection], x: usize, z: usize, below: usize, counts: &impl Fn(u32) -> bool) -> u32 {
+    (0..below).rev()
+        .filter(|y| !sections[y / SECTION_SIZE].is_empty())
+        .find(|y| counts(sections[y / SECTION_SIZE].get_block(x, y % SECTION_SIZE, z)))
+        .map_or(0, |y| y as u32 + 1)
+}
+
+/// The heightmaps the client needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmaps {
+    /// Highest block that blocks motion or holds a fluid.
+    pub motion_blocking: Heightmap,
+    /// Highest block that isn't air.
+    pub world_surface: Heightmap,
+}
+
+impl Heightmaps {
+    pub fn new() -> Self {
+        Self { motion_blocking: Heightmap::new(), world_surface: Heightmap::new() }
+    }
+
+    // Without block properties, every block but air blocks motion
+    pub fn compute(sections: &[ChunkSection]) -> Self {
+        Self {
+            motion_blocking: Heightmap::compute(sections, |state| state != AIR),
+            world_surface: Heightmap::compute(sections, |state| state != AIR),
+        }
+    }
+
+    pub fn update(&mut self, sections: &[ChunkSection], x: usize, y: usize, z: usize) {
+        self.motion_blocking.update(sections, x, y, z, |state| state != AIR);
+        self.world_surface.update(sections, x, y, z, |state| state != AIR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{custom_types::position::Position, world::chunk::{Chunk, ChunkPos}};
+
+    use super::*;
+
+    #[test]
+    fn test_compute() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), 0);
+        chunk.set_block(&Position::new(0, -64, 0), 1);
+        chunk.set_block(&Position::new(0, 70, 0), 1);
+        chunk.set_block(&Position::new(15, 319, 3), 2);
+        chunk.set_block(&Position::new(4, 10, 4), 3);
+
+        let heightmap = Heightmap::compute(chunk.sections(), |state| state != AIR && state != 3);
+        assert_eq!(heightmap.get(0, 0), 135);
+        assert_eq!(heightmap.get(15, 3), 384);
+        assert_eq!(heightmap.get(4, 4), 0);
+        assert_eq!(heightmap.get(1, 0), 0);
+        // 7 heights per long
+        assert_eq!(heightmap.data().len(), 37);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), 0);
+        chunk.set_block(&Position::new(2, -64, 3), 1);
+        chunk.set_block(&Position::new(2, 100, 3), 1);
+        assert_eq!(chunk.heightmaps().world_surface.get(2, 3), 165);
+
+        chunk.set_block(&Position::new(2, 100, 3), AIR);
+        assert_eq!(chunk.heightmaps().world_surface.get(2, 3), 1);
+        chunk.set_block(&Position::new(2, -64, 3), AIR);
+        assert_eq!(chunk.heightmaps().motion_blocking.get(2, 3), 0);
+        assert_eq!(*chunk.heightmaps(), Heightmaps::compute(chunk.sections()));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,166 @@
+use bytes::BufMut;
+
+use crate::{custom_types::bit_set::BitSet, utils::packet_utils::{write_bit_set, write_varint}};
+
+use super::chunk::SECTION_COUNT;
+
+/// Light is also tracked for the sections right below and above the world.
+pub const LIGHT_SECTION_COUNT: usize = SECTION_COUNT + 2;
+/// 4096 levels of 4 bits each.
+pub const LIGHT_ARRAY_SIZE: usize = 2048;
+pub const MAX_LIGHT: u8 = 15;
+
+/// Light levels of one section, indexed like its blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightArray {
+    data: Box<[u8; LIGHT_ARRAY_SIZE]>,
+}
+
+impl LightArray {
+    /// Every block at `level`.
+    pub fn filled(level: u8) -> Self {
+        let level = level.min(MAX_LIGHT);
+        Self { data: Box::new([level | (level << 4); LIGHT_ARRAY_SIZE]) }
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        let index = (y * 16 + z) * 16 + x;
+        (self.data[index / 2] >> ((index % 2) * 4)) & 0x0F
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let index = (y * 16 + z) * 16 + x;
+        let shift = (index % 2) * 4;
+        self.data[index / 2] = (self.data[index / 2] & !(0x0F << shift)) | ((level.min(MAX_LIGHT)) << shift);
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.data.iter().all(|&byte| byte == 0)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[..]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    /// Light coming down from the sky.
+    Sky,
+    /// Light given off by blocks like lava.
+    Block,
+}
+
+/// Sky and block light of a chunk, bottom to top starting with the section below the world.
+/// `None` means the light of that section isn't known, which the client leaves as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightData {
+    pub sky_light: Vec<Option<LightArray>>,
+    pub block_light: Vec<Option<LightArray>>,
+}
+
+impl LightData {
+    /// Light that isn't known for any section.
+    pub fn new() -> Self {
+        Self { sky_light: vec![None; LIGHT_SECTION_COUNT], block_light: vec![None; LIGHT_SECTION_COUNT] }
+    }
+
+    /// Full sky light and no block light everywhere.
+    pub fn full_bright() -> Self {
+        Self {
+            sky_light: vec![Some(LightArray::filled(MAX_LIGHT)); LIGHT_SECTION_COUNT],
+            block_light: vec![Some(LightArray::filled(0)); LIGHT_SECTION_COUNT],
+        }
+    }
+
+    pub fn sections(&self, kind: LightKind) -> &[Option<LightArray>] {
+        match kind {
+            LightKind::Sky => &self.sky_light,
+            LightKind::Block => &self.block_light,
+        }
+    }
+
+    pub fn sections_mut(&mut self, kind: LightKind) -> &mut [Option<LightArray>] {
+        match kind {
+            LightKind::Sky => &mut self.sky_light,
+            LightKind::Block => &mut self.block_light,
+        }
+    }
+
+    /// Writes the light fields shared by Chunk Data and Update Light: the masks of sections with light,
+    /// the masks of completely dark sections, then the arrays for the sections with light.
+    pub fn write(&self, buf: &mut dyn BufMut) {
+        let (sky_mask, empty_sky_mask, sky_arrays) = Self::split(&self.sky_light);
+        let (block_mask, empty_block_mask, block_arrays) = Self::split(&self.block_light);
+
+        for mask in [sky_mask, block_mask, empty_sky_mask, empty_block_mask] {
+            write_bit_set(buf, &mask);
+        }
+
+        for arrays in [sky_arrays, block_arrays] {
+            write_varint(buf, arrays.len() as i32);
+            for array in arrays {
+                write_varint(buf, LIGHT_ARRAY_SIZE as i32);
+                buf.put_slice(array.bytes());
+            }
+        }
+    }
+
+    /// Dark sections only need a bit in the empty mask, not an array.
+    fn split(sections: &[Option<LightArray>]) -> (BitSet, BitSet, Vec<&LightArray>) {
+        let (mut mask, mut empty_mask, mut arrays) = (BitSet::new(), BitSet::new(), Vec::new());
+
+        for (index, section) in sections.iter().enumerate() {
+            match section {
+                Some(array) if array.is_dark() => empty_mask.set(index, true),
+                Some(array) => {
+                    mask.set(index, true);
+                    arrays.push(array);
+                },
+                None => {},
+            }
+        }
+
+        (mask, empty_mask, arrays)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_array() {
+        let mut array = LightArray::filled(0);
+        array.set(1, 0, 0, 7);
+        array.set(2, 0, 0, 20);
+
+        assert_eq!(array.get(1, 0, 0), 7);
+        assert_eq!(array.get(2, 0, 0), 15);
+        assert_eq!(array.get(0, 0, 0), 0);
+        assert_eq!(array.bytes()[0], 0x70);
+        assert_eq!(array.bytes()[1], 0x0F);
+        assert!(!array.is_dark());
+    }
+
+    #[test]
+    fn test_write() {
+        let mut light = LightData::new();
+        light.sky_light[25] = Some(LightArray::filled(15));
+        light.block_light[0] = Some(LightArray::filled(0));
+
+        let mut buf = Vec::new();
+        light.write(&mut buf);
+
+        let mut expected = vec![1];
+        expected.extend_from_slice(&(1u64 << 25).to_be_bytes());
+        // No block light, no dark sky light sections, block light section 0 is dark
+        expected.extend_from_slice(&[0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        // One sky light array of 2048 bytes, no block light arrays
+        expected.extend_from_slice(&[1, 0x80, 0x10]);
+        expected.extend_from_slice(&[0xFF; LIGHT_ARRAY_SIZE]);
+        expected.push(0);
+
+        assert_eq!(buf, expected);
+    }
+}
\ No newline at end of file
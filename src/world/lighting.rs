@@ -0,0 +1,437 @@
+use std::collections::VecDeque;
+
+use once_cell::sync::Lazy;
+
+use super::{
+    chunk::{Chunk, ChunkPos, MIN_Y, WORLD_HEIGHT},
+    chunk_section::{AIR, SECTION_SIZE},
+    light::{LightArray, LightData, LightKind, LIGHT_SECTION_COUNT, MAX_LIGHT},
+    registry,
+};
+
+/// Light is kept from the bottom of the section below the world to the top of the one above it.
+const MIN_LIGHT_Y: i64 = MIN_Y - SECTION_SIZE as i64;
+const MAX_LIGHT_Y: i64 = MIN_LIGHT_Y + (LIGHT_SECTION_COUNT * SECTION_SIZE) as i64;
+
+/// Down first, sky light treats it differently.
+const DIRECTIONS: [(i64, i64, i64); 6] = [(0, -1, 0), (0, 1, 0), (-1, 0, 0), (1, 0, 0), (0, 0, -1), (0, 0, 1)];
+
+/// Blocks light goes through. Every other block stops it, unless it's `TRANSLUCENT`.
+const TRANSPARENT: [&str; 16] = [
+    "minecraft:air", "minecraft:cave_air", "minecraft:void_air", "minecraft:glass",
+    "minecraft:torch", "minecraft:wall_torch", "minecraft:soul_torch", "minecraft:soul_wall_torch",
+    "minecraft:lantern", "minecraft:soul_lantern", "minecraft:fire", "minecraft:soul_fire",
+    "minecraft:short_grass", "minecraft:fern", "minecraft:dandelion", "minecraft:poppy",
+];
+
+/// Blocks light goes through, but that keep sky light from going straight down without getting dimmer.
+/// Leaves are too.
+const TRANSLUCENT: [&str; 4] = ["minecraft:water", "minecraft:lava", "minecraft:ice", "minecraft:frosted_ice"];
+
+/// Blocks that give off light, and how much.
+const LIGHT_SOURCES: [(&str, u8); 16] = [
+    ("minecraft:lava", 15), ("minecraft:fire", 15), ("minecraft:soul_fire", 10), ("minecraft:torch", 14),
+    ("minecraft:wall_torch", 14), ("minecraft:soul_torch", 10), ("minecraft:soul_wall_torch", 10), ("minecraft:lantern", 15),
+    ("minecraft:soul_lantern", 10), ("minecraft:glowstone", 15), ("minecraft:sea_lantern", 15), ("minecraft:jack_o_lantern", 15),
+    ("minecraft:shroomlight", 15), ("minecraft:beacon", 15), ("minecraft:end_rod", 14), ("minecraft:magma_block", 3),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct LightProperties {
+    /// How much dimmer light gets going into the block, on top of the 1 it loses for moving.
+    opacity: u8,
+    emission: u8,
+}
+
+const OPAQUE: LightProperties = LightProperties { opacity: MAX_LIGHT, emission: 0 };
+
+/// The light properties of every known block state by ID. The data reports don't have them, so they go by block name.
+static PROPERTIES: Lazy<Vec<LightProperties>> = Lazy::new(|| {
+    let blocks = registry::blocks();
+    let count = blocks.iter().flat_map(|block| block.states).map(|state| state.id as usize + 1).max().unwrap_or(0);
+    let mut properties = vec![OPAQUE; count];
+
+    for block in blocks {
+        let opacity = if TRANSPARENT.contains(&block.name) {
+            0
+        } else if TRANSLUCENT.contains(&block.name) || block.name.ends_with("_leaves") {
+            1
+        } else {
+            MAX_LIGHT
+        };
+        let emission = LIGHT_SOURCES.iter().find(|(name, _)| *name == block.name).map_or(0, |(_, level)| *level);
+
+        for state in block.states {
+            properties[state.id as usize] = LightProperties { opacity, emission };
+        }
+    }
+
+    properties
+});
+
+/// Unknown block states are opaque.
+fn properties(state: u32) -> LightProperties {
+    PROPERTIES.get(state as usize).copied().unwrap_or(OPAQUE)
+}
+
+/// The chunks light spreads through.
+pub trait LightWorld {
+    /// `None` if the chunk isn't loaded, light doesn't go there.
+    fn chunk(&self, pos: ChunkPos) -> Option<(&Chunk, &LightData)>;
+
+    /// The light of a section of a loaded chunk, counted like in `LightData`. Called for every change,
+    /// so implementations can keep track of what changed.
+    fn section_light_mut(&mut self, pos: ChunkPos, kind: LightKind, section: usize) -> Option<&mut LightArray>;
+}
+
+/// The chunk, the section counted like in `LightData`, and the coordinates within the section.
+fn locate(x: i64, y: i64, z: i64) -> (ChunkPos, usize, usize, usize, usize) {
+    let light_y = (y - MIN_LIGHT_Y) as usize;
+    (ChunkPos::new((x >> 4) as i32, (z >> 4) as i32), light_y / SECTION_SIZE, (x & 15) as usize, light_y % SECTION_SIZE, (z & 15) as usize)
+}
+
+/// `None` where light isn't kept. Above and below the world, it's air.
+fn block(world: &impl LightWorld, x: i64, y: i64, z: i64) -> Option<u32> {
+    if !(MIN_LIGHT_Y..MAX_LIGHT_Y).contains(&y) { return None; }
+
+    let (chunk, _) = world.chunk(ChunkPos::new((x >> 4) as i32, (z >> 4) as i32))?;
+    let y = y - MIN_Y;
+    if !(0..WORLD_HEIGHT).contains(&y) { return Some(AIR); }
+
+    let y = y as usize;
+    Some(chunk.sections()[y / SECTION_SIZE].get_block((x & 15) as usize, y % SECTION_SIZE, (z & 15) as usize))
+}
+
+fn get_light(world: &impl LightWorld, kind: LightKind, x: i64, y: i64, z: i64) -> u8 {
+    let (pos, section, x, y, z) = locate(x, y, z);
+    world.chunk(pos)
+        .and_then(|(_, light)| light.sections(kind)[section].as_ref())
+        .map_or(0, |array| array.get(x, y, z))
+}
+
+fn set_light(world: &mut impl LightWorld, kind: LightKind, x: i64, y: i64, z: i64, level: u8) {
+    let (pos, section, x, y, z) = locate(x, y, z);
+    if let Some(array) = world.section_light_mut(pos, kind, section) {
+        array.set(x, y, z, level);
+    }
+}
+
+/// The light a block at `level` gives its neighbour. Sky light at full strength goes straight down through
+/// transparent blocks without getting dimmer.
+fn propagated(kind: LightKind, level: u8, down: bool, opacity: u8) -> u8 {
+    if kind == LightKind::Sky && down && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Spreads light from the queued blocks at their levels, as far as it gets brighter.
+fn brighten(world: &mut impl LightWorld, kind: LightKind, mut queue: VecDeque<(i64, i64, i64, u8)>) {
+    while let Some((x, y, z, level)) = queue.pop_front() {
+        // Changed since it was queued, whatever changed it spreads it
+        if get_light(world, kind, x, y, z) != level { continue; }
+
+        for (dx, dy, dz) in DIRECTIONS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(state) = block(world, nx, ny, nz) else { continue; };
+
+            let spread = propagated(kind, level, dy < 0, properties(state).opacity);
+            if spread > get_light(world, kind, nx, ny, nz) {
+                set_light(world, kind, nx, ny, nz, spread);
+                queue.push_back((nx, ny, nz, spread));
+            }
+        }
+    }
+}
+
+/// Removes the light of the block and every block that got its light through it. Returns the blocks around the
+/// darkened area that still have light, and light sources in it, which have to spread their light again.
+fn darken(world: &mut impl LightWorld, kind: LightKind, x: i64, y: i64, z: i64) -> VecDeque<(i64, i64, i64, u8)> {
+    let mut removed = VecDeque::from([(x, y, z, get_light(world, kind, x, y, z))]);
+    let mut relight = VecDeque::new();
+    set_light(world, kind, x, y, z, 0);
+
+    while let Some((x, y, z, level)) = removed.pop_front() {
+        for (dx, dy, dz) in DIRECTIONS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(state) = block(world, nx, ny, nz) else { continue; };
+            let neighbour = get_light(world, kind, nx, ny, nz);
+            if neighbour == 0 { continue; }
+
+            let lit_from_here = neighbour < level || (kind == LightKind::Sky && dy < 0 && level == MAX_LIGHT && neighbour == MAX_LIGHT);
+            if !lit_from_here {
+                relight.push_back((nx, ny, nz, neighbour));
+                continue;
+            }
+
+            set_light(world, kind, nx, ny, nz, 0);
+            removed.push_back((nx, ny, nz, neighbour));
+
+            let emission = if kind == LightKind::Block { properties(state).emission } else { 0 };
+            if emission > 0 {
+                set_light(world, kind, nx, ny, nz, emission);
+                relight.push_back((nx, ny, nz, emission));
+            }
+        }
+    }
+
+    relight
+}
+
+/// The light of a chunk by itself, as if there was nothing around it.
+pub fn light_chunk(chunk: &Chunk) -> LightData {
+    struct Alone<'a> {
+        chunk: &'a Chunk,
+        light: LightData,
+    }
+
+    impl LightWorld for Alone<'_> {
+        fn chunk(&self, pos: ChunkPos) -> Option<(&Chunk, &LightData)> {
+            (pos == self.chunk.pos()).then_some((self.chunk, &self.light))
+        }
+
+        fn section_light_mut(&mut self, pos: ChunkPos, kind: LightKind, section: usize) -> Option<&mut LightArray> {
+            if pos != self.chunk.pos() { return None; }
+            Some(self.light.sections_mut(kind)[section].get_or_insert_with(|| LightArray::filled(0)))
+        }
+    }
+
+    let dark = vec![Some(LightArray::filled(0)); LIGHT_SECTION_COUNT];
+    let mut world = Alone { chunk, light: LightData { sky_light: dark.clone(), block_light: dark } };
+    let (base_x, base_z) = (chunk.pos().x as i64 * 16, chunk.pos().z as i64 * 16);
+    let columns = || (base_x..base_x + 16).flat_map(move |x| (base_z..base_z + 16).map(move |z| (x, z)));
+
+    // Sky light goes down each column first, then spreads sideways from where the next column is darker
+    for (x, z) in columns() {
+        let mut level = MAX_LIGHT;
+        for y in (MIN_LIGHT_Y..MAX_LIGHT_Y).rev() {
+            if y < MAX_LIGHT_Y - 1 {
+                level = propagated(LightKind::Sky, level, true, properties(block(&world, x, y, z).unwrap_or(AIR)).opacity);
+            }
+            if level == 0 { break; }
+            set_light(&mut world, LightKind::Sky, x, y, z, level);
+        }
+    }
+
+    let mut sky = VecDeque::new();
+    for (x, z) in columns() {
+        for y in MIN_LIGHT_Y..MAX_LIGHT_Y {
+            let level = get_light(&world, LightKind::Sky, x, y, z);
+            let darker_next = DIRECTIONS[2..].iter()
+                .any(|(dx, _, dz)| block(&world, x + dx, y, z + dz).is_some() && get_light(&world, LightKind::Sky, x + dx, y, z + dz) + 1 < level);
+            if darker_next {
+                sky.push_back((x, y, z, level));
+            }
+        }
+    }
+    brighten(&mut world, LightKind::Sky, sky);
+
+    let mut sources = VecDeque::new();
+    for (index, section) in chunk.sections().iter().enumerate().filter(|(_, section)| !section.is_empty()) {
+        for y in 0..SECTION_SIZE {
+            for (x, z) in columns() {
+                let emission = properties(section.get_block((x & 15) as usize, y, (z & 15) as usize)).emission;
+                if emission > 0 {
+                    let y = MIN_Y + (index * SECTION_SIZE + y) as i64;
+                    set_light(&mut world, LightKind::Block, x, y, z, emission);
+                    sources.push_back((x, y, z, emission));
+                }
+            }
+        }
+    }
+    brighten(&mut world, LightKind::Block, sources);
+
+    world.light
+}
+
+/// Lets light through the borders between the chunk at `pos` and the loaded chunks next to it, both ways.
+/// Called once a chunk lit by `light_chunk` joins the others.
+pub fn spread_across_borders(world: &mut impl LightWorld, pos: ChunkPos) {
+    let (base_x, base_z) = (pos.x as i64 * 16, pos.z as i64 * 16);
+
+    for kind in [LightKind::Sky, LightKind::Block] {
+        let mut queue = VecDeque::new();
+
+        for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            if world.chunk(ChunkPos::new(pos.x + dx as i32, pos.z + dz as i32)).is_none() { continue; }
+
+            for along in 0..16 {
+                // The block on this chunk's edge and the one across the border from it
+                let (x, z) = match (dx, dz) {
+                    (-1, _) => (base_x, base_z + along),
+                    (1, _) => (base_x + 15, base_z + along),
+                    (_, -1) => (base_x + along, base_z),
+                    _ => (base_x + along, base_z + 15),
+                };
+
+                for y in MIN_LIGHT_Y..MAX_LIGHT_Y {
+                    let inside = get_light(world, kind, x, y, z);
+                    let across = get_light(world, kind, x + dx, y, z + dz);
+                    if inside > across + 1 {
+                        queue.push_back((x, y, z, inside));
+                    } else if across > inside + 1 {
+                        queue.push_back((x + dx, y, z + dz, across));
+                    }
+                }
+            }
+        }
+
+        brighten(world, kind, queue);
+    }
+}
+
+/// Updates the light after the block at `x`, `y`, `z` changed, also in the loaded chunks around it.
+pub fn update_block(world: &mut impl LightWorld, x: i64, y: i64, z: i64) {
+    let Some(state) = block(world, x, y, z) else { return; };
+
+    for kind in [LightKind::Sky, LightKind::Block] {
+        let mut relight = darken(world, kind, x, y, z);
+
+        let emission = properties(state).emission;
+        if kind == LightKind::Block && emission > 0 {
+            set_light(world, kind, x, y, z, emission);
+            relight.push_back((x, y, z, emission));
+        }
+
+        brighten(world, kind, relight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{custom_types::position::Position, world::registry::{blocks::{LAVA, STONE, WATER}, PLAINS}};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestWorld {
+        chunks: HashMap<ChunkPos, (Chunk, LightData)>,
+    }
+
+    impl LightWorld for TestWorld {
+        fn chunk(&self, pos: ChunkPos) -> Option<(&Chunk, &LightData)> {
+            self.chunks.get(&pos).map(|(chunk, light)| (chunk, light))
+        }
+
+        fn section_light_mut(&mut self, pos: ChunkPos, kind: LightKind, section: usize) -> Option<&mut LightArray> {
+            self.chunks.get_mut(&pos)?.1.sections_mut(kind)[section].as_mut()
+        }
+    }
+
+    impl TestWorld {
+        fn load(&mut self, chunk: Chunk) {
+            let pos = chunk.pos();
+            let light = light_chunk(&chunk);
+            self.chunks.insert(pos, (chunk, light));
+            spread_across_borders(self, pos);
+        }
+
+        fn set_block(&mut self, x: i64, y: i64, z: i64, state: u32) {
+            let position = Position::new(x, y, z);
+            self.chunks.get_mut(&ChunkPos::of(&position)).unwrap().0.set_block(&position, state);
+            update_block(self, x, y, z);
+        }
+
+        fn light(&self, kind: LightKind, x: i64, y: i64, z: i64) -> u8 {
+            get_light(self, kind, x, y, z)
+        }
+
+        /// The same chunks lit from scratch, which updates should always agree with.
+        fn assert_relit(&self) {
+            let mut relit = TestWorld::default();
+            for (chunk, _) in self.chunks.values() {
+                relit.load(chunk.clone());
+            }
+            for (pos, (_, light)) in &self.chunks {
+                assert!(relit.chunks[pos].1 == *light, "Light of chunk {} differs from lighting it from scratch", pos);
+            }
+        }
+    }
+
+    /// A stone floor with its top at y 60, and a stone roof at y 70 over the blocks in `roofed`.
+    fn chunk(pos: ChunkPos, roofed: impl Fn(i64, i64) -> bool) -> Chunk {
+        let mut chunk = Chunk::new(pos, PLAINS);
+        for x in pos.x as i64 * 16..pos.x as i64 * 16 + 16 {
+            for z in pos.z as i64 * 16..pos.z as i64 * 16 + 16 {
+                chunk.set_block(&Position::new(x, 60, z), STONE);
+                if roofed(x, z) {
+                    chunk.set_block(&Position::new(x, 70, z), STONE);
+                }
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_light_chunk() {
+        let mut chunk = chunk(ChunkPos::new(0, 0), |x, _| x < 8);
+        for (x, y, z) in (10..15).flat_map(|x| (61..64).flat_map(move |y| (10..15).map(move |z| (x, y, z)))) {
+            chunk.set_block(&Position::new(x, y, z), WATER);
+        }
+        chunk.set_block(&Position::new(5, 20, 5), LAVA);
+
+        let mut world = TestWorld::default();
+        world.load(chunk);
+
+        assert_eq!(world.light(LightKind::Sky, 12, 319, 3), 15);
+        assert_eq!(world.light(LightKind::Sky, 12, 335, 3), 15);
+        assert_eq!(world.light(LightKind::Sky, 12, 61, 3), 15);
+        assert_eq!(world.light(LightKind::Sky, 12, 60, 3), 0);
+        assert_eq!(world.light(LightKind::Sky, 12, 59, 3), 0);
+        // Under the roof, sky light comes in from the side
+        assert_eq!(world.light(LightKind::Sky, 7, 65, 3), 14);
+        assert_eq!(world.light(LightKind::Sky, 2, 65, 3), 9);
+        // Water dims it going down, and light from the sides of the pool doesn't reach the middle as bright
+        assert_eq!(world.light(LightKind::Sky, 12, 63, 12), 14);
+        assert_eq!(world.light(LightKind::Sky, 12, 61, 12), 12);
+        assert_eq!(world.light(LightKind::Sky, 11, 61, 12), 13);
+
+        assert_eq!(world.light(LightKind::Block, 5, 20, 5), 15);
+        assert_eq!(world.light(LightKind::Block, 5, 20, 8), 12);
+        assert_eq!(world.light(LightKind::Block, 0, 23, 0), 2);
+        assert_eq!(world.light(LightKind::Block, 5, 65, 5), 0);
+    }
+
+    #[test]
+    fn test_across_borders() {
+        let mut world = TestWorld::default();
+        world.load(chunk(ChunkPos::new(0, 0), |_, _| true));
+        assert_eq!(world.light(LightKind::Sky, 10, 65, 5), 0);
+
+        // Light comes in from the open chunk next to it, and goes on into the next one
+        world.load(chunk(ChunkPos::new(1, 0), |_, _| false));
+        world.load(chunk(ChunkPos::new(-1, 0), |_, _| true));
+        assert_eq!(world.light(LightKind::Sky, 15, 65, 5), 14);
+        assert_eq!(world.light(LightKind::Sky, 10, 65, 5), 9);
+        assert_eq!(world.light(LightKind::Sky, -1, 65, 5), 0);
+        world.assert_relit();
+
+        world.set_block(3, 70, 3, AIR);
+        assert_eq!(world.light(LightKind::Sky, 3, 65, 3), 15);
+        assert_eq!(world.light(LightKind::Sky, -1, 65, 3), 11);
+        world.assert_relit();
+
+        // Walling off the open side leaves only the hole in the roof
+        for y in 61..70 {
+            for z in 0..16 {
+                world.set_block(16, y, z, STONE);
+            }
+        }
+        assert_eq!(world.light(LightKind::Sky, 15, 65, 3), 3);
+        world.assert_relit();
+
+        world.set_block(14, 65, 8, LAVA);
+        assert_eq!(world.light(LightKind::Block, 12, 65, 8), 13);
+        assert_eq!(world.light(LightKind::Block, 16, 65, 8), 0);
+        world.assert_relit();
+
+        world.set_block(3, 70, 3, STONE);
+        world.set_block(14, 65, 8, AIR);
+        assert_eq!(world.light(LightKind::Sky, 3, 65, 3), 0);
+        assert_eq!(world.light(LightKind::Block, 12, 65, 8), 0);
+        world.assert_relit();
+    }
+}
\ No newline at end of file
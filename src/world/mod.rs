@@ -0,0 +1,12 @@
+pub mod anvil;
+pub mod bit_storage;
+pub mod block_entity;
+pub mod chunk;
+pub mod chunk_manager;
+pub mod chunk_section;
+pub mod generation;
+pub mod heightmap;
+pub mod light;
+pub mod lighting;
+pub mod paletted_container;
+pub mod registry;
\ No newline at end of file
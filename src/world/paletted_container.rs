@@ -0,0 +1,280 @@
+use bytes::{Buf, BufMut};
+
+use crate::utils::{errors::PacketReadError, packet_utils::{read_varint, write_varint}};
+
+use super::bit_storage::BitStorage;
+
+/// What a container holds and how many bits each palette kind may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerKind {
+    pub size: usize,
+    pub min_indirect_bits: u8,
+    pub max_indirect_bits: u8,
+    /// Enough bits for every ID in the global registry.
+    pub direct_bits: u8,
+}
+
+/// 16x16x16 block states. 1.21 has 26684 block states, which takes 15 bits.
+pub const BLOCK_STATES: ContainerKind = ContainerKind { size: 4096, min_indirect_bits: 4, max_indirect_bits: 8, direct_bits: 15 };
+/// 4x4x4 biomes. 1.21 has 64 biomes, which takes 6 bits.
+pub const BIOMES: ContainerKind = ContainerKind { size: 64, min_indirect_bits: 1, max_indirect_bits: 3, direct_bits: 6 };
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Palette {
+    /// Every entry has the same value, so there's no data.
+    Single(u32),
+    /// The data holds indices into the list.
+    Indirect(Vec<u32>),
+    /// The data holds the values themselves.
+    Direct,
+}
+
+/// Block states or biomes of a chunk section, stored like the client expects them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalettedContainer {
+    kind: ContainerKind,
+    palette: Palette,
+    storage: Option<BitStorage>,
+}
+
+impl PalettedContainer {
+    /// A container where every entry is `value`.
+    pub fn new(kind: ContainerKind, value: u32) -> Self {
+        Self { kind, palette: Palette::Single(value), storage: None }
+    }
+
+    pub fn kind(&self) -> ContainerKind {
+        self.kind
+    }
+
+    /// Bits per entry as sent to the client. 0 for a single value.
+    pub fn bits(&self) -> u8 {
+        self.storage.as_ref().map_or(0, BitStorage::bits)
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        match (&self.palette, &self.storage) {
+            (Palette::Single(value), _) => *value,
+            (Palette::Indirect(entries), Some(storage)) => entries.get(storage.get(index) as usize).copied().unwrap_or_default(),
+            (_, Some(storage)) => storage.get(index),
+            (_, None) => 0,
+        }
+    }
+
+    /// Returns the value that was there before.
+    pub fn set(&mut self, index: usize, value: u32) -> u32 {
+        let old = self.get(index);
+        if old != value {
+            let id = self.id_for(value);
+            if let Some(storage) = self.storage.as_mut() {
+                storage.set(index, id);
+            }
+        }
+
+        old
+    }
+
+    /// Sets every entry to `value`.
+    pub fn fill(&mut self, value: u32) {
+        self.palette = Palette::Single(value);
+        self.storage = None;
+    }
+
+    /// Counts the entries `predicate` holds for.
+    pub fn count(&self, predicate: impl Fn(u32) -> bool) -> usize {
+        match &self.palette {
+            Palette::Single(value) => if predicate(*value) { self.kind.size } else { 0 },
+            _ => (0..self.kind.size).filter(|&index| predicate(self.get(index))).count(),
+        }
+    }
+
+    /// The ID to store for `value`, adding it to the palette and switching to more bits if needed.
+    fn id_for(&mut self, value: u32) -> u32 {
+        let bits = self.bits();
+        match &mut self.palette {
+            Palette::Single(current) => {
+                // Every entry is 0 in the new storage, which is the current value
+                self.palette = Palette::Indirect(vec![*current, value]);
+                self.storage = Some(BitStorage::new(self.kind.min_indirect_bits, self.kind.size));
+                1
+            },
+            Palette::Indirect(entries) => {
+                if let Some(id) = entries.iter().position(|&entry| entry == value) {
+                    return id as u32;
+                }
+
+                if entries.len() < 1 << bits {
+                    entries.push(value);
+                    return entries.len() as u32 - 1;
+                }
+
+                self.resize(bits + 1);
+                self.id_for(value)
+            },
+            Palette::Direct => value,
+        }
+    }
+
+    /// Repacks the data with `bits` per entry, going direct if that's more than an indirect palette may use.
+    fn resize(&mut self, bits: u8) {
+        let values: Vec<u32> = (0..self.kind.size).map(|index| self.get(index)).collect();
+
+        if bits > self.kind.max_indirect_bits {
+            let mut storage = BitStorage::new(self.kind.direct_bits.max(bits), self.kind.size);
+            for (index, value) in values.into_iter().enumerate() {
+                storage.set(index, value);
+            }
+
+            self.palette = Palette::Direct;
+            self.storage = Some(storage);
+            return;
+        }
+
+        let Some(old) = self.storage.take() else { return; };
+        let mut storage = BitStorage::new(bits, self.kind.size);
+        for index in 0..self.kind.size {
+            storage.set(index, old.get(index));
+        }
+        self.storage = Some(storage);
+    }
+
+    pub fn write(&self, buf: &mut dyn BufMut) {
+        buf.put_u8(self.bits());
+
+        match &self.palette {
+            Palette::Single(value) => write_varint(buf, *value as i32),
+            Palette::Indirect(entries) => {
+                write_varint(buf, entries.len() as i32);
+                for entry in entries {
+                    write_varint(buf, *entry as i32);
+                }
+            },
+            Palette::Direct => {},
+        }
+
+        let data = self.storage.as_ref().map(BitStorage::data).unwrap_or_default();
+        write_varint(buf, data.len() as i32);
+        for long in data {
+            buf.put_u64(*long);
+        }
+    }
+
+    pub fn read(kind: ContainerKind, buf: &mut dyn Buf) -> Result<Self, PacketReadError> {
+        if !buf.has_remaining() { return Err(PacketReadError::BufferUnderflow); }
+        let bits = buf.get_u8();
+
+        let palette = match bits {
+            0 => Palette::Single(read_varint(buf)? as u32),
+            bits if bits <= kind.max_indirect_bits => {
+                let length = read_varint(buf)?;
+                if length < 1 || length as usize > 1 << bits { return Err(PacketReadError::InvalidLength(length)); }
+                Palette::Indirect((0..length).map(|_| read_varint(buf).map(|entry| entry as u32)).collect::<Result<_, _>>()?)
+            },
+            _ => Palette::Direct,
+        };
+
+        let length = read_varint(buf)?;
+        if length < 0 || buf.remaining() < length as usize * 8 { return Err(PacketReadError::InvalidLength(length)); }
+        let data: Vec<u64> = (0..length).map(|_| buf.get_u64()).collect();
+
+        if bits == 0 {
+            return Ok(Self::new(kind, match palette { Palette::Single(value) => value, _ => 0 }));
+        }
+
+        let mut container = Self { kind, palette, storage: Some(BitStorage::from_data(bits, kind.size, data)?) };
+        // Clients only accept a few bit counts, so fewer bits than the minimum get repacked
+        if bits < kind.min_indirect_bits {
+            container.resize(kind.min_indirect_bits);
+        }
+
+        Ok(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(container: &PalettedContainer) -> PalettedContainer {
+        let mut buf = Vec::new();
+        container.write(&mut buf);
+
+        let mut data = &buf[..];
+        let read = PalettedContainer::read(container.kind(), &mut data).unwrap();
+        assert!(data.is_empty());
+        read
+    }
+
+    #[test]
+    fn test_single_value() {
+        let container = PalettedContainer::new(BLOCK_STATES, 1);
+        assert_eq!(container.get(4095), 1);
+        assert_eq!(container.count(|state| state == 1), 4096);
+
+        let mut buf = Vec::new();
+        container.write(&mut buf);
+        assert_eq!(buf, vec![0, 1, 0]);
+        assert_eq!(round_trip(&container), container);
+    }
+
+    #[test]
+    fn test_palette_growth() {
+        let mut container = PalettedContainer::new(BLOCK_STATES, 0);
+
+        assert_eq!(container.set(0, 10), 0);
+        assert_eq!(container.bits(), 4);
+
+        // 16 values fit into 4 bits, the 17th needs 5
+        for value in 1..15 {
+            container.set(value as usize, value + 10);
+        }
+        assert_eq!(container.bits(), 4);
+        container.set(100, 1000);
+        assert_eq!(container.bits(), 5);
+
+        // Past 8 bits the values are stored directly
+        for value in 0..300 {
+            container.set(1000 + value as usize, 2000 + value);
+        }
+        assert_eq!(container.bits(), 15);
+
+        assert_eq!(container.get(0), 10);
+        assert_eq!(container.get(14), 24);
+        assert_eq!(container.get(100), 1000);
+        assert_eq!(container.get(1299), 2299);
+        assert_eq!(container.get(4000), 0);
+        assert_eq!(round_trip(&container), container);
+    }
+
+    #[test]
+    fn test_biomes() {
+        let mut container = PalettedContainer::new(BIOMES, 3);
+        container.set(63, 5);
+        assert_eq!(container.bits(), 1);
+
+        let mut buf = Vec::new();
+        container.write(&mut buf);
+        // 1 bit per entry, palette [3, 5], one long with only the top bit set
+        let mut expected = vec![1, 2, 3, 5, 1];
+        expected.extend_from_slice(&(1u64 << 63).to_be_bytes());
+        assert_eq!(buf, expected);
+
+        for value in 0..8 {
+            container.set(value as usize, value + 20);
+        }
+        assert_eq!(container.bits(), 6);
+        assert_eq!(container.get(63), 5);
+        assert_eq!(round_trip(&container), container);
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        // Says 256 longs follow, but there are none
+        let mut data: &[u8] = &[4, 1, 0, 0x80, 0x02];
+        assert!(PalettedContainer::read(BLOCK_STATES, &mut data).is_err());
+
+        // Palette longer than 4 bits can index
+        let mut data: &[u8] = &[4, 17];
+        assert!(PalettedContainer::read(BLOCK_STATES, &mut data).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+/// Property names and values, like `("axis", "y")`.
+pub type Properties = &'static [(&'static str, &'static str)];
+
+pub struct Block {
+    pub name: &'static str,
+    /// Every property with the values it can take.
+    pub properties: &'static [(&'static str, &'static [&'static str])],
+    pub default_state: u32,
+    pub states: &'static [BlockState],
+}
+
+pub struct BlockState {
+    pub id: u32,
+    pub properties: Properties,
+}
+
+/// Names and protocol IDs of a registry, sorted both ways.
+struct Registry {
+    by_name: &'static [(&'static str, i32)],
+    by_id: &'static [(i32, &'static str)],
+}
+
+impl Registry {
+    fn id(&self, name: &str) -> Option<i32> {
+        self.by_name.binary_search_by_key(&name, |(entry, _)| entry).ok().map(|index| self.by_name[index].1)
+    }
+
+    fn name(&self, id: i32) -> Option<&'static str> {
+        self.by_id.binary_search_by_key(&id, |(entry, _)| *entry).ok().map(|index| self.by_id[index].1)
+    }
+}
+
+// BLOCKS, STATES, ITEMS, ENTITY_TYPES, BLOCK_ENTITY_TYPES, BIOMES, DIMENSION_TYPES and the `blocks`, `items`,
+// `entity_types`, `block_entity_types`, `biomes` and `dimension_types` constants, generated from data/ by build.rs.
+include!(concat!(env!("OUT_DIR"), "/registry.rs"));
+
+/// Blocks the client treats as plain air.
+const AIR_ALIASES: [&str; 2] = ["minecraft:cave_air", "minecraft:void_air"];
+
+/// Stands in for blocks the server doesn't know.
+pub const FALLBACK_BLOCK_STATE: u32 = blocks::STONE;
+
+pub const PLAINS: u32 = biomes::PLAINS as u32;
+
+/// The data pack registries the client needs in Registry Data, with their entries in protocol ID order.
+pub fn synced_registries() -> [(&'static str, Vec<&'static str>); 2] {
+    let entries = |registry: &Registry| registry.by_id.iter().map(|(_, name)| *name).collect();
+    [("minecraft:dimension_type", entries(&DIMENSION_TYPES)), ("minecraft:worldgen/biome", entries(&BIOMES))]
+}
+
+pub fn blocks() -> &'static [Block] {
+    BLOCKS
+}
+
+pub fn block(name: &str) -> Option<&'static Block> {
+    BLOCKS.binary_search_by_key(&name, |block| block.name).ok().map(|index| &BLOCKS[index])
+}
+
+/// The state of block `name` with `properties`, where missing properties keep their default value.
+/// Its default state if no state matches, or `None` for blocks the server doesn't know.
+pub fn block_state_id(name: &str, properties: &HashMap<String, String>) -> Option<u32> {
+    if AIR_ALIASES.contains(&name) { return Some(blocks::AIR); }
+
+    let block = block(name)?;
+    let default = block.states.iter().find(|state| state.id == block.default_state)?;
+    let wanted = |key: &str| properties.get(key).map(String::as_str)
+        .or_else(|| default.properties.iter().find(|(default_key, _)| *default_key == key).map(|(_, value)| *value));
+
+    let state = block.states.iter().find(|state| state.properties.iter().all(|(key, value)| wanted(key) == Some(*value)));
+    Some(state.map_or(block.default_state, |state| state.id))
+}
+
+/// The name and properties of a block state.
+pub fn block_state(id: u32) -> Option<(&'static str, Properties)> {
+    let index = STATES.binary_search_by_key(&id, |(state, _, _)| *state).ok()?;
+    let (_, block, position) = STATES[index];
+    let block = &BLOCKS[block as usize];
+    Some((block.name, block.states[position as usize].properties))
+}
+
+pub fn biome_id(name: &str) -> Option<u32> {
+    BIOMES.id(name).map(|id| id as u32)
+}
+
+pub fn biome_name(id: u32) -> Option<&'static str> {
+    BIOMES.name(i32::try_from(id).ok()?)
+}
+
+pub fn block_entity_type_id(name: &str) -> Option<i32> {
+    BLOCK_ENTITY_TYPES.id(name)
+}
+
+pub fn block_entity_type_name(id: i32) -> Option<&'static str> {
+    BLOCK_ENTITY_TYPES.name(id)
+}
+
+pub fn item_id(name: &str) -> Option<i32> {
+    ITEMS.id(name)
+}
+
+pub fn item_name(id: i32) -> Option<&'static str> {
+    ITEMS.name(id)
+}
+
+pub fn entity_type_id(name: &str) -> Option<i32> {
+    ENTITY_TYPES.id(name)
+}
+
+pub fn entity_type_name(id: i32) -> Option<&'static str> {
+    ENTITY_TYPES.name(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_block_states() {
+        assert_eq!(block_state_id("minecraft:stone", &HashMap::new()), Some(1));
+        assert_eq!(block_state_id("minecraft:grass_block", &properties(&[("snowy", "true")])), Some(8));
+        assert_eq!(block_state_id("minecraft:grass_block", &HashMap::new()), Some(9));
+        assert_eq!(block_state_id("minecraft:oak_log", &properties(&[("axis", "z")])), Some(132));
+        // Missing properties keep their default, values that don't exist give the default state
+        assert_eq!(block_state_id("minecraft:oak_leaves", &properties(&[("distance", "3")])), Some(248));
+        assert_eq!(block_state_id("minecraft:oak_leaves", &properties(&[("distance", "9")])), Some(blocks::OAK_LEAVES));
+        assert_eq!(block_state_id("minecraft:oak_log", &properties(&[("color", "red")])), Some(131));
+        assert_eq!(block_state_id("minecraft:cave_air", &HashMap::new()), Some(0));
+        assert_eq!(block_state_id("minecraft:nonexistent", &HashMap::new()), None);
+
+        assert_eq!(block_state(8), Some(("minecraft:grass_block", &[("snowy", "true")][..])));
+        assert_eq!(block_state(100_000), None);
+
+        let leaves = block("minecraft:oak_leaves").unwrap();
+        assert_eq!(leaves.default_state, 264);
+        for state in leaves.states {
+            let properties = state.properties.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+            assert_eq!(block_state_id(leaves.name, &properties), Some(state.id));
+            assert_eq!(block_state(state.id), Some((leaves.name, state.properties)));
+        }
+    }
+
+    #[test]
+    fn test_biomes_and_block_entities() {
+        assert_eq!(biome_id("minecraft:plains"), Some(PLAINS));
+        assert_eq!(biome_name(PLAINS), Some("minecraft:plains"));
+        assert_eq!(biome_id("minecraft:wooded_badlands"), Some(63));
+
+        assert_eq!(block_entity_type_id("minecraft:chest"), Some(1));
+        assert_eq!(block_entity_type_name(43), Some("minecraft:vault"));
+        assert_eq!(block_entity_type_name(-1), None);
+        assert_eq!(block_entity_type_id("minecraft:vault"), Some(block_entity_types::VAULT));
+    }
+
+    #[test]
+    fn test_synced_registries() {
+        let [(dimension_types, dimensions), (biomes, biome_names)] = synced_registries();
+        assert_eq!(dimension_types, "minecraft:dimension_type");
+        assert_eq!(dimensions[dimension_types::OVERWORLD as usize], "minecraft:overworld");
+        assert_eq!(dimensions.len(), 4);
+        assert_eq!(biomes, "minecraft:worldgen/biome");
+        assert_eq!(biome_names[PLAINS as usize], "minecraft:plains");
+    }
+
+    #[test]
+    fn test_items_and_entity_types() {
+        assert_eq!(item_id("minecraft:stone"), Some(items::STONE));
+        assert_eq!(item_name(0), Some("minecraft:air"));
+        assert_eq!(item_id("minecraft:nonexistent"), None);
+
+        assert_eq!(entity_type_id("minecraft:player"), Some(128));
+        assert_eq!(entity_type_name(entity_types::ZOMBIE), Some("minecraft:zombie"));
+        assert_eq!(entity_type_name(1000), None);
+    }
+}
\ No newline at end of file
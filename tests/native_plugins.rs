@@ -0,0 +1,111 @@
+use std::{env, fs, io::{BufRead, BufReader, Write}, path::PathBuf, process::{Child, ChildStdin, Command, Stdio}, sync::mpsc::{self, Receiver}, thread, time::{Duration, Instant}};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+const CONFIG: &str = r#"
+[server]
+ip = "127.0.0.1"
+port = 0
+max_players = 20
+online_mode = false
+
+[status]
+version_prefix = "Rusty"
+motd = "Plugin test"
+
+[misc]
+log_level = "Info"
+"#;
+
+/// Kills the server if the test fails halfway through.
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    output: Receiver<String>,
+}
+
+impl Server {
+    fn start(directory: &PathBuf) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rustcraft_server"))
+            .current_dir(directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start the server");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() { break; }
+            }
+        });
+
+        Self { child, stdin, output }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.stdin, "{}", line).unwrap();
+    }
+
+    fn wait_for(&self, text: &str) {
+        let deadline = Instant::now() + TIMEOUT;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match self.output.recv_timeout(remaining) {
+                Ok(line) if line.contains(text) => return,
+                Ok(_) => {},
+                Err(_) => break,
+            }
+        }
+
+        panic!("the server never printed '{}'", text);
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Builds the sample plugin the same way as the server under test, and returns its path.
+fn build_sample_plugin() -> PathBuf {
+    // target/<profile>/deps/<this test>
+    let profile_directory = env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+
+    let mut cargo = Command::new(env::var("CARGO").unwrap_or("cargo".to_owned()));
+    cargo.args(["build", "-p", "sample_plugin"]).current_dir(env!("CARGO_MANIFEST_DIR"));
+    if profile_directory.ends_with("release") {
+        cargo.arg("--release");
+    }
+    assert!(cargo.status().unwrap().success(), "failed to build the sample plugin");
+
+    profile_directory.join(format!("{}sample_plugin{}", env::consts::DLL_PREFIX, env::consts::DLL_SUFFIX))
+}
+
+#[test]
+fn test_sample_plugin() {
+    let plugin = build_sample_plugin();
+
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("native_plugins");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("plugins")).unwrap();
+    fs::copy(&plugin, directory.join("plugins").join(plugin.file_name().unwrap())).unwrap();
+    fs::write(directory.join("config.toml"), CONFIG).unwrap();
+
+    let mut server = Server::start(&directory);
+    server.wait_for("Loaded plugin SamplePlugin v0.1.0");
+    server.wait_for("Listening on");
+
+    server.send("hello plugin world");
+    server.wait_for("Hello, Server! You said: plugin world");
+
+    // Scheduled 20 ticks after loading
+    server.wait_for("Sample plugin has been running for a second");
+
+    server.send("stop");
+    server.wait_for("Sample plugin unloaded");
+    server.wait_for("Goodbye!");
+}
\ No newline at end of file